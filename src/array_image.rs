@@ -0,0 +1,195 @@
+//! Image wrapper around ndarrays implementing the image crate traits
+
+use crate::error::{Error, Result};
+use core::marker::PhantomData;
+use image::{GenericImage, GenericImageView, Pixel};
+use ndarray::{ArrayBase, ArrayView3, Axis, Data, DataMut, Ix3, RawData, RawDataClone, s};
+
+/// Wrapper around a `(y, x, z)` ndarray that implements [`GenericImageView`] and [`GenericImage`].
+///
+/// This allows algorithms written against the image crate traits to operate directly
+/// on ndarray data, without converting back to an ImageBuffer.
+///
+/// Wrapping an `ArrayView3` gives a read only image, while wrapping an `ArrayViewMut3`
+/// or an owned `Array3` also allows writing pixels.
+///
+/// This does not copy the data, as it is a reference to the actual data in the array.
+pub struct ArrayImage<P, S>
+where
+    S: RawData,
+{
+    array: ArrayBase<S, Ix3>,
+    pixel: PhantomData<P>,
+}
+
+impl<P, S> ArrayImage<P, S>
+where
+    P: Pixel,
+    S: Data<Elem = P::Subpixel>,
+{
+    /// Wrap the provided array as an image.
+    ///
+    /// Returns [`Error::ChannelMismatch`] if the channel axis does not match the pixel,
+    /// [`Error::NotContiguous`] if the channels of a pixel are not contiguous in memory
+    /// and [`Error::ImageConstructFailed`] if the dimensions do not fit the image crate.
+    pub fn new(array: ArrayBase<S, Ix3>) -> Result<Self> {
+        let (height, width, channels) = array.dim();
+        if channels != P::CHANNEL_COUNT.into() {
+            return Err(Error::ChannelMismatch {
+                expected: P::CHANNEL_COUNT.into(),
+                got: channels,
+            });
+        }
+        if channels > 1 && array.stride_of(Axis(2)) != 1 {
+            return Err(Error::NotContiguous);
+        }
+        if u32::try_from(width).is_err() || u32::try_from(height).is_err() {
+            return Err(Error::ImageConstructFailed);
+        }
+        Ok(Self {
+            array,
+            pixel: PhantomData,
+        })
+    }
+
+    /// Reference the wrapped array as an ArrayView3.
+    pub fn as_ndarray(&self) -> ArrayView3<'_, P::Subpixel> {
+        self.array.view()
+    }
+
+    /// Consume the image and return the wrapped array.
+    pub fn into_inner(self) -> ArrayBase<S, Ix3> {
+        self.array
+    }
+}
+
+impl<P, S> Clone for ArrayImage<P, S>
+where
+    S: RawDataClone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            array: self.array.clone(),
+            pixel: PhantomData,
+        }
+    }
+}
+
+impl<P, S> GenericImageView for ArrayImage<P, S>
+where
+    P: Pixel,
+    S: Data<Elem = P::Subpixel>,
+{
+    type Pixel = P;
+
+    fn dimensions(&self) -> (u32, u32) {
+        let (height, width, _) = self.array.dim();
+        (width as u32, height as u32)
+    }
+
+    fn get_pixel(&self, x: u32, y: u32) -> P {
+        let channels = self.array.slice(s![y as usize, x as usize, ..]);
+        *P::from_slice(channels.to_slice().expect("channels are contiguous"))
+    }
+}
+
+#[allow(deprecated)]
+impl<P, S> GenericImage for ArrayImage<P, S>
+where
+    P: Pixel,
+    S: DataMut<Elem = P::Subpixel>,
+{
+    fn get_pixel_mut(&mut self, x: u32, y: u32) -> &mut P {
+        let channels = self.array.slice_mut(s![y as usize, x as usize, ..]);
+        P::from_slice_mut(channels.into_slice().expect("channels are contiguous"))
+    }
+
+    fn put_pixel(&mut self, x: u32, y: u32, pixel: P) {
+        *self.get_pixel_mut(x, y) = pixel;
+    }
+
+    fn blend_pixel(&mut self, x: u32, y: u32, pixel: P) {
+        self.get_pixel_mut(x, y).blend(&pixel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::ImageArray;
+    use image::{Luma, Rgb, Rgba, Rgba32FImage};
+    use ndarray::Array3;
+
+    #[test]
+    fn test_get_pixel() {
+        let (width, height, channels) = (16, 8, 4);
+        let data = create_test_data(width, height, channels);
+        let test_image = Rgba32FImage::from_vec(16, 8, data).unwrap();
+
+        let image = ArrayImage::<Rgba<f32>, _>::new(test_image.as_ndarray()).unwrap();
+
+        assert_eq!(image.dimensions(), test_image.dimensions());
+        for (x, y, pixel) in test_image.enumerate_pixels() {
+            assert_eq!(image.get_pixel(x, y), *pixel);
+        }
+    }
+
+    #[test]
+    fn test_put_pixel() {
+        let mut test_image = Rgba32FImage::new(16, 8);
+
+        let mut image = ArrayImage::<Rgba<f32>, _>::new(test_image.as_ndarray_mut()).unwrap();
+        image.put_pixel(3, 5, Rgba([1.0, 2.0, 3.0, 4.0]));
+
+        assert_eq!(*test_image.get_pixel(3, 5), Rgba([1.0, 2.0, 3.0, 4.0]));
+        assert_eq!(*test_image.get_pixel(5, 3), Rgba([0.0; 4]));
+    }
+
+    #[test]
+    fn test_copy_from() {
+        let source = Rgba32FImage::from_pixel(4, 4, Rgba([1.0; 4]));
+        let array = Array3::<f32>::zeros((8, 16, 4));
+
+        let mut image = ArrayImage::<Rgba<f32>, _>::new(array).unwrap();
+        image.copy_from(&source, 2, 3).unwrap();
+
+        let array = image.into_inner();
+        assert_eq!(array[[3, 2, 0]], 1.0);
+        assert_eq!(array[[6, 5, 3]], 1.0);
+        assert_eq!(array[[7, 6, 0]], 0.0);
+    }
+
+    #[test]
+    fn test_strided_luma() {
+        let array = Array3::from_shape_fn((8, 16, 1), |(y, x, _)| (y * 16 + x) as u8);
+        let transposed = array.view().permuted_axes([1, 0, 2]);
+
+        let image = ArrayImage::<Luma<u8>, _>::new(transposed).unwrap();
+
+        assert_eq!(image.dimensions(), (8, 16));
+        assert_eq!(image.get_pixel(3, 5), Luma([53]));
+    }
+
+    #[test]
+    fn test_new_errors() {
+        let array = Array3::<f32>::zeros((8, 16, 4));
+
+        let result = ArrayImage::<Rgb<f32>, _>::new(array.view()).err().unwrap();
+        assert_eq!(
+            result,
+            Error::ChannelMismatch {
+                expected: 3,
+                got: 4
+            }
+        );
+
+        let planar = Array3::<f32>::zeros((4, 8, 16)).permuted_axes([1, 2, 0]);
+        let result = ArrayImage::<Rgba<f32>, _>::new(planar).err().unwrap();
+        assert_eq!(result, Error::NotContiguous);
+    }
+
+    fn create_test_data(width: usize, height: usize, channels: usize) -> Vec<f32> {
+        let total_elements = width * height * channels;
+        (0..total_elements).map(|x| (x + 1) as f32).collect()
+    }
+}
@@ -0,0 +1,215 @@
+//! Introspection of the sample types and channel counts image formats can store
+
+use image::{
+    DynamicImage, GrayAlphaImage, GrayImage, ImageBuffer, ImageFormat, Luma, LumaA, Rgb32FImage,
+    RgbImage, Rgba32FImage, RgbaImage,
+};
+use std::io::Cursor;
+use std::sync::{Mutex, PoisonError};
+
+/// Sample type of an array that is written to an image file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SampleType {
+    U8,
+    U16,
+    F32,
+}
+
+impl SampleType {
+    /// All sample types, in order of increasing precision.
+    pub const ALL: [SampleType; 3] = [SampleType::U8, SampleType::U16, SampleType::F32];
+}
+
+/// What an image format can store, with the codecs enabled in the image crate.
+///
+/// Generic tools can use this to pick an output format for an array, instead of trying to
+/// encode it in every format until one succeeds:
+///
+/// ```
+/// use image::ImageFormat;
+/// use image_ndarray::{Capabilities, SampleType};
+///
+/// let png = Capabilities::for_format(ImageFormat::Png);
+/// if png.can_write() {
+///     assert!(png.can_write_array(SampleType::U16, 4));
+/// }
+/// ```
+///
+/// Every format is probed once by encoding a single pixel for every sample type and channel
+/// count, so the result reflects the features of the image crate that are actually
+/// enabled. Arrays with 1 to 4 channels can be written if [`Self::can_write_array`]
+/// returns true for their sample type, for example with [`save_ndarray_u16`] for `u16`.
+///
+/// [`save_ndarray_u16`]: crate::save_ndarray_u16
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    format: ImageFormat,
+    read: bool,
+    writable: [[bool; 4]; 3],
+}
+
+impl Capabilities {
+    /// Probe the capabilities of the format.
+    ///
+    /// Every format is only probed once, later calls return the cached result.
+    pub fn for_format(format: ImageFormat) -> Self {
+        let mut cache = PROBED.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(capabilities) = cache.iter().find(|probed| probed.format == format) {
+            return *capabilities;
+        }
+
+        let mut writable = [[false; 4]; 3];
+        if format.writing_enabled() {
+            for (index, sample) in SampleType::ALL.into_iter().enumerate() {
+                for channels in 1..=4 {
+                    writable[index][channels - 1] = probe(format, sample, channels);
+                }
+            }
+        }
+        let capabilities = Self {
+            format,
+            read: format.reading_enabled(),
+            writable,
+        };
+        cache.push(capabilities);
+        capabilities
+    }
+
+    /// The probed format.
+    pub fn format(&self) -> ImageFormat {
+        self.format
+    }
+
+    /// Whether files of the format can be decoded, for example by [`load_ndarray_u16`].
+    ///
+    /// [`load_ndarray_u16`]: crate::load_ndarray_u16
+    pub fn can_read(&self) -> bool {
+        self.read
+    }
+
+    /// Whether any array can be encoded in the format.
+    pub fn can_write(&self) -> bool {
+        self.writable.iter().flatten().any(|writable| *writable)
+    }
+
+    /// Whether an array of the sample type with `channels` channels can be encoded as is,
+    /// without reducing its depth or dropping channels.
+    pub fn can_write_array(&self, sample: SampleType, channels: usize) -> bool {
+        (1..=4).contains(&channels) && self.writable[sample_index(sample)][channels - 1]
+    }
+
+    /// Sample types that can be encoded with at least one channel count.
+    pub fn sample_types(&self) -> impl Iterator<Item = SampleType> + '_ {
+        SampleType::ALL
+            .into_iter()
+            .filter(|sample| self.writable[sample_index(*sample)].contains(&true))
+    }
+
+    /// Channel counts that can be encoded for the sample type.
+    pub fn channel_counts(&self, sample: SampleType) -> impl Iterator<Item = usize> + '_ {
+        (1..=4).filter(move |channels| self.can_write_array(sample, *channels))
+    }
+
+    /// Whether an alpha channel can be encoded, as 2 or 4 channels of any sample type.
+    pub fn supports_alpha(&self) -> bool {
+        self.writable
+            .iter()
+            .any(|channels| channels[1] || channels[3])
+    }
+
+    /// Largest width and height the format can store, if the format itself limits it below
+    /// the u32 range of the image crate.
+    pub fn max_dimension(&self) -> Option<u32> {
+        match self.format {
+            ImageFormat::Png => Some(i32::MAX as u32),
+            ImageFormat::Jpeg | ImageFormat::Gif | ImageFormat::Tga => Some(u16::MAX.into()),
+            ImageFormat::WebP => Some(16383),
+            ImageFormat::Ico => Some(256),
+            _ => None,
+        }
+    }
+}
+
+/// Capabilities of the formats that were probed so far.
+static PROBED: Mutex<Vec<Capabilities>> = Mutex::new(Vec::new());
+
+/// Position of the sample type in [`SampleType::ALL`].
+fn sample_index(sample: SampleType) -> usize {
+    match sample {
+        SampleType::U8 => 0,
+        SampleType::U16 => 1,
+        SampleType::F32 => 2,
+    }
+}
+
+/// Encode a single pixel of the sample type and channel count in memory.
+fn probe(format: ImageFormat, sample: SampleType, channels: usize) -> bool {
+    let image: DynamicImage = match (sample, channels) {
+        (SampleType::U8, 1) => GrayImage::new(1, 1).into(),
+        (SampleType::U8, 2) => GrayAlphaImage::new(1, 1).into(),
+        (SampleType::U8, 3) => RgbImage::new(1, 1).into(),
+        (SampleType::U8, 4) => RgbaImage::new(1, 1).into(),
+        (SampleType::U16, 1) => ImageBuffer::<Luma<u16>, _>::new(1, 1).into(),
+        (SampleType::U16, 2) => ImageBuffer::<LumaA<u16>, _>::new(1, 1).into(),
+        (SampleType::U16, 3) => DynamicImage::new_rgb16(1, 1),
+        (SampleType::U16, 4) => DynamicImage::new_rgba16(1, 1),
+        (SampleType::F32, 3) => Rgb32FImage::new(1, 1).into(),
+        (SampleType::F32, 4) => Rgba32FImage::new(1, 1).into(),
+        _ => return false,
+    };
+    image.write_to(&mut Cursor::new(Vec::new()), format).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_png() {
+        let capabilities = Capabilities::for_format(ImageFormat::Png);
+
+        assert_eq!(capabilities.format(), ImageFormat::Png);
+        assert!(capabilities.can_read());
+        assert!(capabilities.can_write());
+        assert!(capabilities.supports_alpha());
+        assert_eq!(
+            capabilities.sample_types().collect::<Vec<_>>(),
+            [SampleType::U8, SampleType::U16]
+        );
+        assert_eq!(
+            capabilities
+                .channel_counts(SampleType::U16)
+                .collect::<Vec<_>>(),
+            [1, 2, 3, 4]
+        );
+        assert!(!capabilities.can_write_array(SampleType::F32, 4));
+        assert!(!capabilities.can_write_array(SampleType::U8, 5));
+        assert_eq!(capabilities.max_dimension(), Some(i32::MAX as u32));
+    }
+
+    #[test]
+    fn test_cached() {
+        let capabilities = Capabilities::for_format(ImageFormat::Png);
+
+        assert_eq!(Capabilities::for_format(ImageFormat::Png), capabilities);
+        let cache = PROBED.lock().unwrap();
+        assert_eq!(
+            cache
+                .iter()
+                .filter(|probed| probed.format == ImageFormat::Png)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_disabled_format() {
+        let capabilities = Capabilities::for_format(ImageFormat::Farbfeld);
+
+        assert!(!capabilities.can_read());
+        assert!(!capabilities.can_write());
+        assert!(!capabilities.supports_alpha());
+        assert_eq!(capabilities.sample_types().count(), 0);
+        assert_eq!(capabilities.max_dimension(), None);
+    }
+}
@@ -0,0 +1,390 @@
+//! Adaptation of arrays between differing channel counts
+
+use crate::error::{Error, Result};
+use image::{Luma, LumaA, Pixel, Primitive, Rgb, Rgba};
+use ndarray::{Array3, ArrayView3, ArrayViewMut3, Axis};
+use num_traits::NumCast;
+
+/// Interpretation of the alpha channel when channels are adapted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AlphaMode {
+    /// Color channels are independent of the alpha channel.
+    #[default]
+    Straight,
+    /// Color channels are already multiplied by the alpha channel.
+    ///
+    /// When the alpha channel gets dropped, the color channels are divided by alpha
+    /// so the result holds the straight colors.
+    Premultiplied,
+}
+
+/// Pixel that is known to hold exactly `N` channels at compile time.
+///
+/// Methods bound on this trait verify the channel count statically, so passing a
+/// mismatching `N` fails to compile instead of returning [`Error::ChannelMismatch`].
+///
+/// This trait is sealed and implemented for the pixel types of the image crate.
+///
+/// ```compile_fail
+/// use image::RgbaImage;
+/// use image_ndarray::prelude::*;
+///
+/// let image = RgbaImage::new(16, 8);
+/// let pixels = image.as_pixel_ndarray_const::<3>();
+/// ```
+pub trait ChannelCount<const N: usize>: Pixel + sealed::Sealed {}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+macro_rules! impl_channel_count {
+    ($type:ident, $channels:expr) => {
+        impl<T> sealed::Sealed for $type<T> {}
+        impl<T> ChannelCount<$channels> for $type<T> where $type<T>: Pixel {}
+    };
+}
+
+impl_channel_count!(Luma, 1);
+impl_channel_count!(LumaA, 2);
+impl_channel_count!(Rgb, 3);
+impl_channel_count!(Rgba, 4);
+
+/// Adapt the `(y, x, z)` array to the channel count of the pixel.
+///
+/// Supported adaptations are:
+/// * Adding an opaque alpha channel (1→2, 3→4)
+/// * Dropping the alpha channel (2→1, 4→3)
+/// * Replicating gray into RGB, keeping or adding alpha (1→3, 1→4, 2→3, 2→4)
+/// * Reducing RGB to its Rec. 709 luminance, keeping or adding alpha (3→1, 3→2, 4→1, 4→2)
+///
+/// Channel counts outside of 1 to 4 return [`Error::UnsupportedChannels`].
+pub(crate) fn adapt_channels<P>(
+    array: ArrayView3<'_, P::Subpixel>,
+    alpha: AlphaMode,
+) -> Result<Array3<P::Subpixel>>
+where
+    P: Pixel,
+{
+    let (height, width, source) = array.dim();
+    let target = P::CHANNEL_COUNT.into();
+    let (source_colors, source_alpha) = color_layout(source)?;
+    let (target_colors, target_alpha) = color_layout(target)?;
+
+    let unpremultiply = alpha == AlphaMode::Premultiplied && source_alpha && !target_alpha;
+    let color = |y: usize, x: usize, channel: usize| {
+        let value = array[[y, x, channel]];
+        match unpremultiply {
+            true => divide_alpha(value, array[[y, x, source_colors]]),
+            false => value,
+        }
+    };
+    Ok(Array3::from_shape_fn(
+        (height, width, target),
+        |(y, x, channel)| {
+            if channel == target_colors {
+                return match source_alpha {
+                    true => array[[y, x, source_colors]],
+                    false => P::Subpixel::DEFAULT_MAX_VALUE,
+                };
+            }
+            match source_colors > target_colors {
+                true => luminance(color(y, x, 0), color(y, x, 1), color(y, x, 2)),
+                false => color(y, x, channel.min(source_colors - 1)),
+            }
+        },
+    ))
+}
+
+/// Multiply the color channels of the `(y, x, z)` array by its alpha channel in place.
+///
+/// The last channel is the alpha channel, so both gray with alpha (2 channels) and
+/// RGBA (4 channels) arrays are supported. Arrays without an alpha channel return
+/// [`Error::UnsupportedChannels`].
+pub fn premultiply_alpha<C: Primitive>(array: ArrayViewMut3<'_, C>) -> Result<()> {
+    apply_alpha(array, multiply_alpha)
+}
+
+/// Divide the color channels of the `(y, x, z)` array by its alpha channel in place.
+///
+/// This is the inverse of [`premultiply_alpha`]. Fully transparent pixels become black.
+/// Arrays without an alpha channel return [`Error::UnsupportedChannels`].
+pub fn unpremultiply_alpha<C: Primitive>(array: ArrayViewMut3<'_, C>) -> Result<()> {
+    apply_alpha(array, divide_alpha)
+}
+
+/// Combine every color channel with the alpha channel of its pixel.
+fn apply_alpha<C: Primitive>(
+    mut array: ArrayViewMut3<'_, C>,
+    operation: fn(C, C) -> C,
+) -> Result<()> {
+    let (colors, alpha) = color_layout(array.dim().2)?;
+    if !alpha {
+        return Err(Error::UnsupportedChannels(array.dim().2));
+    }
+    for mut pixel in array.lanes_mut(Axis(2)) {
+        let alpha = pixel[colors];
+        pixel
+            .iter_mut()
+            .take(colors)
+            .for_each(|value| *value = operation(*value, alpha));
+    }
+    Ok(())
+}
+
+/// Split the channel count into the amount of color channels and whether alpha is present.
+fn color_layout(channels: usize) -> Result<(usize, bool)> {
+    match channels {
+        1 => Ok((1, false)),
+        2 => Ok((1, true)),
+        3 => Ok((3, false)),
+        4 => Ok((3, true)),
+        _ => Err(Error::UnsupportedChannels(channels)),
+    }
+}
+
+/// Weigh the RGB values into their Rec. 709 luminance.
+fn luminance<C: Primitive>(red: C, green: C, blue: C) -> C {
+    let [red, green, blue] = [red, green, blue].map(|value| value.to_f64().unwrap_or_default());
+    cast_sample(0.2126 * red + 0.7152 * green + 0.0722 * blue)
+}
+
+/// Convert a straight color value to its premultiplied value.
+fn multiply_alpha<C: Primitive>(value: C, alpha: C) -> C {
+    let max = C::DEFAULT_MAX_VALUE.to_f64().unwrap_or(1.0);
+    match (value.to_f64(), alpha.to_f64()) {
+        (Some(value), Some(alpha)) => cast_sample(value * alpha / max),
+        _ => C::DEFAULT_MIN_VALUE,
+    }
+}
+
+/// Cast the value to the sample type, rounding to the nearest integer for integer samples.
+///
+/// Integer samples saturate at their minimum or maximum and NaN becomes zero, float
+/// samples are not limited.
+fn cast_sample<C: Primitive>(value: f64) -> C {
+    let value = match C::DEFAULT_MAX_VALUE.to_f64() {
+        Some(max) if max > 1.0 => value.round(),
+        _ => value,
+    };
+    NumCast::from(value).unwrap_or(match value {
+        value if value.is_nan() => C::zero(),
+        value if value < 0.0 => C::DEFAULT_MIN_VALUE,
+        _ => C::DEFAULT_MAX_VALUE,
+    })
+}
+
+/// Convert a premultiplied color value back to its straight value.
+fn divide_alpha<C: Primitive>(value: C, alpha: C) -> C {
+    let max = C::DEFAULT_MAX_VALUE.to_f64().unwrap_or(1.0);
+    match (value.to_f64(), alpha.to_f64()) {
+        (Some(value), Some(alpha)) if alpha > 0.0 => cast_sample(value / alpha * max),
+        _ => C::DEFAULT_MIN_VALUE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Luma, LumaA, Rgb, Rgba};
+    use rstest::*;
+
+    #[test]
+    fn test_adapt_to_luma() {
+        assert_eq!(adapt_test_pixel::<Luma<u8>>(2).unwrap(), vec![10]);
+    }
+
+    #[test]
+    fn test_adapt_to_luma_alpha() {
+        assert_eq!(adapt_test_pixel::<LumaA<u8>>(1).unwrap(), vec![10, 255]);
+    }
+
+    #[rstest]
+    #[case(1, vec![10, 10, 10])]
+    #[case(2, vec![10, 10, 10])]
+    #[case(4, vec![10, 20, 30])]
+    fn test_adapt_to_rgb(#[case] source: usize, #[case] expected: Vec<u8>) {
+        assert_eq!(adapt_test_pixel::<Rgb<u8>>(source).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case(1, vec![10, 10, 10, 255])]
+    #[case(2, vec![10, 10, 10, 20])]
+    #[case(3, vec![10, 20, 30, 255])]
+    fn test_adapt_to_rgba(#[case] source: usize, #[case] expected: Vec<u8>) {
+        assert_eq!(adapt_test_pixel::<Rgba<u8>>(source).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case(3, vec![19])]
+    #[case(4, vec![19])]
+    fn test_adapt_to_luma_luminance(#[case] source: usize, #[case] expected: Vec<u8>) {
+        assert_eq!(adapt_test_pixel::<Luma<u8>>(source).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case(3, vec![19, 255])]
+    #[case(4, vec![19, 40])]
+    fn test_adapt_to_luma_alpha_luminance(#[case] source: usize, #[case] expected: Vec<u8>) {
+        assert_eq!(adapt_test_pixel::<LumaA<u8>>(source).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_adapt_unsupported() {
+        assert_eq!(
+            adapt_test_pixel::<Rgba<u8>>(5),
+            Err(Error::UnsupportedChannels(5))
+        );
+        assert_eq!(
+            adapt_test_pixel::<Luma<u8>>(5),
+            Err(Error::UnsupportedChannels(5))
+        );
+    }
+
+    #[test]
+    fn test_adapt_luma_alpha_premultiplied() {
+        let data = vec![0.25, 0.5, 0.5, 0.0];
+        let array = Array3::from_shape_vec((1, 2, 2), data).unwrap();
+
+        let result = adapt_channels::<Luma<f32>>(array.view(), AlphaMode::Premultiplied).unwrap();
+
+        assert_eq!(result.into_raw_vec_and_offset().0, vec![0.5, 0.0]);
+    }
+
+    #[rstest]
+    #[case(vec![100, 128], vec![50, 128])]
+    #[case(vec![100, 200, 50, 0], vec![0, 0, 0, 0])]
+    #[case(vec![100, 200, 50, 255], vec![100, 200, 50, 255])]
+    fn test_premultiply_alpha(#[case] pixel: Vec<u8>, #[case] expected: Vec<u8>) {
+        let mut array = Array3::from_shape_vec((1, 1, pixel.len()), pixel).unwrap();
+
+        premultiply_alpha(array.view_mut()).unwrap();
+
+        assert_eq!(array.into_raw_vec_and_offset().0, expected);
+    }
+
+    #[rstest]
+    #[case(2)]
+    #[case(4)]
+    fn test_premultiply_round_trip(#[case] channels: usize) {
+        let array = Array3::from_shape_fn((4, 8, channels), |(y, x, c)| {
+            (y * 8 + x + c) as f32 / 128.0 + 0.25
+        });
+        let mut result = array.clone();
+
+        premultiply_alpha(result.view_mut()).unwrap();
+        assert_ne!(result, array);
+        unpremultiply_alpha(result.view_mut()).unwrap();
+
+        assert!(
+            result
+                .iter()
+                .zip(array.iter())
+                .all(|(result, expected)| (result - expected).abs() < 1e-6)
+        );
+    }
+
+    #[test]
+    fn test_premultiply_round_trip_u8() {
+        for alpha in 1..=u8::MAX {
+            for value in 0..=u8::MAX {
+                let premultiplied = multiply_alpha(value, alpha);
+                let straight = divide_alpha(premultiplied, alpha);
+                assert_eq!(multiply_alpha(straight, alpha), premultiplied);
+            }
+        }
+        assert_eq!(divide_alpha(50u8, 128), 100);
+    }
+
+    #[rstest]
+    #[case(1)]
+    #[case(2)]
+    #[case(257)]
+    #[case(32768)]
+    #[case(65534)]
+    #[case(65535)]
+    fn test_premultiply_round_trip_u16(#[case] alpha: u16) {
+        for value in 0..=u16::MAX {
+            let premultiplied = multiply_alpha(value, alpha);
+            let straight = divide_alpha(premultiplied, alpha);
+            assert_eq!(multiply_alpha(straight, alpha), premultiplied);
+        }
+    }
+
+    #[test]
+    fn test_unpremultiply_float_above_one() {
+        let mut array = Array3::from_shape_vec((1, 1, 2), vec![1.0f32, 0.5]).unwrap();
+
+        unpremultiply_alpha(array.view_mut()).unwrap();
+
+        assert_eq!(array.into_raw_vec_and_offset().0, vec![2.0, 0.5]);
+    }
+
+    #[test]
+    fn test_unpremultiply_signed_saturates() {
+        let mut array = Array3::from_shape_vec((1, 2, 2), vec![-300i16, 2, 300, 2]).unwrap();
+
+        unpremultiply_alpha(array.view_mut()).unwrap();
+
+        assert_eq!(
+            array.into_raw_vec_and_offset().0,
+            vec![i16::MIN, 2, i16::MAX, 2]
+        );
+    }
+
+    #[rstest]
+    #[case(-1e12, i32::MIN)]
+    #[case(1e12, i32::MAX)]
+    #[case(f64::NAN, 0)]
+    #[case(-2.5, -3)]
+    fn test_cast_sample_signed(#[case] value: f64, #[case] expected: i32) {
+        assert_eq!(cast_sample::<i32>(value), expected);
+    }
+
+    #[rstest]
+    #[case(1)]
+    #[case(3)]
+    #[case(5)]
+    fn test_premultiply_without_alpha(#[case] channels: usize) {
+        let mut array = Array3::<u8>::zeros((2, 2, channels));
+
+        assert_eq!(
+            premultiply_alpha(array.view_mut()),
+            Err(Error::UnsupportedChannels(channels))
+        );
+        assert_eq!(
+            unpremultiply_alpha(array.view_mut()),
+            Err(Error::UnsupportedChannels(channels))
+        );
+    }
+
+    #[test]
+    fn test_adapt_premultiplied() {
+        let data = vec![0.25, 0.5, 0.1, 0.5, 1.0, 1.0, 1.0, 0.0];
+        let array = Array3::from_shape_vec((1, 2, 4), data).unwrap();
+
+        let result = adapt_channels::<Rgb<f32>>(array.view(), AlphaMode::Premultiplied).unwrap();
+
+        assert_eq!(
+            result.into_raw_vec_and_offset().0,
+            vec![0.5, 1.0, 0.2, 0.0, 0.0, 0.0]
+        );
+    }
+
+    /// Adapt an array filled with the first `source` values of `[10, 20, 30, 40]`.
+    fn adapt_test_pixel<P: Pixel<Subpixel = u8>>(source: usize) -> Result<Vec<u8>> {
+        let array = Array3::from_shape_fn((2, 3, source), |(_, _, c)| [10, 20, 30, 40, 50][c]);
+
+        let result = adapt_channels::<P>(array.view(), AlphaMode::Straight)?;
+
+        assert_eq!(result.dim(), (2, 3, P::CHANNEL_COUNT.into()));
+        let first = result.slice(ndarray::s![0, 0, ..]).to_vec();
+        assert!(
+            result
+                .rows()
+                .into_iter()
+                .all(|pixel| pixel.to_vec() == first)
+        );
+        Ok(first)
+    }
+}
@@ -0,0 +1,304 @@
+//! Configurable conversion of `(y, x, z)` arrays and images in a single pass
+
+use crate::channels::AlphaMode;
+use crate::error::{Error, Result};
+use crate::layout::ChannelLayout;
+use crate::normalize::{Clamping, ConvertSample, Rounding, SignedNormalization};
+use crate::parallel;
+use crate::trace::span;
+use crate::traits::{ImageArray, NormalizedFloat};
+use image::{ImageBuffer, Pixel};
+use ndarray::{Array3, ArrayBase, Data, Ix3};
+use num_traits::AsPrimitive;
+
+/// Options for [`ConvertWith::convert_with`].
+///
+/// All options start at their defaults, which convert the samples as plain values
+/// into the same `(y, x, z)` layout. Combine the options as needed:
+///
+/// ```
+/// use image_ndarray::{ChannelLayout, ConvertOptions, Rounding};
+///
+/// let options = ConvertOptions::new()
+///     .layout(ChannelLayout::Chw)
+///     .normalize(true)
+///     .rounding(Rounding::Floor);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConvertOptions {
+    layout: ChannelLayout,
+    normalize: bool,
+    rounding: Rounding,
+    clamping: Clamping,
+    signed: SignedNormalization,
+    alpha: AlphaMode,
+}
+
+impl ConvertOptions {
+    /// Create the options with all defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the order of the axes of the result.
+    pub fn layout(mut self, layout: ChannelLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Set whether the samples are normalized to the range of the target type.
+    ///
+    /// When enabled, the maximum value of an integer source becomes 1.0 in a float target
+    /// and 1.0 of a float source becomes the maximum value of an integer target.
+    pub fn normalize(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    /// Set the rounding applied when the target is an integer type.
+    pub fn rounding(mut self, rounding: Rounding) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// Set the handling of values outside of the range of an integer target.
+    pub fn clamping(mut self, clamping: Clamping) -> Self {
+        self.clamping = clamping;
+        self
+    }
+
+    /// Set how signed integer samples map onto the normalized range.
+    ///
+    /// This applies to both the source and the target and only when normalizing.
+    pub fn signed(mut self, signed: SignedNormalization) -> Self {
+        self.signed = signed;
+        self
+    }
+
+    /// Set the alpha representation of the result.
+    ///
+    /// With [`AlphaMode::Premultiplied`] the color channels are multiplied by the alpha
+    /// channel, which is the last channel of 2 and 4 channel arrays.
+    pub fn alpha(mut self, alpha: AlphaMode) -> Self {
+        self.alpha = alpha;
+        self
+    }
+}
+
+/// Conversion of the samples into another type, layout and alpha representation.
+pub trait ConvertWith<ImageContainer> {
+    /// Convert into a new array of `T` samples as described by the options.
+    ///
+    /// The source is always read as `(y, x, z)` and the result is written in a single pass,
+    /// which runs in parallel with the `rayon` feature.
+    ///
+    /// Returns [`Error::UnsupportedChannels`] if premultiplied alpha is requested for an array
+    /// without alpha channel and [`Error::OutOfRange`] with the `[y, x, z]` index of the
+    /// first sample that is rejected by [`Clamping::Error`].
+    fn convert_with<T: ConvertSample>(&self, options: &ConvertOptions) -> Result<Array3<T>>;
+}
+
+impl<S, C> ConvertWith<C> for ArrayBase<S, Ix3>
+where
+    S: Data<Elem = C>,
+    C: NormalizedFloat<C> + AsPrimitive<f32> + AsPrimitive<f64> + Sync,
+{
+    fn convert_with<T: ConvertSample>(&self, options: &ConvertOptions) -> Result<Array3<T>> {
+        span!(
+            DEBUG,
+            "convert_with",
+            shape = ?self.dim(),
+            source = core::any::type_name::<C>(),
+            dtype = core::any::type_name::<T>(),
+        );
+        let (height, width, channels) = self.dim();
+        let alpha = match (options.alpha, channels) {
+            (AlphaMode::Straight, _) => None,
+            (AlphaMode::Premultiplied, 2 | 4) => Some(channels - 1),
+            (AlphaMode::Premultiplied, _) => return Err(Error::UnsupportedChannels(channels)),
+        };
+
+        let source = self.view();
+        let convert = |y: usize, x: usize, z: usize| {
+            let sample = source[[y, x, z]];
+            let mut value = match options.normalize {
+                true => sample
+                    .to_f64_normalized_signed(options.signed)
+                    .unwrap_or(f64::NAN),
+                false => sample.as_(),
+            };
+            if let Some(alpha) = alpha.filter(|alpha| *alpha != z) {
+                value *= source[[y, x, alpha]]
+                    .to_f64_normalized()
+                    .unwrap_or(f64::NAN);
+            }
+            match options.normalize {
+                true => {
+                    T::from_f64_signed(value, options.signed, options.rounding, options.clamping)
+                }
+                false => T::from_f64_with(value, false, options.rounding, options.clamping),
+            }
+        };
+        let shape = match options.layout {
+            ChannelLayout::Hwc => (height, width, channels),
+            ChannelLayout::Chw => (channels, height, width),
+        };
+        let source_index = |(a, b, c): (usize, usize, usize)| match options.layout {
+            ChannelLayout::Hwc => [a, b, c],
+            ChannelLayout::Chw => [b, c, a],
+        };
+        let convert_index = |index| {
+            let [y, x, z] = source_index(index);
+            convert(y, x, z)
+        };
+        if options.clamping != Clamping::Error {
+            return Ok(parallel::from_shape_fn(shape, |index| {
+                convert_index(index).expect("only Clamping::Error rejects a value")
+            }));
+        }
+
+        let converted = parallel::from_shape_fn(shape, convert_index);
+        let rejected = converted
+            .indexed_iter()
+            .filter(|(_, value)| value.is_none())
+            .map(|(index, _)| source_index(index))
+            .min();
+        match rejected {
+            Some(index) => Err(Error::OutOfRange(index)),
+            None => Ok(converted.mapv(|value| value.expect("no value was rejected"))),
+        }
+    }
+}
+
+impl<P, C> ConvertWith<C> for ImageBuffer<P, Vec<C>>
+where
+    P: Pixel<Subpixel = C>,
+    C: NormalizedFloat<C> + AsPrimitive<f32> + AsPrimitive<f64> + Sync,
+{
+    fn convert_with<T: ConvertSample>(&self, options: &ConvertOptions) -> Result<Array3<T>> {
+        self.as_ndarray().convert_with(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage, Rgba, Rgba32FImage};
+    use rstest::*;
+
+    #[test]
+    fn test_convert_default() {
+        let array = Array3::from_shape_fn((4, 8, 3), |(y, x, z)| (y * 24 + x * 3 + z) as u8);
+
+        let result = array.convert_with::<u16>(&ConvertOptions::new()).unwrap();
+
+        assert_eq!(result, array.mapv(u16::from));
+    }
+
+    #[test]
+    fn test_convert_normalized_chw() {
+        let test_image = RgbImage::from_fn(8, 4, |x, y| Rgb([x as u8, y as u8, 255]));
+        let options = ConvertOptions::new()
+            .layout(ChannelLayout::Chw)
+            .normalize(true);
+
+        let result = test_image.convert_with::<f32>(&options).unwrap();
+
+        assert_eq!(result.dim(), (3, 4, 8));
+        assert_eq!(result[[0, 2, 5]], 5.0 / 255.0);
+        assert_eq!(result[[1, 2, 5]], 2.0 / 255.0);
+        assert_eq!(result[[2, 2, 5]], 1.0);
+    }
+
+    #[rstest]
+    #[case(Rounding::Nearest, 128)]
+    #[case(Rounding::Floor, 127)]
+    fn test_convert_rounding(#[case] rounding: Rounding, #[case] expected: u8) {
+        let array = Array3::from_elem((2, 2, 1), 0.5f32);
+        let options = ConvertOptions::new().normalize(true).rounding(rounding);
+
+        let result = array.convert_with::<u8>(&options).unwrap();
+
+        assert!(result.iter().all(|value| *value == expected));
+    }
+
+    #[rstest]
+    #[case(ChannelLayout::Hwc)]
+    #[case(ChannelLayout::Chw)]
+    fn test_convert_clamping_error(#[case] layout: ChannelLayout) {
+        let mut array = Array3::from_elem((4, 3, 2), 0.5f32);
+        array[[2, 1, 1]] = 1.5;
+        array[[3, 0, 0]] = -0.5;
+        let options = ConvertOptions::new()
+            .normalize(true)
+            .layout(layout)
+            .clamping(Clamping::Error);
+
+        let result = array.convert_with::<u16>(&options).err();
+        assert_eq!(result, Some(Error::OutOfRange([2, 1, 1])));
+
+        array[[2, 1, 1]] = 1.0;
+        array[[3, 0, 0]] = 0.0;
+        let result = array.convert_with::<u16>(&options).unwrap();
+        assert_eq!(result[[0, 0, 0]], 32768);
+        assert_eq!(result.iter().filter(|value| **value == 65535).count(), 1);
+
+        let result = array.convert_with::<f32>(&options.normalize(false));
+        assert!(result.is_ok());
+    }
+
+    #[rstest]
+    #[case(Clamping::Clamp, 255)]
+    #[case(Clamping::Wrap, 44)]
+    fn test_convert_clamping(#[case] clamping: Clamping, #[case] expected: u8) {
+        let array = Array3::from_elem((2, 2, 1), 300.0f64);
+        let options = ConvertOptions::new().clamping(clamping);
+
+        let result = array.convert_with::<u8>(&options).unwrap();
+
+        assert!(result.iter().all(|value| *value == expected));
+    }
+
+    #[rstest]
+    #[case(SignedNormalization::Legacy, [-128.0 / 127.0, 0.0, 1.0], -128)]
+    #[case(SignedNormalization::Symmetric, [-1.0, 0.0, 1.0], -127)]
+    #[case(SignedNormalization::Offset, [0.0, 128.0 / 255.0, 1.0], -128)]
+    fn test_convert_signed(
+        #[case] signed: SignedNormalization,
+        #[case] expected: [f64; 3],
+        #[case] expected_min: i8,
+    ) {
+        let array = Array3::from_shape_vec((1, 1, 3), vec![i8::MIN, 0, i8::MAX]).unwrap();
+        let options = ConvertOptions::new().normalize(true).signed(signed);
+
+        let normalized = array.convert_with::<f64>(&options).unwrap();
+        let result = normalized.convert_with::<i8>(&options).unwrap();
+
+        assert_eq!(normalized.into_raw_vec_and_offset().0, expected);
+        assert_eq!(result.into_raw_vec_and_offset().0, [expected_min, 0, 127]);
+    }
+
+    #[test]
+    fn test_convert_premultiplied() {
+        let test_image = Rgba32FImage::from_pixel(4, 2, Rgba([0.5, 1.0, 0.25, 0.5]));
+        let options = ConvertOptions::new()
+            .normalize(true)
+            .alpha(AlphaMode::Premultiplied);
+
+        let result = test_image.convert_with::<u8>(&options).unwrap();
+
+        for pixel in result.lanes(ndarray::Axis(2)) {
+            assert_eq!(pixel.to_vec(), [64, 128, 32, 128]);
+        }
+    }
+
+    #[test]
+    fn test_convert_premultiplied_without_alpha() {
+        let array = Array3::<u8>::zeros((2, 2, 3));
+        let options = ConvertOptions::new().alpha(AlphaMode::Premultiplied);
+
+        let result = array.convert_with::<f32>(&options).err();
+
+        assert_eq!(result, Some(Error::UnsupportedChannels(3)));
+    }
+}
@@ -0,0 +1,336 @@
+//! Scaling of samples between bit depths
+
+use crate::error::{Error, Result};
+use crate::normalize::{Clamping, ConvertSample, Rounding};
+use crate::parallel;
+use crate::trace::span;
+use crate::traits::NormalizedFloat;
+use ndarray::{Array1, Array3, ArrayView3, Axis};
+use num_traits::AsPrimitive;
+
+/// Placement of samples with a lower bit depth within a 16 bit word.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Justification {
+    /// The sample occupies the lowest bits, so 12 bit white is 4095.
+    #[default]
+    Right,
+    /// The sample occupies the highest bits, so 12 bit white is 65520.
+    ///
+    /// The unused lower bits are ignored.
+    Left,
+}
+
+impl Justification {
+    /// Amount of bits the sample is shifted up within the word.
+    fn shift(self, depth: u8) -> u32 {
+        match self {
+            Self::Right => 0,
+            Self::Left => u32::from(16 - depth),
+        }
+    }
+}
+
+/// Scale the samples of the `(y, x, z)` array to the range of another sample type.
+///
+/// The maximum of the source maps onto the maximum of the target, so a `u8` of 255
+/// becomes a `u16` of 65535 and an `f32` of 1.0. Integer targets are rounded to the
+/// nearest value and clamped to their range. Samples that can not be represented
+/// become zero.
+///
+/// With the `rayon` feature, the samples are scaled in parallel.
+pub fn scale_depth<S, T>(array: ArrayView3<'_, S>) -> Array3<T>
+where
+    S: NormalizedFloat<S> + AsPrimitive<f32> + AsPrimitive<f64> + Sync,
+    T: ConvertSample,
+{
+    span!(
+        DEBUG,
+        "scale_depth",
+        shape = ?array.dim(),
+        source = core::any::type_name::<S>(),
+        dtype = core::any::type_name::<T>(),
+    );
+    parallel::map(array, |value| {
+        let value = value.to_f64_normalized().unwrap_or_default();
+        T::from_f64_with(value, true, Rounding::Nearest, Clamping::Clamp)
+            .expect("clamping never rejects a value")
+    })
+}
+
+/// Scale 8 bit samples to 16 bits by multiplying with 257.
+///
+/// Unlike a shift, this maps 255 onto 65535, so white stays white.
+pub fn scale_u8_to_u16(array: ArrayView3<'_, u8>) -> Array3<u16> {
+    array.mapv(|value| u16::from(value) * 257)
+}
+
+/// Scale 16 bit samples to 8 bits by dividing by 257, rounded to the nearest value.
+///
+/// This is the exact inverse of [`scale_u8_to_u16`].
+pub fn scale_u16_to_u8(array: ArrayView3<'_, u16>) -> Array3<u8> {
+    array.mapv(|value| ((u32::from(value) + 128) / 257) as u8)
+}
+
+/// Detect the amount of bits the 16 bit samples of the array actually occupy.
+///
+/// Scanners and cameras often store 10, 12 or 14 bit data in 16 bit files, leaving the
+/// upper bits unused. The depth is derived from the largest sample, so an array with a
+/// maximum of 4095 returns 12. The result is at least 1, even when all samples are zero.
+pub fn detect_effective_depth(array: ArrayView3<'_, u16>) -> u8 {
+    sample_depth(array.iter().copied().max().unwrap_or_default())
+}
+
+/// Detect the effective depth of every channel of the `(y, x, z)` array separately.
+///
+/// See [`detect_effective_depth`] for how the depth is derived.
+pub fn detect_channel_depths(array: ArrayView3<'_, u16>) -> Array1<u8> {
+    array
+        .fold_axis(Axis(0), 0, |max, value| *max.max(value))
+        .fold_axis(Axis(0), 0, |max, value| *max.max(value))
+        .mapv(sample_depth)
+}
+
+/// Scale samples that occupy `depth` bits up to the full 16 bit range.
+///
+/// The maximum of the depth maps onto 65535, for example 4095 of 12 bit data. Samples
+/// above the maximum of the depth saturate.
+///
+/// Returns [`Error::InvalidDepth`] if the depth is not between 1 and 16.
+pub fn scale_from_depth(array: ArrayView3<'_, u16>, depth: u8) -> Result<Array3<u16>> {
+    let max = depth_max(depth)?;
+    Ok(array.mapv(|value| rescale(u32::from(value).min(max), max, u32::from(u16::MAX))))
+}
+
+/// Scale full range 16 bit samples down so they occupy `depth` bits.
+///
+/// This is the inverse of [`scale_from_depth`].
+///
+/// Returns [`Error::InvalidDepth`] if the depth is not between 1 and 16.
+pub fn scale_to_depth(array: ArrayView3<'_, u16>, depth: u8) -> Result<Array3<u16>> {
+    let max = depth_max(depth)?;
+    Ok(array.mapv(|value| rescale(u32::from(value), u32::from(u16::MAX), max)))
+}
+
+/// Normalize samples that occupy `depth` bits of a 16 bit word to floats.
+///
+/// The maximum of the depth maps onto 1.0 instead of 65535, so 12 bit white becomes 1.0
+/// for both a right justified 4095 and a left justified 65520. Right justified samples
+/// above the maximum of the depth saturate.
+///
+/// Returns [`Error::InvalidDepth`] if the depth is not between 1 and 16.
+pub fn normalize_depth(
+    array: ArrayView3<'_, u16>,
+    depth: u8,
+    justification: Justification,
+) -> Result<Array3<f32>> {
+    let max = depth_max(depth)?;
+    let shift = justification.shift(depth);
+    Ok(array.mapv(|value| (u32::from(value) >> shift).min(max) as f32 / max as f32))
+}
+
+/// Convert normalized floats into samples that occupy `depth` bits of a 16 bit word.
+///
+/// This is the inverse of [`normalize_depth`]. Values are rounded to the nearest value and
+/// clamped to the range of the depth, NaN becomes zero.
+///
+/// Returns [`Error::InvalidDepth`] if the depth is not between 1 and 16.
+pub fn denormalize_depth(
+    array: ArrayView3<'_, f32>,
+    depth: u8,
+    justification: Justification,
+) -> Result<Array3<u16>> {
+    let max = depth_max(depth)?;
+    let shift = justification.shift(depth);
+    Ok(array.mapv(|value| {
+        let scaled = (value.clamp(0.0, 1.0) * max as f32 + 0.5) as u32;
+        (scaled.min(max) << shift) as u16
+    }))
+}
+
+/// Amount of bits needed to store the sample, at least 1.
+fn sample_depth(value: u16) -> u8 {
+    (u16::BITS - value.leading_zeros()).max(1) as u8
+}
+
+/// Largest value that fits in `depth` bits.
+fn depth_max(depth: u8) -> Result<u32> {
+    match depth {
+        1..=16 => Ok((1 << depth) - 1),
+        _ => Err(Error::InvalidDepth),
+    }
+}
+
+/// Scale the value from the range `0..=source` onto `0..=target`, rounded to the nearest.
+fn rescale(value: u32, source: u32, target: u32) -> u16 {
+    ((u64::from(value) * u64::from(target) + u64::from(source / 2)) / u64::from(source)) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case(0, 0)]
+    #[case(1, 257)]
+    #[case(128, 32896)]
+    #[case(255, 65535)]
+    fn test_scale_u8_to_u16(#[case] value: u8, #[case] expected: u16) {
+        let array = Array3::from_elem((2, 3, 1), value);
+
+        assert_eq!(
+            scale_u8_to_u16(array.view()),
+            Array3::from_elem((2, 3, 1), expected)
+        );
+        assert_eq!(
+            scale_depth::<u8, u16>(array.view()),
+            Array3::from_elem((2, 3, 1), expected)
+        );
+    }
+
+    #[test]
+    fn test_scale_u16_to_u8() {
+        let array = Array3::from_shape_fn((256, 256, 1), |(y, x, _)| (y * 256 + x) as u16);
+
+        let result = scale_u16_to_u8(array.view());
+
+        assert_eq!(result, scale_depth::<u16, u8>(array.view()));
+        assert_eq!(result[[0, 128, 0]], 0);
+        assert_eq!(result[[0, 129, 0]], 1);
+        assert_eq!(result[[255, 255, 0]], 255);
+    }
+
+    #[test]
+    fn test_scale_round_trip() {
+        let array = Array3::from_shape_fn((16, 16, 1), |(y, x, _)| (y * 16 + x) as u8);
+
+        assert_eq!(scale_u16_to_u8(scale_u8_to_u16(array.view()).view()), array);
+    }
+
+    #[rstest]
+    #[case(0, 1)]
+    #[case(1, 1)]
+    #[case(255, 8)]
+    #[case(1023, 10)]
+    #[case(4095, 12)]
+    #[case(4096, 13)]
+    #[case(u16::MAX, 16)]
+    fn test_detect_effective_depth(#[case] max: u16, #[case] expected: u8) {
+        let mut array = Array3::<u16>::zeros((4, 8, 3));
+        array[[2, 5, 1]] = max;
+
+        assert_eq!(detect_effective_depth(array.view()), expected);
+    }
+
+    #[test]
+    fn test_detect_channel_depths() {
+        let array = Array3::from_shape_fn((4, 8, 3), |(y, x, c)| match c {
+            0 => 255,
+            1 => (y * 8 + x) as u16,
+            _ => 0,
+        });
+
+        let result = detect_channel_depths(array.view());
+
+        assert_eq!(result.to_vec(), [8, 5, 1]);
+    }
+
+    #[test]
+    fn test_scale_depth_round_trip() {
+        let array = Array3::from_shape_fn((64, 64, 1), |(y, x, _)| (y * 64 + x) as u16);
+
+        let result = scale_from_depth(array.view(), 12).unwrap();
+        assert_eq!(result[[63, 63, 0]], u16::MAX);
+        assert_eq!(result[[0, 1, 0]], 16);
+        assert_eq!(scale_to_depth(result.view(), 12).unwrap(), array);
+
+        let result = scale_from_depth(array.view(), 8).unwrap();
+        assert_eq!(result[[3, 63, 0]], u16::MAX);
+        assert_eq!(result[[63, 63, 0]], u16::MAX);
+    }
+
+    #[rstest]
+    #[case(0)]
+    #[case(17)]
+    fn test_scale_invalid_depth(#[case] depth: u8) {
+        let array = Array3::<u16>::zeros((2, 2, 1));
+
+        assert_eq!(
+            scale_from_depth(array.view(), depth),
+            Err(Error::InvalidDepth)
+        );
+        assert_eq!(
+            scale_to_depth(array.view(), depth),
+            Err(Error::InvalidDepth)
+        );
+    }
+
+    #[rstest]
+    #[case(Justification::Right, 4095, 1.0)]
+    #[case(Justification::Right, 2048, 2048.0 / 4095.0)]
+    #[case(Justification::Right, 5000, 1.0)]
+    #[case(Justification::Left, 65520, 1.0)]
+    #[case(Justification::Left, 65535, 1.0)]
+    #[case(Justification::Left, 16, 1.0 / 4095.0)]
+    fn test_normalize_depth(
+        #[case] justification: Justification,
+        #[case] value: u16,
+        #[case] expected: f32,
+    ) {
+        let array = Array3::from_elem((2, 2, 1), value);
+
+        let result = normalize_depth(array.view(), 12, justification).unwrap();
+
+        assert_eq!(result, Array3::from_elem((2, 2, 1), expected));
+    }
+
+    #[rstest]
+    #[case(10, Justification::Right)]
+    #[case(12, Justification::Left)]
+    #[case(14, Justification::Right)]
+    #[case(16, Justification::Left)]
+    fn test_depth_round_trip(#[case] depth: u8, #[case] justification: Justification) {
+        let shift = justification.shift(depth);
+        let array = Array3::from_shape_fn((64, 64, 1), |(y, x, _)| {
+            (((y * 64 + x) % (1 << depth)) << shift) as u16
+        });
+
+        let normalized = normalize_depth(array.view(), depth, justification).unwrap();
+        let result = denormalize_depth(normalized.view(), depth, justification).unwrap();
+
+        assert_eq!(result, array);
+    }
+
+    #[test]
+    fn test_denormalize_depth_clamps() {
+        let array = Array3::from_shape_fn((2, 2, 1), |(y, x, _)| match y * 2 + x {
+            0 => -0.5,
+            1 => f32::NAN,
+            2 => 1.5,
+            _ => 0.5,
+        });
+
+        let result = denormalize_depth(array.view(), 10, Justification::Left).unwrap();
+
+        assert_eq!(result.as_slice().unwrap(), [0, 0, 65472, 512 << 6]);
+        assert_eq!(
+            normalize_depth(Array3::zeros((1, 1, 1)).view(), 0, Justification::Right),
+            Err(Error::InvalidDepth)
+        );
+        assert_eq!(
+            denormalize_depth(array.view(), 17, Justification::Left),
+            Err(Error::InvalidDepth)
+        );
+    }
+
+    #[test]
+    fn test_scale_through_f32() {
+        let array = Array3::from_shape_fn((16, 16, 1), |(y, x, _)| (y * 16 + x) as u8);
+
+        let float = scale_depth::<u8, f32>(array.view());
+        assert_eq!(float[[15, 15, 0]], 1.0);
+
+        let result = scale_depth::<f32, u16>(float.view());
+        assert_eq!(result, scale_u8_to_u16(array.view()));
+    }
+}
@@ -0,0 +1,166 @@
+//! Dithering of normalized floats while quantizing them into integers
+
+use crate::normalize::{Clamping, ConvertSample, Rounding};
+use crate::paranoid::paranoid;
+use crate::trace::span;
+use ndarray::{Array3, ArrayView3, Zip};
+use num_traits::{AsPrimitive, PrimInt};
+
+/// Dithering applied when normalized floats are quantized into integers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Dither {
+    /// Round every value to the nearest integer on its own.
+    #[default]
+    None,
+    /// Add the threshold of an 8x8 Bayer matrix before rounding.
+    ///
+    /// Every pixel is quantized independently, so the pattern is stable between frames.
+    Ordered,
+    /// Diffuse the rounding error of every value onto its unvisited neighbours with the
+    /// Floyd-Steinberg weights.
+    ///
+    /// The error is spread per channel, in row order, so this runs serially.
+    FloydSteinberg,
+}
+
+/// Bayer matrix of 8x8, holding the order in which the thresholds are reached.
+const BAYER: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+/// Quantize the normalized `(y, x, z)` f32 array into integers of `T`, dithering the result.
+///
+/// Like [`NormalizedFloat::from_f32_normalized`](crate::prelude::NormalizedFloat::from_f32_normalized),
+/// 1.0 becomes the maximum value of `T`. Dithering trades the banding of smooth gradients
+/// for fine noise, which matters most when going down to 8 bits. Values are clamped to the
+/// range of `T` and NaN becomes zero.
+pub fn quantize_dithered<T>(array: ArrayView3<'_, f32>, dither: Dither) -> Array3<T>
+where
+    T: ConvertSample + PrimInt + AsPrimitive<f64>,
+{
+    span!(DEBUG, "quantize_dithered", shape = ?array.dim(), dtype = core::any::type_name::<T>(), ?dither);
+    paranoid! {
+        crate::paranoid::check_normalized_range(&array);
+    }
+    let max: f64 = T::max_value().as_();
+    match dither {
+        Dither::None => array.mapv(|value| quantize(f64::from(value) * max)),
+        Dither::Ordered => Zip::indexed(array).map_collect(|(y, x, _), value| {
+            let threshold = (f64::from(BAYER[y % 8][x % 8]) + 0.5) / 64.0 - 0.5;
+            quantize(f64::from(*value) * max + threshold)
+        }),
+        Dither::FloydSteinberg => floyd_steinberg(array, max),
+    }
+}
+
+/// Diffuse the rounding errors of the scaled values with the Floyd-Steinberg weights.
+///
+/// Values are clamped before the error is taken, so out of range values do not bleed into
+/// their neighbours.
+fn floyd_steinberg<T>(array: ArrayView3<'_, f32>, max: f64) -> Array3<T>
+where
+    T: ConvertSample + PrimInt + AsPrimitive<f64>,
+{
+    let min: f64 = T::min_value().as_();
+    let (height, width, channels) = array.dim();
+    let mut values = array.mapv(|value| f64::from(value) * max);
+    let mut output = Array3::from_elem(array.dim(), T::zero());
+    for y in 0..height {
+        for x in 0..width {
+            for z in 0..channels {
+                let value = values[[y, x, z]].clamp(min, max);
+                let quantized = quantize::<T>(value);
+                output[[y, x, z]] = quantized;
+
+                let error = value - quantized.as_();
+                if !error.is_finite() {
+                    continue;
+                }
+                if x + 1 < width {
+                    values[[y, x + 1, z]] += error * 7.0 / 16.0;
+                }
+                if y + 1 < height {
+                    if x > 0 {
+                        values[[y + 1, x - 1, z]] += error * 3.0 / 16.0;
+                    }
+                    values[[y + 1, x, z]] += error * 5.0 / 16.0;
+                    if x + 1 < width {
+                        values[[y + 1, x + 1, z]] += error / 16.0;
+                    }
+                }
+            }
+        }
+    }
+    output
+}
+
+/// Round the scaled value to the nearest integer, clamped to the range of `T`.
+fn quantize<T: ConvertSample>(value: f64) -> T {
+    T::from_f64_with(value, false, Rounding::Nearest, Clamping::Clamp)
+        .expect("clamping never rejects a value")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    fn mean(array: &Array3<u8>) -> f64 {
+        array.iter().map(|value| f64::from(*value)).sum::<f64>() / array.len() as f64
+    }
+
+    #[rstest]
+    #[case(Dither::Ordered)]
+    #[case(Dither::FloydSteinberg)]
+    fn test_dither_keeps_mean(#[case] dither: Dither) {
+        let array = Array3::from_elem((64, 64, 2), 100.25 / 255.0);
+
+        let result = quantize_dithered::<u8>(array.view(), dither);
+
+        assert!((mean(&result) - 100.25).abs() < 0.01);
+        assert!(result.iter().all(|value| *value == 100 || *value == 101));
+        assert_eq!(
+            mean(&quantize_dithered::<u8>(array.view(), Dither::None)),
+            100.0
+        );
+    }
+
+    #[rstest]
+    #[case(Dither::None)]
+    #[case(Dither::Ordered)]
+    #[case(Dither::FloydSteinberg)]
+    fn test_dither_exact_values(#[case] dither: Dither) {
+        let array = Array3::from_shape_fn((16, 16, 1), |(y, x, _)| (y * 16 + x) as f32 / 255.0);
+
+        let result = quantize_dithered::<u8>(array.view(), dither);
+
+        assert_eq!(result, array.mapv(|value| (value * 255.0).round() as u8));
+    }
+
+    #[rstest]
+    #[case(Dither::None)]
+    #[case(Dither::Ordered)]
+    #[case(Dither::FloydSteinberg)]
+    #[cfg_attr(
+        feature = "paranoid",
+        should_panic(expected = "outside of the normalized range")
+    )]
+    fn test_dither_clamps(#[case] dither: Dither) {
+        let mut array = Array3::from_elem((8, 8, 1), 1.5);
+        array[[0, 0, 0]] = f32::NAN;
+        array[[4, 4, 0]] = -1.0;
+
+        let result = quantize_dithered::<u16>(array.view(), dither);
+
+        assert_eq!(result[[0, 0, 0]], 0);
+        assert_eq!(result[[4, 4, 0]], 0);
+        assert_eq!(result[[7, 7, 0]], u16::MAX);
+    }
+}
@@ -1,20 +1,214 @@
 use thiserror::Error;
 
 /// Global error object for the image-ndarray crate.
-#[derive(Debug, Error, PartialEq)]
+///
+/// New variants can be added in minor releases, so matches need a wildcard arm.
+///
+/// Errors of the image crate and IO errors are kept as the [source](std::error::Error::source)
+/// of their variant, so callers can downcast to them.
+#[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum Error {
-    #[cfg(feature = "image")]
+    #[cfg(feature = "std")]
     #[error("NDArray had an error during initializaiton of shape: {0}")]
     NDArray(#[from] ndarray::ShapeError),
     #[error("Image could not be constructed from ndarray.")]
     ImageConstructFailed,
-    #[error(
-        "Image could not be constructed from ndarray because output does not match input channel count."
-    )]
-    ChannelMismatch,
+    #[error("Expected {expected} channels, but got {got}.")]
+    ChannelMismatch { expected: usize, got: usize },
+    #[error("Arrays with {0} channels are not supported here.")]
+    UnsupportedChannels(usize),
     #[error("Only 2d and 3d arrays are supported.")]
     Dimensions,
+    #[error("Requested region does not fit within the image bounds.")]
+    OutOfBounds,
+    #[error("Array needs to be contiguous and in standard layout.")]
+    NotContiguous,
+    #[error("Volume needs to contain at least one slice.")]
+    EmptyVolume,
+    #[error("Expected an array of shape {expected:?}, but got {got:?}.")]
+    ShapeMismatch {
+        expected: (usize, usize, usize),
+        got: (usize, usize, usize),
+    },
+    #[error("Data of length {got} does not match the shape {shape:?}.")]
+    LengthMismatch {
+        shape: (usize, usize, usize),
+        got: usize,
+    },
+    #[error("Alignment needs to be a power of two that can be met for the sample type.")]
+    InvalidAlignment,
+    #[error("Sample type of the array does not match the image.")]
+    SampleMismatch,
+    #[error("Bit depth needs to be between 1 and 16.")]
+    InvalidDepth,
+    #[error("Sample at index {0:?} could not be represented in the normalized range.")]
+    NormalizationFailed([usize; 3]),
+    #[error("Sample at index {0:?} is outside of the range of the target type.")]
+    OutOfRange([usize; 3]),
+    #[error("Array is too large to be represented as an image.")]
+    TooLarge,
+    #[error("Sample at index {0:?} is NaN or infinite.")]
+    NonFinite([usize; 3]),
+    #[cfg(feature = "std")]
+    #[error("IO operation failed: {0}")]
+    Io(#[source] std::io::Error),
+    #[cfg(feature = "image")]
+    #[error("Image operation is not supported: {0}")]
+    Unsupported(#[source] image::ImageError),
+    #[cfg(feature = "image")]
+    #[error("Image could not be decoded: {0}")]
+    Decode(#[source] image::ImageError),
+    #[cfg(feature = "image")]
+    #[error("Image could not be encoded: {0}")]
+    Encode(#[source] image::ImageError),
+    #[cfg(feature = "image")]
+    #[error("Image exceeds the decode limits.")]
+    LimitsExceeded,
+}
+
+/// Reason a single value could not be converted by [`NormalizedFloat`](crate::prelude::NormalizedFloat).
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum NormalizeError {
+    #[error("Value is NaN.")]
+    NotANumber,
+    #[error("Value is infinite or does not fit in the target type.")]
+    Overflow,
+    #[error("Value is outside of the normalized range of the target type.")]
+    OutOfRange,
+}
+
+impl PartialEq for Error {
+    /// Compare IO errors by their kind and errors of the image crate by their message, as
+    /// neither implements PartialEq. All other variants compare their fields.
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            #[cfg(feature = "std")]
+            (Self::NDArray(error), Self::NDArray(other)) => error == other,
+            (Self::ImageConstructFailed, Self::ImageConstructFailed) => true,
+            (
+                Self::ChannelMismatch { expected, got },
+                Self::ChannelMismatch {
+                    expected: other_expected,
+                    got: other_got,
+                },
+            ) => (expected, got) == (other_expected, other_got),
+            (Self::UnsupportedChannels(channels), Self::UnsupportedChannels(other)) => {
+                channels == other
+            }
+            (Self::Dimensions, Self::Dimensions) => true,
+            (Self::OutOfBounds, Self::OutOfBounds) => true,
+            (Self::NotContiguous, Self::NotContiguous) => true,
+            (Self::EmptyVolume, Self::EmptyVolume) => true,
+            (
+                Self::ShapeMismatch { expected, got },
+                Self::ShapeMismatch {
+                    expected: other_expected,
+                    got: other_got,
+                },
+            ) => (expected, got) == (other_expected, other_got),
+            (
+                Self::LengthMismatch { shape, got },
+                Self::LengthMismatch {
+                    shape: other_shape,
+                    got: other_got,
+                },
+            ) => (shape, got) == (other_shape, other_got),
+            (Self::InvalidAlignment, Self::InvalidAlignment) => true,
+            (Self::SampleMismatch, Self::SampleMismatch) => true,
+            (Self::InvalidDepth, Self::InvalidDepth) => true,
+            (Self::NormalizationFailed(index), Self::NormalizationFailed(other)) => index == other,
+            (Self::OutOfRange(index), Self::OutOfRange(other)) => index == other,
+            (Self::TooLarge, Self::TooLarge) => true,
+            (Self::NonFinite(index), Self::NonFinite(other)) => index == other,
+            #[cfg(feature = "std")]
+            (Self::Io(error), Self::Io(other)) => error.kind() == other.kind(),
+            #[cfg(feature = "image")]
+            (Self::Unsupported(error), Self::Unsupported(other))
+            | (Self::Decode(error), Self::Decode(other))
+            | (Self::Encode(error), Self::Encode(other)) => error.to_string() == other.to_string(),
+            #[cfg(feature = "image")]
+            (Self::LimitsExceeded, Self::LimitsExceeded) => true,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
 }
 
 #[cfg(feature = "image")]
+impl From<image::ImageError> for Error {
+    /// Map the error onto the variant of the same category, exceeded limits become
+    /// [`Error::LimitsExceeded`] and IO errors [`Error::Io`].
+    fn from(error: image::ImageError) -> Self {
+        use image::ImageError;
+        match error {
+            ImageError::Limits(_) => Self::LimitsExceeded,
+            ImageError::IoError(error) => error.into(),
+            ImageError::Decoding(_) => Self::Decode(error),
+            ImageError::Encoding(_) => Self::Encode(error),
+            ImageError::Parameter(_) | ImageError::Unsupported(_) => Self::Unsupported(error),
+        }
+    }
+}
+
 pub type Result<T> = core::result::Result<T, Error>;
+
+#[cfg(feature = "std")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_io_error() {
+        use std::error::Error as _;
+
+        let error = std::io::Error::new(std::io::ErrorKind::NotFound, "missing.png");
+
+        let result: Error = error.into();
+
+        assert_eq!(
+            result,
+            std::io::Error::from(std::io::ErrorKind::NotFound).into()
+        );
+        assert_ne!(
+            result,
+            std::io::Error::from(std::io::ErrorKind::Other).into()
+        );
+        let source = result.source().unwrap();
+        assert_eq!(source.to_string(), "missing.png");
+        assert!(source.downcast_ref::<std::io::Error>().is_some());
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_from_image_error() {
+        use image::ImageError;
+        use image::error::{
+            ImageFormatHint, LimitError, LimitErrorKind, UnsupportedError, UnsupportedErrorKind,
+        };
+
+        let limits = ImageError::Limits(LimitError::from_kind(LimitErrorKind::DimensionError));
+        assert_eq!(Error::from(limits), Error::LimitsExceeded);
+
+        let io = ImageError::IoError(std::io::ErrorKind::PermissionDenied.into());
+        assert!(matches!(
+            Error::from(io),
+            Error::Io(error) if error.kind() == std::io::ErrorKind::PermissionDenied
+        ));
+
+        let unsupported = ImageError::Unsupported(UnsupportedError::from_format_and_kind(
+            ImageFormatHint::Unknown,
+            UnsupportedErrorKind::GenericFeature("layers".to_string()),
+        ));
+        let result = Error::from(unsupported);
+        let source = std::error::Error::source(&result).unwrap();
+        assert!(source.downcast_ref::<ImageError>().is_some());
+        assert!(matches!(result, Error::Unsupported(_)));
+    }
+}
@@ -0,0 +1,241 @@
+//! Interoperability with the strided FlatSamples of the image crate
+
+use crate::error::{Error, Result};
+use image::flat::{FlatSamples, SampleLayout};
+use ndarray::{Array3, ArrayBase, ArrayView3, Axis, Data, Ix3, ShapeBuilder};
+
+/// Conversion methods for working with ndarrays on FlatSamples.
+///
+/// The sample layout of FlatSamples translates directly into ndarray strides,
+/// so buffers with any layout can be referenced without repacking.
+pub trait FlatSamplesArray<ImageContainer> {
+    /// Cast the FlatSamples as an ArrayView3.
+    ///
+    /// * `Y` index is the row
+    /// * `X` index is the columns
+    /// * `Z` index is the channel
+    ///
+    /// So when referencing:
+    /// `array[[y, x, z]]`
+    ///
+    /// Returns [`Error::NDArray`] if the samples do not cover the layout.
+    ///
+    /// This does not copy the data, as it is a reference to the actual samples.
+    fn as_ndarray(&self) -> Result<ArrayView3<'_, ImageContainer>>;
+}
+
+/// Conversion methods for owned FlatSamples.
+pub trait FlatSamplesArrayOwned<ImageContainer> {
+    /// Interpret the FlatSamples as an Array3, keeping the strides of the layout.
+    ///
+    /// * `Y` index is the row
+    /// * `X` index is the columns
+    /// * `Z` index is the channel
+    ///
+    /// So when referencing:
+    /// `array[[y, x, z]]`
+    ///
+    /// Returns [`Error::NDArray`] if the samples do not cover the layout or if
+    /// the layout references the same sample more than once.
+    ///
+    /// This does not copy the data, but it does consume the samples.
+    fn into_ndarray(self) -> Result<Array3<ImageContainer>>;
+}
+
+impl<B, C> FlatSamplesArray<C> for FlatSamples<B>
+where
+    B: AsRef<[C]>,
+{
+    fn as_ndarray(&self) -> Result<ArrayView3<'_, C>> {
+        Ok(ArrayView3::from_shape(
+            layout_shape(&self.layout),
+            self.samples.as_ref(),
+        )?)
+    }
+}
+
+impl<C> FlatSamplesArrayOwned<C> for FlatSamples<Vec<C>> {
+    fn into_ndarray(self) -> Result<Array3<C>> {
+        Ok(Array3::from_shape_vec(
+            layout_shape(&self.layout),
+            self.samples,
+        )?)
+    }
+}
+
+/// Conversion methods from a `(y, x, z)` ndarray into FlatSamples.
+pub trait NdarrayFlatSamplesExt<ImageContainer> {
+    /// Reference the array as FlatSamples, keeping the strides of the array.
+    ///
+    /// Returns [`Error::NotContiguous`] if the array has negative strides or gaps
+    /// in memory, and [`Error::ImageConstructFailed`] if the dimensions do not fit.
+    ///
+    /// This does not copy the data, as it is a reference to the actual data in the array.
+    fn as_flat_samples(&self) -> Result<FlatSamples<&[ImageContainer]>>;
+
+    /// Convert the array into FlatSamples, keeping the strides of the array.
+    ///
+    /// Returns [`Error::ImageConstructFailed`] if the dimensions do not fit.
+    ///
+    /// This does not copy the data when the array is owned and has no negative strides,
+    /// but it does consume the array.
+    fn into_flat_samples(self) -> Result<FlatSamples<Vec<ImageContainer>>>;
+}
+
+impl<S, C> NdarrayFlatSamplesExt<C> for ArrayBase<S, Ix3>
+where
+    S: Data<Elem = C>,
+    C: Clone,
+{
+    fn as_flat_samples(&self) -> Result<FlatSamples<&[C]>> {
+        let layout = sample_layout(self.dim(), self.strides())?;
+        let samples = self.as_slice_memory_order().ok_or(Error::NotContiguous)?;
+        Ok(FlatSamples {
+            samples,
+            layout,
+            color_hint: None,
+        })
+    }
+
+    fn into_flat_samples(self) -> Result<FlatSamples<Vec<C>>> {
+        let array = self.into_owned();
+        let array = match array.strides().iter().all(|stride| *stride >= 0) {
+            true => array,
+            false => array.as_standard_layout().into_owned(),
+        };
+        let layout = sample_layout(array.dim(), array.strides())?;
+        let (mut samples, offset) = array.into_raw_vec_and_offset();
+        samples.drain(..offset.unwrap_or_default());
+        Ok(FlatSamples {
+            samples,
+            layout,
+            color_hint: None,
+        })
+    }
+}
+
+/// Translate the sample layout into a `(y, x, z)` shape with strides.
+fn layout_shape(layout: &SampleLayout) -> ndarray::StrideShape<Ix3> {
+    (
+        layout.height as usize,
+        layout.width as usize,
+        layout.channels as usize,
+    )
+        .strides((
+            layout.height_stride,
+            layout.width_stride,
+            layout.channel_stride,
+        ))
+}
+
+/// Translate the `(y, x, z)` shape and strides of an array into a sample layout.
+fn sample_layout(shape: (usize, usize, usize), strides: &[isize]) -> Result<SampleLayout> {
+    let (height, width, channels) = shape;
+    let stride = |axis: Axis| usize::try_from(strides[axis.index()]).or(Err(Error::NotContiguous));
+    Ok(SampleLayout {
+        channels: u8::try_from(channels).or(Err(Error::ImageConstructFailed))?,
+        channel_stride: stride(Axis(2))?,
+        width: u32::try_from(width).or(Err(Error::ImageConstructFailed))?,
+        width_stride: stride(Axis(1))?,
+        height: u32::try_from(height).or(Err(Error::ImageConstructFailed))?,
+        height_stride: stride(Axis(0))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::ImageArray;
+    use image::{Rgba, Rgba32FImage};
+    use ndarray::s;
+
+    #[test]
+    fn test_flat_samples_as_ndarray() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let test_image = Rgba32FImage::from_vec(256, 128, data).unwrap();
+
+        let flat = test_image.as_flat_samples();
+        let array = flat.as_ndarray().unwrap();
+
+        assert_eq!(array, test_image.as_ndarray());
+        assert_eq!(array.as_ptr(), test_image.as_ptr());
+    }
+
+    #[test]
+    fn test_flat_samples_column_major() {
+        let (width, height, channels) = (16, 8, 3);
+        let flat = FlatSamples {
+            samples: create_test_data(width, height, channels),
+            layout: SampleLayout::column_major_packed(3, 16, 8),
+            color_hint: None,
+        };
+        let pointer = flat.samples.as_ptr();
+
+        let view = flat.as_ndarray().unwrap();
+        assert_eq!(view[[2, 5, 1]], flat.get_sample(1, 5, 2).copied().unwrap());
+
+        let array = flat.clone().into_ndarray().unwrap();
+        assert_eq!(array, view);
+        assert_eq!(flat.into_ndarray().unwrap().as_ptr(), pointer);
+    }
+
+    #[test]
+    fn test_flat_samples_out_of_bounds() {
+        let flat = FlatSamples {
+            samples: vec![0.0f32; 10],
+            layout: SampleLayout::row_major_packed(4, 16, 8),
+            color_hint: None,
+        };
+
+        assert!(matches!(flat.as_ndarray(), Err(Error::NDArray(_))));
+        assert!(matches!(flat.into_ndarray(), Err(Error::NDArray(_))));
+    }
+
+    #[test]
+    fn test_as_flat_samples() {
+        let (width, height, channels) = (16, 8, 3);
+        let data = create_test_data(width, height, channels);
+        let array = Array3::from_shape_vec((channels, height, width), data).unwrap();
+        let interleaved = array.view().permuted_axes([1, 2, 0]);
+
+        let flat = interleaved.as_flat_samples().unwrap();
+
+        let layout = flat.layout;
+        assert_eq!(layout.strides_cwh(), (128, 1, 16));
+        assert_eq!((layout.channels, layout.width, layout.height), (3, 16, 8));
+        for ((y, x, c), value) in interleaved.indexed_iter() {
+            assert_eq!(flat.get_sample(c as u8, x as u32, y as u32), Some(value));
+        }
+    }
+
+    #[test]
+    fn test_into_flat_samples_with_offset() {
+        let (width, height, channels) = (16, 8, 4);
+        let data = create_test_data(width, height, channels);
+        let mut array = Array3::from_shape_vec((height, width, channels), data).unwrap();
+        array.slice_collapse(s![2..6, .., ..]);
+        let compare = array.clone();
+
+        let flat = array.into_flat_samples().unwrap();
+
+        assert_eq!(flat.as_ndarray().unwrap(), compare);
+        let image = flat.try_into_buffer::<Rgba<f32>>().unwrap();
+        assert_eq!(image.as_ndarray(), compare);
+    }
+
+    #[test]
+    fn test_flat_samples_negative_strides() {
+        let array = Array3::from_shape_fn((8, 16, 3), |(y, x, c)| (y * 48 + x * 3 + c) as f32);
+        let flipped = array.slice(s![..;-1, .., ..]);
+
+        assert_eq!(flipped.as_flat_samples().err(), Some(Error::NotContiguous));
+        let flat = flipped.into_flat_samples().unwrap();
+        assert_eq!(flat.as_ndarray().unwrap(), flipped);
+    }
+
+    fn create_test_data(width: usize, height: usize, channels: usize) -> Vec<f32> {
+        let total_elements = width * height * channels;
+        (0..total_elements).map(|x| (x + 1) as f32).collect()
+    }
+}
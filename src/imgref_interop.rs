@@ -0,0 +1,255 @@
+//! Interoperability with the strided images of the imgref crate
+
+use crate::error::{Error, Result};
+use crate::raw_parts::RawParts;
+use bytemuck::Pod;
+use imgref::{Img, ImgRef, ImgVec};
+use ndarray::{Array3, ArrayBase, ArrayView3, Data, Ix3, ShapeBuilder};
+
+/// Conversion methods for working with ndarrays on an imgref image.
+///
+/// The pixels of the image are split into `C` components, so for example an
+/// `ImgRef<RGB8>` becomes an `ArrayView3<u8>` with 3 channels.
+pub trait ImgArray<Pixel> {
+    /// Cast the image as an ArrayView3 of the pixel components.
+    ///
+    /// * `Y` index is the row
+    /// * `X` index is the columns
+    /// * `Z` index is the channel
+    ///
+    /// So when referencing:
+    /// `array[[y, x, z]]`
+    ///
+    /// The stride of the image is kept, so padding between the rows is skipped.
+    ///
+    /// Returns [`Error::SampleMismatch`] if the pixel can not be split into `C` components
+    /// and [`Error::NDArray`] if the buffer does not cover the image.
+    ///
+    /// This does not copy the data, as it is a reference to the actual data in the buffer.
+    fn as_ndarray<C: Pod>(&self) -> Result<ArrayView3<'_, C>>;
+}
+
+/// Conversion methods for owned imgref images.
+pub trait ImgArrayOwned<Pixel> {
+    /// Convert the image into an Array3 of the pixel components, keeping the stride.
+    ///
+    /// * `Y` index is the row
+    /// * `X` index is the columns
+    /// * `Z` index is the channel
+    ///
+    /// So when referencing:
+    /// `array[[y, x, z]]`
+    ///
+    /// Returns [`Error::SampleMismatch`] if the pixel can not be split into `C` components
+    /// and [`Error::NDArray`] if the buffer does not cover the image.
+    ///
+    /// This does not copy the data when `C` has the size and alignment of the pixel, like
+    /// a single channel image, otherwise the buffer is copied once.
+    fn into_ndarray<C: Pod>(self) -> Result<Array3<C>>;
+}
+
+impl<B, Px> ImgArray<Px> for Img<B>
+where
+    B: AsRef<[Px]>,
+    Px: Pod,
+{
+    fn as_ndarray<C: Pod>(&self) -> Result<ArrayView3<'_, C>> {
+        let channels = components::<Px, C>()?;
+        let samples =
+            bytemuck::try_cast_slice(self.buf().as_ref()).or(Err(Error::SampleMismatch))?;
+        Ok(ArrayView3::from_shape(
+            img_shape(self.height(), self.width(), self.stride(), channels),
+            samples,
+        )?)
+    }
+}
+
+impl<Px> ImgArrayOwned<Px> for ImgVec<Px>
+where
+    Px: Pod,
+{
+    fn into_ndarray<C: Pod>(self) -> Result<Array3<C>> {
+        let channels = components::<Px, C>()?;
+        let shape = img_shape(self.height(), self.width(), self.stride(), channels);
+        let samples = match bytemuck::allocation::try_cast_vec(self.into_buf()) {
+            Ok(samples) => samples,
+            Err((_, pixels)) => bytemuck::cast_slice(&pixels).to_vec(),
+        };
+        Ok(Array3::from_shape_vec(shape, samples)?)
+    }
+}
+
+/// Conversion methods from a `(y, x, z)` ndarray into imgref images.
+pub trait NdarrayImgExt<Component> {
+    /// Reference the array as an imgref image of `Px` pixels.
+    ///
+    /// Returns [`Error::ChannelMismatch`] if the channels do not make up a pixel,
+    /// [`Error::SampleMismatch`] if the data is not aligned for the pixel and
+    /// [`Error::NotContiguous`] if the array is not contiguous in standard layout.
+    ///
+    /// This does not copy the data, as it is a reference to the actual data in the array.
+    fn as_img<Px: Pod>(&self) -> Result<ImgRef<'_, Px>>;
+
+    /// Convert the array into an imgref image of `Px` pixels.
+    ///
+    /// Returns [`Error::ChannelMismatch`] if the channels do not make up a pixel and
+    /// [`Error::SampleMismatch`] if the pixel is not made up of whole components.
+    ///
+    /// This does not copy the data when the array is owned, in standard layout and `Px`
+    /// has the size and alignment of the components, otherwise the data is copied once.
+    fn into_img<Px: Pod>(self) -> Result<ImgVec<Px>>;
+}
+
+impl<S, C> NdarrayImgExt<C> for ArrayBase<S, Ix3>
+where
+    S: Data<Elem = C>,
+    C: Pod,
+{
+    fn as_img<Px: Pod>(&self) -> Result<ImgRef<'_, Px>> {
+        let (height, width, channels) = self.dim();
+        check_components::<Px, C>(channels)?;
+        let samples = self.as_slice().ok_or(Error::NotContiguous)?;
+        let pixels = bytemuck::try_cast_slice(samples).or(Err(Error::SampleMismatch))?;
+        Ok(Img::new(pixels, width, height))
+    }
+
+    fn into_img<Px: Pod>(self) -> Result<ImgVec<Px>> {
+        let (height, width, channels) = self.dim();
+        check_components::<Px, C>(channels)?;
+        let array = if self.is_standard_layout() {
+            self.into_owned()
+        } else {
+            self.as_standard_layout().into_owned()
+        };
+        let (_, samples) = RawParts::from_ndarray(array).into_inner();
+        let pixels = match bytemuck::allocation::try_cast_vec(samples) {
+            Ok(pixels) => pixels,
+            Err((_, samples)) => bytemuck::cast_slice(&samples).to_vec(),
+        };
+        Ok(Img::new(pixels, width, height))
+    }
+}
+
+/// Amount of `C` components that make up a `Px` pixel.
+fn components<Px, C>() -> Result<usize> {
+    let (pixel, component) = (size_of::<Px>(), size_of::<C>());
+    if component == 0 || pixel % component != 0 || align_of::<Px>() < align_of::<C>() {
+        return Err(Error::SampleMismatch);
+    }
+    Ok(pixel / component)
+}
+
+/// Verify that `channels` components make up a pixel.
+fn check_components<Px, C>(channels: usize) -> Result<()> {
+    let expected = components::<Px, C>()?;
+    if expected != channels {
+        return Err(Error::ChannelMismatch {
+            expected,
+            got: channels,
+        });
+    }
+    Ok(())
+}
+
+/// Shape with strides of a `(y, x, z)` array over an image with the row `stride` in pixels.
+fn img_shape(
+    height: usize,
+    width: usize,
+    stride: usize,
+    channels: usize,
+) -> ndarray::StrideShape<Ix3> {
+    (height, width, channels).strides((stride * channels, channels, 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::s;
+    use rgb::{RGB8, RGBA8};
+
+    #[test]
+    fn test_as_ndarray_with_stride() {
+        let pixels: Vec<RGB8> = (0..20 * 8).map(|i| RGB8::new(i as u8, 1, 2)).collect();
+        let img = Img::new_stride(pixels.as_slice(), 16, 8, 20);
+
+        let array = img.as_ndarray::<u8>().unwrap();
+
+        assert_eq!(array.dim(), (8, 16, 3));
+        assert_eq!(array.as_ptr(), pixels.as_ptr().cast());
+        for ((y, x, c), value) in array.indexed_iter() {
+            let pixel = img[(x, y)];
+            assert_eq!(*value, [pixel.r, pixel.g, pixel.b][c]);
+        }
+    }
+
+    #[test]
+    fn test_into_ndarray() {
+        let pixels: Vec<u16> = (0..16 * 8).collect();
+        let img = Img::new(pixels, 16, 8);
+        let pointer = img.buf().as_ptr();
+
+        let array = img.into_ndarray::<u16>().unwrap();
+
+        assert_eq!(array.dim(), (8, 16, 1));
+        assert_eq!(array.as_ptr(), pointer);
+        assert_eq!(array[[2, 3, 0]], 35);
+    }
+
+    #[test]
+    fn test_into_ndarray_copies_pixels() {
+        let pixels: Vec<RGBA8> = (0..16 * 8)
+            .map(|i| RGBA8::new(i as u8, 0, 0, 255))
+            .collect();
+        let img = Img::new(pixels, 16, 8);
+
+        let array = img.into_ndarray::<u8>().unwrap();
+
+        assert_eq!(array.dim(), (8, 16, 4));
+        assert_eq!(array.slice(s![2, 3, ..]).to_vec(), [35, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_as_img() {
+        let array = Array3::from_shape_fn((8, 16, 3), |(y, x, c)| (y * 48 + x * 3 + c) as u8);
+
+        let img = array.as_img::<RGB8>().unwrap();
+
+        assert_eq!((img.width(), img.height(), img.stride()), (16, 8, 16));
+        assert_eq!(img[(3usize, 2usize)], RGB8::new(105, 106, 107));
+        assert_eq!(img.as_ndarray::<u8>().unwrap(), array);
+    }
+
+    #[test]
+    fn test_into_img() {
+        let array = Array3::from_shape_fn((16, 8, 4), |(x, y, c)| (y * 64 + x * 4 + c) as u8);
+        let transposed = array.permuted_axes([1, 0, 2]);
+
+        let img = transposed.clone().into_img::<RGBA8>().unwrap();
+
+        assert_eq!(img[(3usize, 2usize)], RGBA8::new(140, 141, 142, 143));
+        assert_eq!(img.into_ndarray::<u8>().unwrap(), transposed);
+
+        let array = Array3::<u8>::zeros((8, 16, 4));
+        let pointer = array.as_ptr();
+        let img = array.into_img::<[u8; 4]>().unwrap();
+        assert_eq!(img.buf().as_ptr().cast(), pointer);
+    }
+
+    #[test]
+    fn test_img_errors() {
+        let array = Array3::<u8>::zeros((8, 16, 4));
+        assert_eq!(
+            array.as_img::<RGB8>().err(),
+            Some(Error::ChannelMismatch {
+                expected: 3,
+                got: 4
+            })
+        );
+
+        let img = Img::new(vec![0u8; 16 * 8], 16, 8);
+        assert_eq!(img.as_ndarray::<u16>().err(), Some(Error::SampleMismatch));
+
+        let img = Img::new(vec![0u8; 10], 16, 8);
+        assert!(matches!(img.as_ndarray::<u8>(), Err(Error::NDArray(_))));
+    }
+}
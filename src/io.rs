@@ -0,0 +1,723 @@
+//! Decoding of image files directly into ndarrays
+
+use crate::capabilities::{Capabilities, SampleType};
+use crate::convert::{ConvertOptions, ConvertWith};
+use crate::error::{Error, Result};
+use crate::normalize::{ConvertSample, Rounding};
+use crate::trace::{record, span};
+use crate::traits::{ImageArray, NdarrayImageExt, NormalizedFloat};
+use bytemuck::Pod;
+use image::error::{UnsupportedError, UnsupportedErrorKind};
+use image::{
+    DynamicImage, ImageBuffer, ImageDecoder, ImageError, ImageFormat, ImageReader, Limits, Luma,
+    LumaA, Pixel, Rgb, Rgba,
+};
+use ndarray::{Array3, ArrayView3, ArrayViewMut3};
+use num_traits::AsPrimitive;
+use std::io::{BufRead, Cursor, Seek, Write};
+use std::path::Path;
+
+/// Resource limits for decoding untrusted files.
+///
+/// The limits are checked against the header of the file, before any pixel data is
+/// decoded, so a small compressed file that expands into a huge image is rejected without
+/// allocating its buffer. The default only limits the memory to 512 MiB, like the image
+/// crate does:
+///
+/// ```
+/// use image_ndarray::DecodeLimits;
+///
+/// let limits = DecodeLimits::new()
+///     .max_pixels(4096 * 4096)
+///     .max_bytes(256 * 1024 * 1024);
+/// ```
+///
+/// Decoding can not be interrupted, so there is no time limit. Bounding the pixels and
+/// bytes bounds the decoding time as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeLimits {
+    max_pixels: Option<u64>,
+    max_bytes: Option<u64>,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_pixels: None,
+            max_bytes: Limits::default().max_alloc,
+        }
+    }
+}
+
+impl DecodeLimits {
+    /// Create the default limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create limits that accept any file, for trusted input only.
+    pub fn unlimited() -> Self {
+        Self {
+            max_pixels: None,
+            max_bytes: None,
+        }
+    }
+
+    /// Set the maximum amount of pixels, which is the width times the height.
+    pub fn max_pixels(mut self, pixels: u64) -> Self {
+        self.max_pixels = Some(pixels);
+        self
+    }
+
+    /// Set the maximum amount of bytes allocated for the decoded image and the array.
+    pub fn max_bytes(mut self, bytes: u64) -> Self {
+        self.max_bytes = Some(bytes);
+        self
+    }
+
+    /// Check the dimensions and the amount of bytes of the decoded image.
+    fn check(&self, (width, height): (u32, u32), bytes: u64) -> Result<()> {
+        let pixels = u64::from(width) * u64::from(height);
+        if self.max_pixels.is_some_and(|max| pixels > max)
+            || self.max_bytes.is_some_and(|max| bytes > max)
+        {
+            return Err(Error::LimitsExceeded);
+        }
+        Ok(())
+    }
+
+    /// Limits for the decoders of the image crate.
+    fn image_limits(&self) -> Limits {
+        let mut limits = Limits::no_limits();
+        limits.max_alloc = self.max_bytes;
+        limits
+    }
+}
+
+/// Decode the image read by `reader` directly into the provided `(y, x, z)` array.
+///
+/// * `Y` index is the row
+/// * `X` index is the columns
+/// * `Z` index is the channel
+///
+/// The decoder writes straight into the memory of the target, so a single allocation can
+/// be reused for every frame. No conversion is done, so the target has to match the
+/// native layout of the file, for example `u16` with 3 channels for a 16 bit RGB png.
+///
+/// Returns [`Error::ShapeMismatch`] if the dimensions differ, [`Error::ChannelMismatch`]
+/// if the channel count differs, [`Error::SampleMismatch`] if the sample type has another
+/// size than the file and [`Error::NotContiguous`] if the target is not in standard layout.
+///
+/// The dimensions are checked before decoding, so files larger than the target are always
+/// rejected without allocating. Other limits can be set on the `reader`.
+pub fn decode_into<R, C>(reader: ImageReader<R>, mut target: ArrayViewMut3<'_, C>) -> Result<()>
+where
+    R: BufRead + Seek,
+    C: Pod,
+{
+    span!(INFO, "decode_into", shape = ?target.dim(), dtype = core::any::type_name::<C>());
+    let decoder = reader.into_decoder()?;
+    let (width, height) = decoder.dimensions();
+    let color = decoder.color_type();
+
+    let expected = (
+        height as usize,
+        width as usize,
+        color.channel_count().into(),
+    );
+    let (target_height, target_width, channels) = target.dim();
+    if (target_height, target_width) != (expected.0, expected.1) {
+        return Err(Error::ShapeMismatch {
+            expected,
+            got: target.dim(),
+        });
+    }
+    if channels != expected.2 {
+        return Err(Error::ChannelMismatch {
+            expected: expected.2,
+            got: channels,
+        });
+    }
+    if channels * size_of::<C>() != color.bytes_per_pixel().into() {
+        return Err(Error::SampleMismatch);
+    }
+
+    let data = target.as_slice_mut().ok_or(Error::NotContiguous)?;
+    Ok(decoder.read_image(bytemuck::cast_slice_mut(data))?)
+}
+
+/// Load the image file at `path` as a `(y, x, z)` array of 16 bit samples.
+///
+/// * `Y` index is the row
+/// * `X` index is the columns
+/// * `Z` index is the channel
+///
+/// The channel count of the file is kept. 16 bit files are returned as stored, without
+/// passing through 8 bits, and 8 bit files are scaled to the full 16 bit range.
+/// The decoders of the image crate already return the samples in native byte order, so
+/// the big endian samples of a png are converted correctly.
+///
+/// The format of the file needs to be enabled in the image crate, for example `png`.
+///
+/// The [default limits](DecodeLimits::default) apply, see [`load_ndarray_u16_with_limits`]
+/// to load untrusted files.
+pub fn load_ndarray_u16(path: impl AsRef<Path>) -> Result<Array3<u16>> {
+    load_ndarray_u16_with_limits(path, &DecodeLimits::default())
+}
+
+/// Load the image file at `path` as a `(y, x, z)` array of 16 bit samples, rejecting files
+/// that exceed the `limits`.
+///
+/// See [`load_ndarray_u16`] for how the samples are loaded.
+///
+/// Returns [`Error::LimitsExceeded`] if the decoded image or the array would exceed the
+/// limits.
+pub fn load_ndarray_u16_with_limits(
+    path: impl AsRef<Path>,
+    limits: &DecodeLimits,
+) -> Result<Array3<u16>> {
+    span!(INFO, "load_ndarray_u16", path = ?path.as_ref(), shape = tracing::field::Empty);
+    let mut reader = ImageReader::open(path)?.with_guessed_format()?;
+    reader.limits(limits.image_limits());
+    let decoder = reader.into_decoder()?;
+    let (width, height) = decoder.dimensions();
+    let samples =
+        u64::from(width) * u64::from(height) * u64::from(decoder.color_type().channel_count());
+    limits.check((width, height), decoder.total_bytes().max(samples * 2))?;
+    let image = DynamicImage::from_decoder(decoder)?;
+
+    let array = match image.color().channel_count() {
+        1 => image.into_luma16().to_ndarray(),
+        2 => image.into_luma_alpha16().to_ndarray(),
+        3 => image.into_rgb16().to_ndarray(),
+        _ => image.into_rgba16().to_ndarray(),
+    };
+    record!("shape", array.dim());
+    Ok(array)
+}
+
+/// Pixels that image files can be opened as with [`open_as`].
+///
+/// Implemented for the gray, gray alpha, RGB and RGBA pixels with 8 and 16 bit samples and
+/// the RGB and RGBA pixels with 32 bit float samples.
+pub trait OpenPixel: Pixel + sealed::Sealed {
+    /// Convert the decoded image into a buffer of this pixel.
+    fn from_dynamic(image: DynamicImage) -> ImageBuffer<Self, Vec<Self::Subpixel>>;
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+macro_rules! impl_open_pixel {
+    ($pixel:ty, $convert:ident) => {
+        impl sealed::Sealed for $pixel {}
+        impl OpenPixel for $pixel {
+            fn from_dynamic(image: DynamicImage) -> ImageBuffer<Self, Vec<Self::Subpixel>> {
+                image.$convert()
+            }
+        }
+    };
+}
+
+impl_open_pixel!(Luma<u8>, into_luma8);
+impl_open_pixel!(LumaA<u8>, into_luma_alpha8);
+impl_open_pixel!(Rgb<u8>, into_rgb8);
+impl_open_pixel!(Rgba<u8>, into_rgba8);
+impl_open_pixel!(Luma<u16>, into_luma16);
+impl_open_pixel!(LumaA<u16>, into_luma_alpha16);
+impl_open_pixel!(Rgb<u16>, into_rgb16);
+impl_open_pixel!(Rgba<u16>, into_rgba16);
+impl_open_pixel!(Rgb<f32>, into_rgb32f);
+impl_open_pixel!(Rgba<f32>, into_rgba32f);
+
+/// Open the image file at `path` as a `(y, x, z)` array of the pixel `P`.
+///
+/// * `Y` index is the row
+/// * `X` index is the columns
+/// * `Z` index is the channel
+///
+/// The image is converted to the channels and sample type of the pixel, like the `into_*`
+/// methods of [`DynamicImage`] do, so for example `open_as::<Rgb<u8>>` always returns 3
+/// channels of 8 bits:
+///
+/// ```no_run
+/// use image::Rgb;
+/// use image_ndarray::open_as;
+///
+/// let array = open_as::<Rgb<u8>>("photo.png")?;
+/// assert_eq!(array.dim().2, 3);
+/// # Ok::<(), image_ndarray::Error>(())
+/// ```
+///
+/// The format of the file needs to be enabled in the image crate, for example `png`.
+///
+/// The [default limits](DecodeLimits::default) apply, see [`open_as_with_limits`] to open
+/// untrusted files.
+pub fn open_as<P: OpenPixel>(path: impl AsRef<Path>) -> Result<Array3<P::Subpixel>> {
+    open_as_with_limits::<P>(path, &DecodeLimits::default())
+}
+
+/// Open the image file at `path` as a `(y, x, z)` array of the pixel `P`, rejecting files
+/// that exceed the `limits`.
+///
+/// See [`open_as`] for how the image is converted.
+///
+/// Returns [`Error::LimitsExceeded`] if the decoded image or the array would exceed the
+/// limits.
+pub fn open_as_with_limits<P: OpenPixel>(
+    path: impl AsRef<Path>,
+    limits: &DecodeLimits,
+) -> Result<Array3<P::Subpixel>> {
+    span!(INFO, "open_as", path = ?path.as_ref(), dtype = core::any::type_name::<P>());
+    let mut reader = ImageReader::open(path)?.with_guessed_format()?;
+    reader.limits(limits.image_limits());
+    let decoder = reader.into_decoder()?;
+    let (width, height) = decoder.dimensions();
+    let samples = u64::from(width) * u64::from(height) * u64::from(P::CHANNEL_COUNT);
+    let sample_size = core::mem::size_of::<P::Subpixel>() as u64;
+    limits.check(
+        (width, height),
+        decoder.total_bytes().max(samples * sample_size),
+    )?;
+    let image = DynamicImage::from_decoder(decoder)?;
+    Ok(P::from_dynamic(image).to_ndarray())
+}
+
+/// Save the `(y, x, z)` array of 16 bit samples as an image file at `path`.
+///
+/// The format is derived from the extension of `path`. Every sample is written with its
+/// full 16 bits, formats that can not store 16 bit samples, like jpeg, return an error
+/// instead of silently reducing the depth.
+///
+/// Returns [`Error::UnsupportedChannels`] if the array does not have 1 to 4 channels,
+/// [`Error::Unsupported`] if the format can not store 16 bit samples, [`Error::Io`] if the
+/// file could not be written and [`Error::Encode`] if the encoder failed.
+pub fn save_ndarray_u16(path: impl AsRef<Path>, array: ArrayView3<'_, u16>) -> Result<()> {
+    span!(INFO, "save_ndarray_u16", path = ?path.as_ref(), shape = ?array.dim());
+    let result = match array.dim().2 {
+        1 => array.to_image::<Luma<u16>>()?.save(path),
+        2 => array.to_image::<LumaA<u16>>()?.save(path),
+        3 => array.to_image::<Rgb<u16>>()?.save(path),
+        4 => array.to_image::<Rgba<u16>>()?.save(path),
+        channels => return Err(Error::UnsupportedChannels(channels)),
+    };
+    result.map_err(Error::from)
+}
+
+/// Sample types of the arrays that can be saved with [`save_ndarray`].
+pub trait SaveSample:
+    NormalizedFloat<Self> + AsPrimitive<f32> + AsPrimitive<f64> + Sync + sealed::Sealed
+{
+    /// Sample type of the array.
+    const SAMPLE_TYPE: SampleType;
+}
+
+impl sealed::Sealed for u8 {}
+impl SaveSample for u8 {
+    const SAMPLE_TYPE: SampleType = SampleType::U8;
+}
+
+impl sealed::Sealed for u16 {}
+impl SaveSample for u16 {
+    const SAMPLE_TYPE: SampleType = SampleType::U16;
+}
+
+impl sealed::Sealed for f32 {}
+impl SaveSample for f32 {
+    const SAMPLE_TYPE: SampleType = SampleType::F32;
+}
+
+/// Save the `(y, x, z)` array as an image file at `path`, converting the samples if needed.
+///
+/// The format is derived from the extension of `path`, see [`save_ndarray_with_format`]
+/// for how the samples are converted.
+pub fn save_ndarray<T: SaveSample>(
+    path: impl AsRef<Path>,
+    array: ArrayView3<'_, T>,
+    rounding: Rounding,
+) -> Result<()> {
+    let format = ImageFormat::from_path(path.as_ref())?;
+    save_ndarray_with_format(path, array, format, rounding)
+}
+
+/// Save the `(y, x, z)` array as an image file of the `format` at `path`, converting the
+/// samples if needed.
+///
+/// The samples are stored as is when the format supports their type. Otherwise they are
+/// normalized into the most precise type the format can store, using `rounding` for integer
+/// targets and clamping values outside of the range. For example an f32 array is saved as
+/// 16 bit png and a u16 array as 8 bit jpeg.
+///
+/// Returns [`Error::UnsupportedChannels`] if the format can not store the channel count
+/// of the array and [`Error::Unsupported`] if the format can not be written at all.
+pub fn save_ndarray_with_format<T: SaveSample>(
+    path: impl AsRef<Path>,
+    array: ArrayView3<'_, T>,
+    format: ImageFormat,
+    rounding: Rounding,
+) -> Result<()> {
+    span!(INFO, "save_ndarray", path = ?path.as_ref(), shape = ?array.dim(), dtype = core::any::type_name::<T>());
+    Ok(encodable_image(array, format, rounding)?.save_with_format(path, format)?)
+}
+
+/// Encode the `(y, x, z)` array as an image file of the `format` in memory.
+///
+/// The samples are converted like [`save_ndarray_with_format`] does, so the bytes are the
+/// same as the file it would write:
+///
+/// ```
+/// use image::ImageFormat;
+/// use image_ndarray::{Rounding, encode_ndarray};
+/// use ndarray::Array3;
+///
+/// let array = Array3::<u8>::zeros((8, 16, 3));
+/// let png = encode_ndarray(array.view(), ImageFormat::Png, Rounding::Nearest)?;
+/// assert!(png.starts_with(b"\x89PNG"));
+/// # Ok::<(), image_ndarray::Error>(())
+/// ```
+pub fn encode_ndarray<T: SaveSample>(
+    array: ArrayView3<'_, T>,
+    format: ImageFormat,
+    rounding: Rounding,
+) -> Result<Vec<u8>> {
+    let mut data = Cursor::new(Vec::new());
+    write_ndarray(&mut data, array, format, rounding)?;
+    Ok(data.into_inner())
+}
+
+/// Encode the `(y, x, z)` array as an image file of the `format` into `writer`.
+///
+/// Some encoders of the image crate seek back to fill in headers, so the writer has to
+/// implement [`Seek`], like a [`Cursor`] or a [`File`](std::fs::File). For writers without
+/// seeking, like a socket, write the bytes of [`encode_ndarray`] instead.
+///
+/// The samples are converted like [`save_ndarray_with_format`] does.
+pub fn write_ndarray<T: SaveSample, W: Write + Seek>(
+    writer: &mut W,
+    array: ArrayView3<'_, T>,
+    format: ImageFormat,
+    rounding: Rounding,
+) -> Result<()> {
+    span!(INFO, "write_ndarray", format = ?format, shape = ?array.dim(), dtype = core::any::type_name::<T>());
+    Ok(encodable_image(array, format, rounding)?.write_to(writer, format)?)
+}
+
+/// Convert the array into an image of a sample type the format can store.
+fn encodable_image<T: SaveSample>(
+    array: ArrayView3<'_, T>,
+    format: ImageFormat,
+    rounding: Rounding,
+) -> Result<DynamicImage> {
+    let capabilities = Capabilities::for_format(format);
+    if !capabilities.can_write() {
+        return Err(Error::Unsupported(ImageError::Unsupported(
+            UnsupportedError::from_format_and_kind(
+                format.into(),
+                UnsupportedErrorKind::Format(format.into()),
+            ),
+        )));
+    }
+    let channels = array.dim().2;
+    let preference = match T::SAMPLE_TYPE {
+        SampleType::U8 => [SampleType::U8, SampleType::U16, SampleType::F32],
+        SampleType::U16 => [SampleType::U16, SampleType::F32, SampleType::U8],
+        SampleType::F32 => [SampleType::F32, SampleType::U16, SampleType::U8],
+    };
+    let target = preference
+        .into_iter()
+        .find(|sample| capabilities.can_write_array(*sample, channels))
+        .ok_or(Error::UnsupportedChannels(channels))?;
+
+    let options = ConvertOptions::new().normalize(true).rounding(rounding);
+    match (target, channels) {
+        (SampleType::U8, 1) => converted_image::<_, Luma<u8>>(array, &options),
+        (SampleType::U8, 2) => converted_image::<_, LumaA<u8>>(array, &options),
+        (SampleType::U8, 3) => converted_image::<_, Rgb<u8>>(array, &options),
+        (SampleType::U8, 4) => converted_image::<_, Rgba<u8>>(array, &options),
+        (SampleType::U16, 1) => converted_image::<_, Luma<u16>>(array, &options),
+        (SampleType::U16, 2) => converted_image::<_, LumaA<u16>>(array, &options),
+        (SampleType::U16, 3) => converted_image::<_, Rgb<u16>>(array, &options),
+        (SampleType::U16, 4) => converted_image::<_, Rgba<u16>>(array, &options),
+        (SampleType::F32, 3) => converted_image::<_, Rgb<f32>>(array, &options),
+        (SampleType::F32, 4) => converted_image::<_, Rgba<f32>>(array, &options),
+        _ => Err(Error::UnsupportedChannels(channels)),
+    }
+}
+
+/// Convert the array into an image of the pixel `P`.
+fn converted_image<T: SaveSample, P>(
+    array: ArrayView3<'_, T>,
+    options: &ConvertOptions,
+) -> Result<DynamicImage>
+where
+    P: Pixel,
+    P::Subpixel: ConvertSample,
+    DynamicImage: From<ImageBuffer<P, Vec<P::Subpixel>>>,
+{
+    let converted = array.convert_with::<P::Subpixel>(options)?;
+    Ok(converted.into_image::<P>()?.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, GrayImage, ImageBuffer, ImageFormat, RgbImage};
+    use rstest::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_decode_into() {
+        let test_image = RgbImage::from_fn(16, 8, |x, y| Rgb([x as u8, y as u8, 100]));
+        let reader = encode_test_image(test_image.clone());
+        let mut array = Array3::<u8>::zeros((8, 16, 3));
+        let pointer = array.as_ptr();
+
+        decode_into(reader, array.view_mut()).unwrap();
+
+        assert_eq!(array.as_ptr(), pointer);
+        assert_eq!(array, test_image.as_ndarray());
+    }
+
+    #[test]
+    fn test_decode_into_u16() {
+        let test_image = ImageBuffer::<Rgba<u16>, Vec<u16>>::from_fn(16, 8, |x, y| {
+            Rgba([x as u16 * 1000, y as u16 * 1000, 0, u16::MAX])
+        });
+        let reader = encode_test_image(test_image.clone());
+        let mut array = Array3::<u16>::zeros((8, 16, 4));
+
+        decode_into(reader, array.view_mut()).unwrap();
+
+        assert_eq!(array, test_image.as_ndarray());
+    }
+
+    #[test]
+    fn test_decode_into_errors() {
+        let test_image = RgbImage::new(16, 8);
+
+        let mut array = Array3::<u8>::zeros((16, 8, 3));
+        let result = decode_into(encode_test_image(test_image.clone()), array.view_mut());
+        assert_eq!(
+            result,
+            Err(Error::ShapeMismatch {
+                expected: (8, 16, 3),
+                got: (16, 8, 3)
+            })
+        );
+
+        let mut array = Array3::<u8>::zeros((8, 16, 4));
+        let result = decode_into(encode_test_image(test_image.clone()), array.view_mut());
+        assert_eq!(
+            result,
+            Err(Error::ChannelMismatch {
+                expected: 3,
+                got: 4
+            })
+        );
+
+        let mut array = Array3::<u16>::zeros((8, 16, 3));
+        let result = decode_into(encode_test_image(test_image.clone()), array.view_mut());
+        assert_eq!(result, Err(Error::SampleMismatch));
+
+        let mut array = Array3::<u8>::zeros((16, 8, 3));
+        let result = decode_into(
+            encode_test_image(test_image.clone()),
+            array.view_mut().permuted_axes([1, 0, 2]),
+        );
+        assert_eq!(result, Err(Error::NotContiguous));
+    }
+
+    #[test]
+    fn test_u16_round_trip() {
+        let path = test_path("u16_round_trip.png");
+        let array =
+            Array3::from_shape_fn((8, 16, 3), |(y, x, c)| (y * 4099 + x * 257 + c * 13) as u16);
+
+        save_ndarray_u16(&path, array.view()).unwrap();
+        let result = load_ndarray_u16(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(result, array);
+    }
+
+    #[test]
+    fn test_load_u8_as_u16() {
+        let path = test_path("load_u8_as_u16.png");
+        let test_image = GrayImage::from_fn(16, 8, |x, y| Luma([(y * 16 + x) as u8]));
+        test_image.save(&path).unwrap();
+
+        let result = load_ndarray_u16(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(result.dim(), (8, 16, 1));
+        assert_eq!(
+            result,
+            test_image.as_ndarray().mapv(|value| value as u16 * 257)
+        );
+    }
+
+    #[test]
+    fn test_load_with_limits() {
+        let path = test_path("load_with_limits.png");
+        RgbImage::new(16, 8).save(&path).unwrap();
+
+        let pixels = load_ndarray_u16_with_limits(&path, &DecodeLimits::new().max_pixels(127));
+        let bytes = load_ndarray_u16_with_limits(&path, &DecodeLimits::new().max_bytes(767));
+        let fitting = load_ndarray_u16_with_limits(
+            &path,
+            &DecodeLimits::unlimited().max_pixels(128).max_bytes(768),
+        );
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(pixels, Err(Error::LimitsExceeded));
+        assert_eq!(bytes, Err(Error::LimitsExceeded));
+        assert_eq!(fitting.unwrap().dim(), (8, 16, 3));
+    }
+
+    #[test]
+    fn test_open_as() {
+        let path = test_path("open_as.png");
+        let test_image = GrayImage::from_fn(16, 8, |x, y| Luma([(y * 16 + x) as u8]));
+        test_image.save(&path).unwrap();
+
+        let gray = open_as::<Luma<u8>>(&path).unwrap();
+        let rgba = open_as::<Rgba<u16>>(&path).unwrap();
+        let rgb = open_as::<Rgb<f32>>(&path).unwrap();
+        let missing = open_as::<Rgb<u8>>(test_path("missing_open_as.png"));
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(gray, test_image.as_ndarray());
+        assert_eq!(rgba.dim(), (8, 16, 4));
+        assert_eq!(rgba[[2, 5, 0]], 37 * 257);
+        assert_eq!(rgba[[2, 5, 3]], u16::MAX);
+        assert!((rgb[[7, 15, 1]] - 127.0 / 255.0).abs() < 1e-6);
+        assert!(matches!(missing, Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn test_open_as_with_limits() {
+        let path = test_path("open_as_with_limits.png");
+        GrayImage::new(16, 8).save(&path).unwrap();
+
+        let pixels = open_as_with_limits::<Luma<u8>>(&path, &DecodeLimits::new().max_pixels(127));
+        let gray = open_as_with_limits::<Luma<u8>>(&path, &DecodeLimits::new().max_bytes(128));
+        let rgba = open_as_with_limits::<Rgba<u16>>(&path, &DecodeLimits::new().max_bytes(1023));
+        let fitting = open_as_with_limits::<Rgba<u16>>(
+            &path,
+            &DecodeLimits::unlimited().max_pixels(128).max_bytes(1024),
+        );
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(pixels, Err(Error::LimitsExceeded));
+        assert_eq!(gray.unwrap().dim(), (8, 16, 1));
+        assert_eq!(rgba, Err(Error::LimitsExceeded));
+        assert_eq!(fitting.unwrap().dim(), (8, 16, 4));
+    }
+
+    #[test]
+    fn test_save_u16_errors() {
+        let array = Array3::<u16>::zeros((8, 16, 5));
+        let result = save_ndarray_u16(test_path("five_channels.png"), array.view());
+        assert_eq!(result, Err(Error::UnsupportedChannels(5)));
+
+        let array = Array3::<u16>::zeros((8, 16, 3));
+        let result = save_ndarray_u16(test_path("unsupported.jpg"), array.view());
+        assert!(matches!(result, Err(Error::Unsupported(_))));
+
+        let result = save_ndarray_u16(test_path("missing/directory.png"), array.view());
+        assert!(matches!(
+            result,
+            Err(Error::Io(error)) if error.kind() == std::io::ErrorKind::NotFound
+        ));
+
+        let result = load_ndarray_u16(test_path("missing.png"));
+        assert!(matches!(
+            result,
+            Err(Error::Io(error)) if error.kind() == std::io::ErrorKind::NotFound
+        ));
+    }
+
+    #[rstest]
+    #[case(Rounding::Nearest, 32768)]
+    #[case(Rounding::Floor, 32767)]
+    fn test_save_f32_as_u16(#[case] rounding: Rounding, #[case] expected: u16) {
+        let path = test_path(&format!("save_f32_{expected}.png"));
+        let mut array = Array3::from_elem((8, 16, 4), 0.5f32);
+        array[[0, 0, 0]] = 1.5;
+
+        save_ndarray(&path, array.view(), rounding).unwrap();
+        let result = load_ndarray_u16(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(result.dim(), (8, 16, 4));
+        assert_eq!(result[[0, 0, 0]], u16::MAX);
+        assert_eq!(result[[7, 15, 3]], expected);
+    }
+
+    #[test]
+    fn test_save_u8() {
+        let path = test_path("save_u8.png");
+        let array = Array3::from_shape_fn((8, 16, 2), |(y, x, z)| (y * 32 + x * 2 + z) as u8);
+
+        save_ndarray_with_format(&path, array.view(), ImageFormat::Png, Rounding::Nearest).unwrap();
+        let result = open_as::<LumaA<u8>>(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(result, array);
+    }
+
+    #[test]
+    fn test_save_errors() {
+        let array = Array3::<u8>::zeros((8, 16, 5));
+        let result = save_ndarray(test_path("save_five.png"), array.view(), Rounding::Nearest);
+        assert_eq!(result, Err(Error::UnsupportedChannels(5)));
+
+        let array = Array3::<u8>::zeros((8, 16, 3));
+        let result = save_ndarray(test_path("save.jpg"), array.view(), Rounding::Nearest);
+        assert!(matches!(result, Err(Error::Unsupported(_))));
+
+        let result = save_ndarray(test_path("save.unknown"), array.view(), Rounding::Nearest);
+        assert!(matches!(result, Err(Error::Unsupported(_))));
+    }
+
+    #[test]
+    fn test_encode_ndarray() {
+        let array = Array3::from_shape_fn((8, 16, 3), |(y, x, z)| (y * 1000 + x * 100 + z) as u16);
+
+        let data = encode_ndarray(array.view(), ImageFormat::Png, Rounding::Nearest).unwrap();
+        let mut written = Cursor::new(Vec::new());
+        write_ndarray(
+            &mut written,
+            array.view(),
+            ImageFormat::Png,
+            Rounding::Nearest,
+        )
+        .unwrap();
+        let mut result = Array3::<u16>::zeros((8, 16, 3));
+        decode_into(
+            ImageReader::with_format(Cursor::new(&data), ImageFormat::Png),
+            result.view_mut(),
+        )
+        .unwrap();
+
+        assert_eq!(written.into_inner(), data);
+        assert_eq!(result, array);
+
+        let result = encode_ndarray(array.view(), ImageFormat::Jpeg, Rounding::Nearest);
+        assert!(matches!(result, Err(Error::Unsupported(_))));
+    }
+
+    /// Unique path in the temporary directory for the test file.
+    fn test_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("image-ndarray-{}-{name}", std::process::id()))
+    }
+
+    /// Encode the image as png and return a reader for it.
+    fn encode_test_image(image: impl Into<DynamicImage>) -> ImageReader<Cursor<Vec<u8>>> {
+        let mut data = Cursor::new(Vec::new());
+        image.into().write_to(&mut data, ImageFormat::Png).unwrap();
+        data.set_position(0);
+        ImageReader::with_format(data, ImageFormat::Png)
+    }
+}
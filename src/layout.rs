@@ -0,0 +1,231 @@
+//! Conversions between the interleaved `(y, x, z)` layout and planar layouts
+
+use crate::error::Result;
+use crate::parallel;
+use crate::paranoid::paranoid;
+use crate::standardize::{check_channels, standardize_sample};
+use crate::trace::span;
+use crate::traits::NormalizedFloat;
+use ndarray::{Array3, ArrayView2, ArrayView3, ArrayViewMut2, Axis, Zip, s};
+use num_traits::{AsPrimitive, Zero};
+
+/// Order of the axes of the converted array.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChannelLayout {
+    /// Channels last, indexed as `array[[y, x, z]]`.
+    #[default]
+    Hwc,
+    /// Channels first, indexed as `array[[z, y, x]]`.
+    Chw,
+}
+
+/// Bytes of a single tile used when transposing, so a tile of both the source and the
+/// target fits in an L1 cache of 32 KiB.
+const TILE_BYTES: usize = 16 * 1024;
+
+/// Side of the square tiles of `T` used when transposing, 64 for 4 byte samples.
+fn block_size<T>() -> usize {
+    (TILE_BYTES / core::mem::size_of::<T>().max(1)).isqrt()
+}
+
+/// Transpose an interleaved `(y, x, z)` array into a planar `(z, y, x)` array.
+///
+/// The copy is done in cache sized tiles, which is considerably faster on large images
+/// than `permuted_axes([2, 0, 1]).as_standard_layout()`.
+/// With the `rayon` feature, the tiles of large arrays are copied in parallel.
+pub fn hwc_to_chw<T>(array: ArrayView3<'_, T>) -> Array3<T>
+where
+    T: Clone + Zero + Send + Sync,
+{
+    span!(DEBUG, "hwc_to_chw", shape = ?array.dim(), dtype = core::any::type_name::<T>());
+    let (height, width, channels) = array.dim();
+    let mut output = Array3::zeros((channels, height, width));
+    if let (Ok(source), Ok(target)) = (
+        array.into_shape_with_order((height * width, channels)),
+        output
+            .view_mut()
+            .into_shape_with_order((channels, height * width)),
+    ) {
+        transpose_into(source, target);
+        return output;
+    }
+    for (y, row) in array.outer_iter().enumerate() {
+        transpose_into(row, output.slice_mut(s![.., y, ..]));
+    }
+    output
+}
+
+/// Transpose a planar `(z, y, x)` array into an interleaved `(y, x, z)` array.
+///
+/// This is the inverse of [`hwc_to_chw`] and uses the same tiled copy.
+pub fn chw_to_hwc<T>(array: ArrayView3<'_, T>) -> Array3<T>
+where
+    T: Clone + Zero + Send + Sync,
+{
+    span!(DEBUG, "chw_to_hwc", shape = ?array.dim(), dtype = core::any::type_name::<T>());
+    let (channels, height, width) = array.dim();
+    let mut output = Array3::zeros((height, width, channels));
+    if let (Ok(source), Ok(target)) = (
+        array.into_shape_with_order((channels, height * width)),
+        output
+            .view_mut()
+            .into_shape_with_order((height * width, channels)),
+    ) {
+        transpose_into(source, target);
+        return output;
+    }
+    for (y, row) in output.outer_iter_mut().enumerate() {
+        transpose_into(array.slice(s![.., y, ..]), row);
+    }
+    output
+}
+
+/// Copy the transpose of `source` into `target` one tile at a time.
+///
+/// With the `rayon` feature, bands of tiles of large arrays are copied in parallel.
+fn transpose_into<T>(source: ArrayView2<'_, T>, target: ArrayViewMut2<'_, T>)
+where
+    T: Clone + Send + Sync,
+{
+    let block = block_size::<T>();
+    parallel::for_each_band(source, target, block, |source, mut target| {
+        let (rows, columns) = source.dim();
+        for row in (0..rows).step_by(block) {
+            let rows = row..(row + block).min(rows);
+            for column in (0..columns).step_by(block) {
+                let columns = column..(column + block).min(columns);
+                target
+                    .slice_mut(s![columns.clone(), rows.clone()])
+                    .assign(&source.slice(s![rows.clone(), columns]).t());
+            }
+        }
+    });
+}
+
+/// Normalize, standardize and transpose an interleaved `(y, x, z)` array into a planar
+/// `(z, y, x)` float array in a single pass over memory.
+///
+/// Every value is converted with [`NormalizedFloat::to_f32_normalized`], after which the
+/// channel `mean` is subtracted and the result is divided by the channel `std`:
+///
+/// `output[[z, y, x]] = (normalized(array[[y, x, z]]) - mean[z]) / std[z]`
+///
+/// Values that can not be represented result in NaN. Use
+/// [`standardize`](crate::standardize) to keep the interleaved layout.
+///
+/// Returns [`Error::ChannelMismatch`](crate::Error::ChannelMismatch) if the array does not
+/// have `C` channels.
+pub fn hwc_to_chw_normalized<T, const C: usize>(
+    array: ArrayView3<'_, T>,
+    mean: [f32; C],
+    std: [f32; C],
+) -> Result<Array3<f32>>
+where
+    T: NormalizedFloat<T> + AsPrimitive<f32> + AsPrimitive<f64>,
+{
+    span!(DEBUG, "hwc_to_chw_normalized", shape = ?array.dim(), dtype = core::any::type_name::<T>());
+    let (height, width, channels) = array.dim();
+    check_channels(C, channels)?;
+    paranoid! {
+        crate::paranoid::check_normalized_range(&array);
+    }
+
+    let mut output = Array3::<f32>::zeros((channels, height, width));
+    let mut interleaved = output.view_mut().permuted_axes([1, 2, 0]);
+    Zip::from(interleaved.lanes_mut(Axis(2)))
+        .and(array.lanes(Axis(2)))
+        .for_each(|mut output, pixel| {
+            for (channel, (output, value)) in output.iter_mut().zip(pixel).enumerate() {
+                *output = standardize_sample(*value, mean[channel], std[channel]);
+            }
+        });
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    #[test]
+    fn test_hwc_to_chw() {
+        let array = Array3::from_shape_fn((100, 150, 3), |(y, x, c)| y * 1000 + x * 10 + c);
+
+        let result = hwc_to_chw(array.view());
+
+        assert!(result.is_standard_layout());
+        assert_eq!(result, array.view().permuted_axes([2, 0, 1]));
+        assert_eq!(chw_to_hwc(result.view()), array);
+    }
+
+    #[test]
+    fn test_hwc_to_chw_large() {
+        let array =
+            Array3::from_shape_fn((300, 257, 3), |(y, x, c)| (y * 1000 + x * 10 + c) as f64);
+
+        let result = hwc_to_chw(array.view());
+
+        assert_eq!(block_size::<f64>(), 45);
+        assert_eq!(result, array.view().permuted_axes([2, 0, 1]));
+        assert_eq!(chw_to_hwc(result.view()), array);
+    }
+
+    #[test]
+    fn test_hwc_to_chw_non_standard_layout() {
+        let array = Array3::from_shape_fn((150, 100, 4), |(x, y, c)| y * 1000 + x * 10 + c);
+        let transposed = array.view().permuted_axes([1, 0, 2]);
+
+        let result = hwc_to_chw(transposed);
+
+        assert_eq!(result, transposed.permuted_axes([2, 0, 1]));
+        let planar = result.view().permuted_axes([0, 2, 1]);
+        assert_eq!(chw_to_hwc(planar), planar.permuted_axes([1, 2, 0]));
+    }
+
+    #[test]
+    fn test_hwc_to_chw_normalized() {
+        let (height, width, channels) = (8, 16, 3);
+        let array = Array3::from_shape_fn((height, width, channels), |(y, x, c)| {
+            (y * 16 + x + c * 50) as u8
+        });
+        let (mean, std) = ([0.485, 0.456, 0.406], [0.229, 0.224, 0.225]);
+
+        let result = hwc_to_chw_normalized(array.view(), mean, std).unwrap();
+
+        assert_eq!(result.dim(), (channels, height, width));
+        for ((c, y, x), value) in result.indexed_iter() {
+            let expected = (array[[y, x, c]] as f32 / 255.0 - mean[c]) / std[c];
+            assert_eq!(*value, expected);
+        }
+    }
+
+    #[test]
+    fn test_hwc_to_chw_normalized_non_standard_layout() {
+        let array = Array3::from_shape_fn((16, 8, 2), |(x, y, c)| (y * 16 + x + c) as u16);
+        let transposed = array.view().permuted_axes([1, 0, 2]);
+
+        let result = hwc_to_chw_normalized(transposed, [0.0, 0.5], [1.0, 2.0]).unwrap();
+
+        for ((c, y, x), value) in result.indexed_iter() {
+            let expected = (transposed[[y, x, c]] as f32 / 65535.0 - [0.0, 0.5][c]) / [1.0, 2.0][c];
+            assert_eq!(*value, expected);
+        }
+    }
+
+    #[test]
+    fn test_hwc_to_chw_normalized_with_invalid_channels() {
+        let array = Array3::<u8>::zeros((8, 16, 3));
+
+        let result = hwc_to_chw_normalized(array.view(), [0.0; 4], [1.0; 4])
+            .err()
+            .unwrap();
+
+        assert_eq!(
+            result,
+            Error::ChannelMismatch {
+                expected: 4,
+                got: 3
+            }
+        );
+    }
+}
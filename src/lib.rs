@@ -1,7 +1,107 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 #![doc = include_str!("../README.md")]
+#[cfg(feature = "image")]
+mod array_image;
+#[cfg(feature = "image")]
+mod capabilities;
+#[cfg(feature = "image")]
+mod channels;
+#[cfg(feature = "image")]
+mod convert;
+mod depth;
+mod dither;
 mod error;
+#[cfg(feature = "image")]
+mod flat;
+#[cfg(feature = "imgref")]
+mod imgref_interop;
+#[cfg(feature = "image")]
+mod io;
+mod layout;
+#[cfg(feature = "std")]
+mod lut;
+#[cfg(feature = "nalgebra")]
+mod nalgebra_interop;
+mod normalize;
+mod parallel;
+mod paranoid;
+#[cfg(feature = "std")]
+mod raw_parts;
+#[cfg(feature = "rgb")]
+mod rgb_interop;
+#[cfg(feature = "std")]
+mod sanitize;
+#[cfg(feature = "simd")]
+mod simd;
+mod standardize;
+#[cfg(feature = "std")]
+mod tone_map;
+mod trace;
 mod traits;
+#[cfg(feature = "std")]
+mod transfer;
+#[cfg(feature = "image")]
+mod typed_array;
+mod volume;
 
-pub use error::Error;
+#[cfg(feature = "image")]
+pub use array_image::ArrayImage;
+#[cfg(feature = "image")]
+pub use capabilities::{Capabilities, SampleType};
+#[cfg(feature = "image")]
+pub use channels::{AlphaMode, ChannelCount, premultiply_alpha, unpremultiply_alpha};
+#[cfg(feature = "image")]
+pub use convert::ConvertOptions;
+pub use depth::{
+    Justification, denormalize_depth, detect_channel_depths, detect_effective_depth,
+    normalize_depth, scale_depth, scale_from_depth, scale_to_depth, scale_u8_to_u16,
+    scale_u16_to_u8,
+};
+pub use dither::{Dither, quantize_dithered};
+pub use error::{Error, NormalizeError};
+#[cfg(feature = "image")]
+pub use io::{
+    DecodeLimits, OpenPixel, SaveSample, decode_into, encode_ndarray, load_ndarray_u16,
+    load_ndarray_u16_with_limits, open_as, open_as_with_limits, save_ndarray, save_ndarray_u16,
+    save_ndarray_with_format, write_ndarray,
+};
+pub use layout::{ChannelLayout, chw_to_hwc, hwc_to_chw, hwc_to_chw_normalized};
+#[cfg(feature = "std")]
+pub use lut::{LutSample, NormalizeLut};
+#[cfg(feature = "std")]
+pub use normalize::quantize_owned;
+pub use normalize::{Clamping, ConvertSample, Rounding, SignedNormalization};
+#[cfg(feature = "std")]
+pub use raw_parts::RawParts;
+#[cfg(feature = "rgb")]
+pub use rgb_interop::RgbPixel;
+#[cfg(feature = "std")]
+pub use sanitize::{Sanitize, find_non_finite, sanitize};
+pub use standardize::{
+    denormalize_channels, destandardize, destandardize_batch, normalize_channels, standardize,
+    standardize_batch,
+};
+#[cfg(feature = "std")]
+pub use tone_map::{ToneMap, tone_map, tone_map_in_place, tone_map_quantized};
+#[cfg(feature = "std")]
+pub use transfer::{
+    apply_gamma, apply_gamma_in_place, linear_to_srgb, linear_to_srgb_in_place,
+    linear_to_srgb_quantized, remove_gamma, remove_gamma_in_place, srgb_to_linear,
+    srgb_to_linear_in_place, srgb_to_linear_normalized,
+};
+#[cfg(feature = "image")]
+pub use typed_array::{
+    LumaAArrayView, LumaAArrayViewMut, LumaArrayView, LumaArrayViewMut, PixelArrayView,
+    PixelArrayViewMut, RgbArrayView, RgbArrayViewMut, RgbaArrayView, RgbaArrayViewMut, TypedArray3,
+};
+pub use volume::{
+    Projection, Volume, pixel_timeseries, project, project_max, project_sum, region_timeseries,
+};
 pub mod prelude;
+
+/// Items used by the expansion of [`impl_as_float`], not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::normalize::{normalize_signed, round_to, scale_signed};
+    pub use num_traits::ToPrimitive;
+}
@@ -0,0 +1,176 @@
+//! Lookup tables for the normalization of 8 and 16 bit samples
+
+use crate::transfer::srgb_to_linear;
+use core::marker::PhantomData;
+use ndarray::{Array3, ArrayView3};
+use std::sync::OnceLock;
+
+/// Integer sample that can index a lookup table with an entry for every value.
+///
+/// This trait is sealed and implemented for u8 and u16.
+pub trait LutSample: Copy + sealed::Sealed {
+    /// Amount of entries in the table.
+    const SIZE: usize;
+
+    /// Position of the value in the table.
+    fn index(self) -> usize;
+}
+
+mod sealed {
+    pub trait Sealed {
+        /// Table of [`NormalizeLut::linear`], built on first use and shared by every
+        /// conversion afterwards.
+        fn linear_table() -> &'static [f32];
+    }
+}
+
+macro_rules! impl_lut_sample {
+    ($type:ty) => {
+        impl sealed::Sealed for $type {
+            fn linear_table() -> &'static [f32] {
+                static TABLE: OnceLock<NormalizeLut<$type>> = OnceLock::new();
+                &TABLE.get_or_init(NormalizeLut::linear).table
+            }
+        }
+
+        impl LutSample for $type {
+            const SIZE: usize = 1 << <$type>::BITS;
+
+            fn index(self) -> usize {
+                self.into()
+            }
+        }
+    };
+}
+
+impl_lut_sample!(u8);
+impl_lut_sample!(u16);
+
+/// Normalize the samples into `output` with the shared linear table.
+///
+/// `output` needs to have the length of `values`. Gives the same results as dividing by
+/// the maximum value. Used as the scalar kernel of
+/// [`NormalizedFloat::to_f32_normalized_slice`] for u8 and u16, so it never fails.
+///
+/// [`NormalizedFloat::to_f32_normalized_slice`]: crate::traits::NormalizedFloat::to_f32_normalized_slice
+#[cfg(not(feature = "simd"))]
+pub(crate) fn normalize_slice<T: LutSample>(
+    values: &[T],
+    output: &mut [f32],
+) -> core::result::Result<(), usize> {
+    debug_assert_eq!(values.len(), output.len());
+    let table = T::linear_table();
+    for (value, output) in values.iter().zip(output) {
+        *output = table[value.index()];
+    }
+    Ok(())
+}
+
+/// Lookup table from integer samples to normalized 32 bit floats.
+///
+/// Every possible value is converted once when the table is built, so converting an
+/// image is a single lookup per sample instead of a division or a transfer curve.
+/// The table has 256 entries for u8 and 65536 entries for u16.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NormalizeLut<T> {
+    table: Vec<f32>,
+    sample: PhantomData<T>,
+}
+
+impl<T: LutSample> NormalizeLut<T> {
+    /// Build a table that applies `curve` to the normalized value of every sample.
+    ///
+    /// For example in u8, the curve receives 1.0 for a value of 255.
+    pub fn from_fn(curve: impl Fn(f32) -> f32) -> Self {
+        let max = (T::SIZE - 1) as f32;
+        Self {
+            table: (0..T::SIZE)
+                .map(|index| curve(index as f32 / max))
+                .collect(),
+            sample: PhantomData,
+        }
+    }
+
+    /// Build a table that normalizes the samples, matching `to_f32_normalized`.
+    pub fn linear() -> Self {
+        Self::from_fn(|value| value)
+    }
+
+    /// Build a table that decodes sRGB encoded samples into normalized linear light.
+    pub fn srgb() -> Self {
+        Self::from_fn(srgb_to_linear)
+    }
+
+    /// Look up the converted value of a single sample.
+    pub fn get(&self, value: T) -> f32 {
+        self.table[value.index()]
+    }
+
+    /// Convert every sample of the `(y, x, z)` array.
+    pub fn apply(&self, array: ArrayView3<'_, T>) -> Array3<f32> {
+        array.mapv(|value| self.get(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::NormalizedFloat;
+
+    #[test]
+    fn test_linear_matches_normalized() {
+        let lut = NormalizeLut::<u8>::linear();
+        for value in 0..=u8::MAX {
+            assert_eq!(lut.get(value), value.to_f32_normalized().unwrap());
+        }
+
+        let lut = NormalizeLut::<u16>::linear();
+        for value in (0..=u16::MAX).step_by(97) {
+            assert_eq!(lut.get(value), value.to_f32_normalized().unwrap());
+        }
+    }
+
+    #[cfg(not(feature = "simd"))]
+    #[test]
+    fn test_normalize_slice() {
+        let values = Vec::from_iter(0..=u8::MAX);
+        let mut output = vec![f32::NAN; values.len()];
+        normalize_slice(&values, &mut output).unwrap();
+        for (value, output) in values.iter().zip(output) {
+            assert_eq!(output, value.to_f32_normalized().unwrap());
+        }
+
+        let values = Vec::from_iter(0..=u16::MAX);
+        let mut output = vec![f32::NAN; values.len()];
+        normalize_slice(&values, &mut output).unwrap();
+        for (value, output) in values.iter().zip(output) {
+            assert_eq!(output, value.to_f32_normalized().unwrap());
+        }
+        assert!(core::ptr::eq(
+            <u16 as sealed::Sealed>::linear_table(),
+            <u16 as sealed::Sealed>::linear_table()
+        ));
+    }
+
+    #[test]
+    fn test_srgb() {
+        let lut = NormalizeLut::<u8>::srgb();
+
+        assert_eq!(lut.get(0), 0.0);
+        assert_eq!(lut.get(255), 1.0);
+        assert!((lut.get(188) - 0.5029).abs() < 1e-4);
+        assert!((lut.get(5) - 5.0 / 255.0 / 12.92).abs() < 1e-7);
+    }
+
+    #[test]
+    fn test_apply_custom_curve() {
+        let lut = NormalizeLut::<u16>::from_fn(|value| 1.0 - value);
+        let array = Array3::from_shape_fn((4, 8, 3), |(y, x, z)| (y * 24 + x * 3 + z) as u16);
+
+        let result = lut.apply(array.view());
+
+        assert_eq!(result.dim(), (4, 8, 3));
+        assert_eq!(result[[0, 0, 0]], 1.0);
+        assert_eq!(result[[3, 7, 2]], 1.0 - 95.0 / u16::MAX as f32);
+    }
+}
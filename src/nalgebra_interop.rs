@@ -0,0 +1,208 @@
+//! Interoperability with the dynamically sized matrices of the nalgebra crate
+
+use crate::error::{Error, Result};
+#[cfg(feature = "image")]
+use image::{ImageBuffer, Luma, Pixel};
+use nalgebra::{DMatrix, Dyn, MatrixView, Scalar};
+use ndarray::{Array2, ArrayBase, ArrayView2, Data, Ix2, ShapeBuilder};
+
+/// Matrix view with dynamic shape and strides, as referenced from an ndarray.
+pub type DMatrixStridedView<'a, T> = MatrixView<'a, T, Dyn, Dyn, Dyn, Dyn>;
+
+/// Conversion methods from a `(y, x)` ndarray into nalgebra matrices.
+///
+/// The row of the matrix is the `Y` index and the column is the `X` index, so
+/// `matrix[(y, x)]` references the same value as `array[[y, x]]`.
+pub trait NdarrayMatrixExt<T: Scalar> {
+    /// Reference the array as a nalgebra matrix view, keeping the strides of the array.
+    ///
+    /// Returns [`Error::NotContiguous`] if the array has negative strides or gaps in memory.
+    ///
+    /// This does not copy the data, as it is a reference to the actual data in the array.
+    fn as_matrix_view(&self) -> Result<DMatrixStridedView<'_, T>>;
+
+    /// Copy the array into a nalgebra matrix.
+    fn to_dmatrix(&self) -> DMatrix<T>;
+}
+
+impl<S, T> NdarrayMatrixExt<T> for ArrayBase<S, Ix2>
+where
+    S: Data<Elem = T>,
+    T: Scalar,
+{
+    fn as_matrix_view(&self) -> Result<DMatrixStridedView<'_, T>> {
+        let (rows, columns) = self.dim();
+        let stride = |index: usize| usize::try_from(self.strides()[index]);
+        let (Ok(row_stride), Ok(column_stride)) = (stride(0), stride(1)) else {
+            return Err(Error::NotContiguous);
+        };
+        let data = self.as_slice_memory_order().ok_or(Error::NotContiguous)?;
+        Ok(MatrixView::from_slice_with_strides_generic(
+            data,
+            Dyn(rows),
+            Dyn(columns),
+            Dyn(row_stride),
+            Dyn(column_stride),
+        ))
+    }
+
+    fn to_dmatrix(&self) -> DMatrix<T> {
+        let (rows, columns) = self.dim();
+        DMatrix::from_fn(rows, columns, |y, x| self[[y, x]].clone())
+    }
+}
+
+/// Conversion methods from nalgebra matrices into `(y, x)` ndarrays.
+pub trait MatrixArray<T> {
+    /// Reference the matrix as an ArrayView2.
+    ///
+    /// * `Y` index is the row
+    /// * `X` index is the columns
+    ///
+    /// So when referencing:
+    /// `array[[y, x]]`
+    ///
+    /// The matrix is stored column major, so the view is in Fortran layout.
+    ///
+    /// This does not copy the data, as it is a reference to the actual data in the matrix.
+    fn as_ndarray(&self) -> ArrayView2<'_, T>;
+
+    /// Convert the matrix into an Array2 in Fortran layout.
+    ///
+    /// This does not copy the data, but it does consume the matrix.
+    fn into_ndarray(self) -> Array2<T>;
+
+    /// Copy the matrix into a grayscale ImageBuffer.
+    ///
+    /// Returns [`Error::ImageConstructFailed`] if the dimensions do not fit in an ImageBuffer.
+    #[cfg(feature = "image")]
+    fn to_luma_image(&self) -> Result<ImageBuffer<Luma<T>, Vec<T>>>
+    where
+        Luma<T>: Pixel<Subpixel = T>;
+}
+
+impl<T> MatrixArray<T> for DMatrix<T>
+where
+    T: Scalar,
+{
+    fn as_ndarray(&self) -> ArrayView2<'_, T> {
+        ArrayView2::from_shape(self.shape().f(), self.as_slice())
+            .expect("matrix storage always fits its shape")
+    }
+
+    fn into_ndarray(self) -> Array2<T> {
+        let shape = self.shape();
+        Array2::from_shape_vec(shape.f(), self.data.into())
+            .expect("matrix storage always fits its shape")
+    }
+
+    #[cfg(feature = "image")]
+    fn to_luma_image(&self) -> Result<ImageBuffer<Luma<T>, Vec<T>>>
+    where
+        Luma<T>: Pixel<Subpixel = T>,
+    {
+        let (rows, columns) = self.shape();
+        let width = u32::try_from(columns).or(Err(Error::ImageConstructFailed))?;
+        let height = u32::try_from(rows).or(Err(Error::ImageConstructFailed))?;
+        let data = self.transpose().data.into();
+        ImageBuffer::from_raw(width, height, data).ok_or(Error::ImageConstructFailed)
+    }
+}
+
+/// Conversion methods from grayscale ImageBuffers into nalgebra matrices.
+#[cfg(feature = "image")]
+pub trait LumaMatrix<T: Scalar> {
+    /// Reference the image as a nalgebra matrix view of `height` rows and `width` columns.
+    ///
+    /// This does not copy the data, as it is a reference to the actual data in the image.
+    fn as_matrix_view(&self) -> DMatrixStridedView<'_, T>;
+
+    /// Copy the image into a nalgebra matrix of `height` rows and `width` columns.
+    fn to_dmatrix(&self) -> DMatrix<T>;
+}
+
+#[cfg(feature = "image")]
+impl<T> LumaMatrix<T> for ImageBuffer<Luma<T>, Vec<T>>
+where
+    Luma<T>: Pixel<Subpixel = T>,
+    T: Scalar,
+{
+    fn as_matrix_view(&self) -> DMatrixStridedView<'_, T> {
+        let (width, height) = (self.width() as usize, self.height() as usize);
+        MatrixView::from_slice_with_strides_generic(
+            &self.as_raw()[..height * width],
+            Dyn(height),
+            Dyn(width),
+            Dyn(width),
+            Dyn(1),
+        )
+    }
+
+    fn to_dmatrix(&self) -> DMatrix<T> {
+        let (width, height) = (self.width() as usize, self.height() as usize);
+        DMatrix::from_row_slice(height, width, &self.as_raw()[..height * width])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::s;
+
+    #[test]
+    fn test_as_matrix_view() {
+        let array = Array2::from_shape_fn((8, 16), |(y, x)| (y * 16 + x) as f32);
+
+        let matrix = array.as_matrix_view().unwrap();
+
+        assert_eq!(matrix.shape(), (8, 16));
+        assert_eq!(matrix[(2, 3)], array[[2, 3]]);
+        assert_eq!(matrix.as_ptr(), array.as_ptr());
+
+        let transposed = array.t();
+        let matrix = transposed.as_matrix_view().unwrap();
+        assert_eq!(matrix.shape(), (16, 8));
+        assert_eq!(matrix[(3, 2)], array[[2, 3]]);
+        assert_eq!(matrix, array.to_dmatrix().transpose());
+    }
+
+    #[test]
+    fn test_as_matrix_view_errors() {
+        let array = Array2::<f32>::zeros((8, 16));
+
+        let flipped = array.slice(s![..;-1, ..]);
+        assert_eq!(flipped.as_matrix_view().err(), Some(Error::NotContiguous));
+
+        let stepped = array.slice(s![.., ..;2]);
+        assert_eq!(stepped.as_matrix_view().err(), Some(Error::NotContiguous));
+    }
+
+    #[test]
+    fn test_matrix_round_trip() {
+        let array = Array2::from_shape_fn((8, 16), |(y, x)| (y * 16 + x) as f64);
+
+        let matrix = array.to_dmatrix();
+        assert_eq!(matrix.as_ndarray(), array);
+
+        let pointer = matrix.as_ptr();
+        let result = matrix.into_ndarray();
+        assert_eq!(result, array);
+        assert_eq!(result.as_ptr(), pointer);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_luma_matrix() {
+        use image::GrayImage;
+
+        let test_image = GrayImage::from_fn(16, 8, |x, y| Luma([(y * 16 + x) as u8]));
+
+        let view = test_image.as_matrix_view();
+        assert_eq!(view.shape(), (8, 16));
+        assert_eq!(view[(2, 3)], 35);
+
+        let matrix = test_image.to_dmatrix();
+        assert_eq!(matrix, view);
+        assert_eq!(matrix.to_luma_image().unwrap(), test_image);
+    }
+}
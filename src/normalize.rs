@@ -0,0 +1,439 @@
+//! Conversions between normalized float arrays and other sample types
+
+#[cfg(feature = "std")]
+use crate::paranoid::paranoid;
+#[cfg(feature = "std")]
+use crate::trace::span;
+#[cfg(feature = "std")]
+use crate::traits::NormalizedFloat;
+#[cfg(feature = "std")]
+use ndarray::Array3;
+#[cfg(feature = "std")]
+use num_traits::Zero;
+use num_traits::float::FloatCore;
+use num_traits::{AsPrimitive, Bounded, PrimInt};
+
+/// Rounding applied when a scaled float is converted into an integer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Rounding {
+    /// Round to the nearest integer, with halfway cases rounded away from zero.
+    #[default]
+    Nearest,
+    /// Round towards negative infinity.
+    Floor,
+    /// Round towards positive infinity.
+    Ceil,
+    /// Round towards zero, which is what an `as` cast does.
+    Truncate,
+}
+
+/// Handling of values that fall outside of the range of the integer type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Clamping {
+    /// Saturate to the minimum or maximum value of the type. NaN becomes zero.
+    #[default]
+    Clamp,
+    /// Wrap around modulo the range of the type, like an integer `as` cast does.
+    Wrap,
+    /// Reject the value, so the conversion fails instead of silently changing it.
+    ///
+    /// NaN and infinite values are rejected as well.
+    Error,
+}
+
+/// Mapping between signed integers and normalized floats.
+///
+/// Unsigned integers map 0 to 0.0 and their maximum to 1.0 in every mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SignedNormalization {
+    /// Divide by the maximum value, so `i8::MIN` becomes slightly less than -1.0.
+    ///
+    /// Floats are converted back by multiplying with the maximum.
+    #[default]
+    Legacy,
+    /// Map onto `[-1.0, 1.0]`, with the minimum value treated as the negated maximum.
+    ///
+    /// Every value but the minimum makes an exact round trip, the minimum comes back as
+    /// the negated maximum.
+    Symmetric,
+    /// Map the minimum onto 0.0 and the maximum onto 1.0.
+    ///
+    /// Every value makes an exact round trip.
+    Offset,
+}
+
+/// Normalize an integer value of the range `min..=max` with the signed mode.
+pub fn normalize_signed(value: f64, min: f64, max: f64, signed: SignedNormalization) -> f64 {
+    match signed {
+        SignedNormalization::Legacy => value / max,
+        SignedNormalization::Symmetric => value.max(-max) / max,
+        SignedNormalization::Offset => (value - min) / (max - min),
+    }
+}
+
+/// Scale a normalized float to the range `min..=max` with the signed mode, without rounding.
+pub fn scale_signed(value: f64, min: f64, max: f64, signed: SignedNormalization) -> f64 {
+    match signed {
+        SignedNormalization::Legacy => value * max,
+        SignedNormalization::Symmetric if value * max < -max => -max,
+        SignedNormalization::Symmetric => value * max,
+        SignedNormalization::Offset => value * (max - min) + min,
+    }
+}
+
+/// Scale a normalized float to the range of `T` and convert it with the provided policies.
+///
+/// Returns None if the value is rejected by [`Clamping::Error`].
+pub(crate) fn denormalize<T>(value: f64, rounding: Rounding, clamping: Clamping) -> Option<T>
+where
+    T: PrimInt + Bounded + AsPrimitive<f64> + 'static,
+    f64: AsPrimitive<T>,
+    i128: AsPrimitive<T>,
+{
+    let max: f64 = T::max_value().as_();
+    round_scaled(value * max, rounding, clamping)
+}
+
+/// Convert a value scaled to the range of `T` with the provided policies, like [`round_to`].
+///
+/// The maximum of the 64 bit types rounds up to a power of two in f64, which does not fit
+/// the type itself, so a value scaled to exactly that maximum saturates to it.
+///
+/// Returns None if the value is rejected by [`Clamping::Error`].
+pub(crate) fn round_scaled<T>(scaled: f64, rounding: Rounding, clamping: Clamping) -> Option<T>
+where
+    T: PrimInt + Bounded + AsPrimitive<f64> + 'static,
+    f64: AsPrimitive<T>,
+    i128: AsPrimitive<T>,
+{
+    if scaled == T::max_value().as_() {
+        return Some(T::max_value());
+    }
+    round_to(scaled, rounding, clamping)
+}
+
+/// Convert a float to the integer `T` with the provided policies, without any scaling.
+///
+/// Returns None if the value is rejected by [`Clamping::Error`].
+pub fn round_to<T>(value: f64, rounding: Rounding, clamping: Clamping) -> Option<T>
+where
+    T: PrimInt + 'static,
+    f64: AsPrimitive<T>,
+    i128: AsPrimitive<T>,
+{
+    let rounded = match rounding {
+        Rounding::Nearest => FloatCore::round(value),
+        Rounding::Floor => FloatCore::floor(value),
+        Rounding::Ceil => FloatCore::ceil(value),
+        Rounding::Truncate => FloatCore::trunc(value),
+    };
+    match clamping {
+        Clamping::Clamp => Some(rounded.as_()),
+        Clamping::Wrap => Some(AsPrimitive::<i128>::as_(rounded).as_()),
+        Clamping::Error => T::from(rounded),
+    }
+}
+
+/// Sample type that normalized or plain float values can be converted into.
+///
+/// Integer types apply the rounding and clamping policies, float types take the value as is.
+pub trait ConvertSample: Copy + Send + Sync + 'static {
+    /// Convert the value, scaling it from the normalized range when `normalized` is set.
+    ///
+    /// Returns None if the value is rejected by [`Clamping::Error`].
+    fn from_f64_with(
+        value: f64,
+        normalized: bool,
+        rounding: Rounding,
+        clamping: Clamping,
+    ) -> Option<Self>;
+
+    /// Convert the normalized value, mapping it onto signed integers as described by `signed`.
+    ///
+    /// With [`SignedNormalization::Legacy`] this is the same as [`Self::from_f64_with`].
+    ///
+    /// Returns None if the value is rejected by [`Clamping::Error`].
+    fn from_f64_signed(
+        value: f64,
+        signed: SignedNormalization,
+        rounding: Rounding,
+        clamping: Clamping,
+    ) -> Option<Self> {
+        let _ = signed;
+        Self::from_f64_with(value, true, rounding, clamping)
+    }
+}
+
+macro_rules! impl_convert_sample_int {
+    ($type:ty) => {
+        impl ConvertSample for $type {
+            fn from_f64_with(
+                value: f64,
+                normalized: bool,
+                rounding: Rounding,
+                clamping: Clamping,
+            ) -> Option<Self> {
+                match normalized {
+                    true => denormalize(value, rounding, clamping),
+                    false => round_to(value, rounding, clamping),
+                }
+            }
+
+            fn from_f64_signed(
+                value: f64,
+                signed: SignedNormalization,
+                rounding: Rounding,
+                clamping: Clamping,
+            ) -> Option<Self> {
+                let scaled = scale_signed(value, <$type>::MIN as f64, <$type>::MAX as f64, signed);
+                round_scaled(scaled, rounding, clamping)
+            }
+        }
+    };
+}
+
+macro_rules! impl_convert_sample_float {
+    ($type:ty) => {
+        impl ConvertSample for $type {
+            fn from_f64_with(value: f64, _: bool, _: Rounding, _: Clamping) -> Option<Self> {
+                Some(value as $type)
+            }
+        }
+    };
+}
+
+impl_convert_sample_int!(u8);
+impl_convert_sample_int!(u16);
+impl_convert_sample_int!(u32);
+impl_convert_sample_int!(u64);
+impl_convert_sample_int!(i8);
+impl_convert_sample_int!(i16);
+impl_convert_sample_int!(i32);
+impl_convert_sample_int!(i64);
+impl_convert_sample_float!(f32);
+impl_convert_sample_float!(f64);
+
+/// Amount of values converted at once.
+#[cfg(feature = "std")]
+const CHUNK_SIZE: usize = 1 << 16;
+
+/// Quantize a normalized `(y, x, z)` f32 array into another sample type, releasing the
+/// float memory while the output is written.
+///
+/// Values are converted with [`NormalizedFloat::from_f32_normalized`], so 1.0 becomes
+/// the maximum value of the type. Values that can not be represented become zero.
+///
+/// This is not in place, the output is a new allocation. The conversion runs back to front
+/// in chunks and whenever half of the float buffer is consumed, it is shrunk to return that
+/// memory to the allocator, while the zero initialized output is only committed to memory
+/// once it is written. Whether peak memory stays below holding both full buffers depends on
+/// the allocator: one that can shrink in place or return pages to the system keeps it close
+/// to the size of the input, others copy the remaining floats on every shrink. The amount of
+/// shrinks is logarithmic in the size of the array.
+///
+/// Arrays that are not in standard layout are repacked first.
+#[cfg(feature = "std")]
+pub fn quantize_owned<T>(array: Array3<f32>) -> Array3<T>
+where
+    T: NormalizedFloat<T> + AsPrimitive<f32> + AsPrimitive<f64> + Zero,
+{
+    span!(DEBUG, "quantize_owned", shape = ?array.dim(), dtype = core::any::type_name::<T>());
+    paranoid! {
+        crate::paranoid::check_normalized_range(&array.view());
+    }
+    let shape = array.dim();
+    let size = array.len();
+    let array = if array.is_standard_layout() {
+        array
+    } else {
+        array.as_standard_layout().into_owned()
+    };
+    let (mut data, offset) = array.into_raw_vec_and_offset();
+    let offset = offset.unwrap_or_default();
+    if offset != 0 || data.len() != size {
+        data = data[offset..offset + size].to_vec();
+    }
+
+    let mut output = vec![T::zero(); size];
+    while !data.is_empty() {
+        let start = data.len().saturating_sub(CHUNK_SIZE);
+        let output = &mut output[start..data.len()];
+        if T::from_f32_normalized_slice(&data[start..], output).is_err() {
+            for (target, value) in output.iter_mut().zip(&data[start..]) {
+                *target = T::from_f32_normalized(*value).unwrap_or_else(T::zero);
+            }
+        }
+        data.truncate(start);
+        if data.len() <= data.capacity() / 2 {
+            data.shrink_to_fit();
+        }
+    }
+    Array3::from_shape_vec(shape, output).expect("output has the size of the input")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "std")]
+    use ndarray::{Array3, s};
+    use rstest::*;
+
+    #[rstest]
+    #[case(0.5, Rounding::Nearest, 128)]
+    #[case(0.5, Rounding::Floor, 127)]
+    #[case(0.5, Rounding::Ceil, 128)]
+    #[case(0.5, Rounding::Truncate, 127)]
+    #[case(1.0, Rounding::Nearest, 255)]
+    #[case(0.0, Rounding::Ceil, 0)]
+    fn test_denormalize_rounding(
+        #[case] value: f64,
+        #[case] rounding: Rounding,
+        #[case] expected: u8,
+    ) {
+        assert_eq!(
+            denormalize::<u8>(value, rounding, Clamping::Clamp),
+            Some(expected)
+        );
+    }
+
+    #[rstest]
+    #[case(1.2, Clamping::Clamp, 255)]
+    #[case(-0.1, Clamping::Clamp, 0)]
+    #[case(f64::NAN, Clamping::Clamp, 0)]
+    #[case(1.2, Clamping::Wrap, 50)]
+    #[case(-0.1, Clamping::Wrap, 230)]
+    #[case(1.0, Clamping::Error, 255)]
+    #[case(0.0, Clamping::Error, 0)]
+    fn test_denormalize_clamping(
+        #[case] value: f64,
+        #[case] clamping: Clamping,
+        #[case] expected: u8,
+    ) {
+        assert_eq!(
+            denormalize::<u8>(value, Rounding::Nearest, clamping),
+            Some(expected)
+        );
+    }
+
+    #[rstest]
+    #[case(1.2)]
+    #[case(-0.1)]
+    #[case(f64::NAN)]
+    #[case(f64::INFINITY)]
+    fn test_denormalize_clamping_error(#[case] value: f64) {
+        assert_eq!(
+            denormalize::<u8>(value, Rounding::Nearest, Clamping::Error),
+            None
+        );
+        assert_eq!(
+            u16::from_f64_with(value, true, Rounding::Nearest, Clamping::Error),
+            None
+        );
+    }
+
+    #[rstest]
+    #[case(SignedNormalization::Legacy, -128, -128.0 / 127.0)]
+    #[case(SignedNormalization::Symmetric, -128, -1.0)]
+    #[case(SignedNormalization::Symmetric, -127, -1.0)]
+    #[case(SignedNormalization::Offset, -128, 0.0)]
+    #[case(SignedNormalization::Offset, 127, 1.0)]
+    #[case(SignedNormalization::Offset, 0, 128.0 / 255.0)]
+    fn test_normalize_signed(
+        #[case] signed: SignedNormalization,
+        #[case] value: i8,
+        #[case] expected: f64,
+    ) {
+        assert_eq!(
+            normalize_signed(value.into(), i8::MIN.into(), i8::MAX.into(), signed),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case(SignedNormalization::Symmetric)]
+    #[case(SignedNormalization::Offset)]
+    fn test_signed_round_trip(#[case] signed: SignedNormalization) {
+        for value in i8::MIN + 1..=i8::MAX {
+            let normalized = normalize_signed(value.into(), i8::MIN.into(), i8::MAX.into(), signed);
+            let result =
+                i8::from_f64_signed(normalized, signed, Rounding::Nearest, Clamping::Clamp);
+            assert_eq!(result, Some(value));
+        }
+
+        let normalized = normalize_signed(-128.0, -128.0, 127.0, signed);
+        let result = i8::from_f64_signed(normalized, signed, Rounding::Nearest, Clamping::Clamp);
+        let expected = match signed {
+            SignedNormalization::Symmetric => -127,
+            _ => -128,
+        };
+        assert_eq!(result, Some(expected));
+    }
+
+    #[test]
+    fn test_signed_unsigned_is_unchanged() {
+        for signed in [
+            SignedNormalization::Legacy,
+            SignedNormalization::Symmetric,
+            SignedNormalization::Offset,
+        ] {
+            assert_eq!(normalize_signed(51.0, 0.0, 255.0, signed), 0.2);
+            assert_eq!(
+                u8::from_f64_signed(0.2, signed, Rounding::Nearest, Clamping::Clamp),
+                Some(51)
+            );
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_quantize_owned() {
+        let array = Array3::from_shape_fn((300, 400, 3), |(y, x, c)| {
+            ((y * 400 + x) * 3 + c) as f32 / (300 * 400 * 3) as f32
+        });
+
+        let result = quantize_owned::<u8>(array.clone());
+
+        assert_eq!(result.dim(), array.dim());
+        assert_eq!(result, array.mapv(|value| (value * 255.0).round() as u8));
+    }
+
+    #[cfg(feature = "std")]
+    #[rstest]
+    #[case(CHUNK_SIZE + 13)]
+    #[case(3 * CHUNK_SIZE + 5)]
+    fn test_quantize_owned_multiple_chunks(#[case] size: usize) {
+        let array = Array3::from_shape_fn((1, size, 1), |(_, x, _)| (x % 256) as f32 / 255.0);
+
+        let bytes = quantize_owned::<u8>(array.clone());
+        let words = quantize_owned::<u16>(array.clone());
+
+        assert_eq!(bytes, array.mapv(|value| (value * 255.0).round() as u8));
+        assert_eq!(words, array.mapv(|value| (value * 65535.0).round() as u16));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    #[cfg_attr(
+        feature = "paranoid",
+        should_panic(expected = "outside of the normalized range")
+    )]
+    fn test_quantize_owned_clamps() {
+        let array = Array3::from_shape_vec((1, 2, 2), vec![-1.0, 2.0, f32::NAN, 0.5]).unwrap();
+
+        let result = quantize_owned::<u16>(array);
+
+        assert_eq!(result.into_raw_vec_and_offset().0, vec![0, 65535, 0, 32768]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_quantize_owned_with_offset_and_layout() {
+        let array = Array3::from_shape_fn((20, 10, 4), |(y, x, c)| (y + x + c) as f32 / 40.0);
+        let mut sliced = array.clone().permuted_axes([1, 0, 2]);
+        sliced.slice_collapse(s![2..8, .., ..]);
+
+        let result = quantize_owned::<u8>(sliced.clone());
+
+        assert_eq!(result, sliced.mapv(|value| (value * 255.0).round() as u8));
+    }
+}
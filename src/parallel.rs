@@ -0,0 +1,189 @@
+//! Element-wise kernels that run on the rayon thread pool with the `rayon` feature
+//!
+//! Without the feature everything runs on the calling thread. As every output sample
+//! only depends on its own input sample, the results are identical either way.
+
+use ndarray::{
+    Array3, ArrayView2, ArrayView3, ArrayView4, ArrayViewMut2, ArrayViewMut3, ArrayViewMut4, Axis,
+    Zip,
+};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// Amount of samples converted by a single task.
+#[cfg(feature = "rayon")]
+const CHUNK_SIZE: usize = 1 << 16;
+
+/// Convert `values` into `output` with a slice kernel, split into chunks.
+///
+/// `output` needs to have the length of `values`. Returns the position of the first value
+/// that could not be represented, counted from the start of `values`.
+pub(crate) fn convert_chunks<A, B>(
+    values: &[A],
+    output: &mut [B],
+    kernel: impl Fn(&[A], &mut [B]) -> core::result::Result<(), usize> + Sync,
+) -> core::result::Result<(), usize>
+where
+    A: Sync,
+    B: Send,
+{
+    debug_assert_eq!(values.len(), output.len());
+    #[cfg(feature = "rayon")]
+    {
+        let failed = values
+            .par_chunks(CHUNK_SIZE)
+            .zip(output.par_chunks_mut(CHUNK_SIZE))
+            .enumerate()
+            .filter_map(|(chunk, (values, output))| {
+                kernel(values, output)
+                    .err()
+                    .map(|position| chunk * CHUNK_SIZE + position)
+            })
+            .min();
+        failed.map_or(Ok(()), Err)
+    }
+    #[cfg(not(feature = "rayon"))]
+    kernel(values, output)
+}
+
+/// Map every sample of the array into a new array.
+pub(crate) fn map<A, B>(array: ArrayView3<'_, A>, f: impl Fn(&A) -> B + Sync + Send) -> Array3<B>
+where
+    A: Sync,
+    B: Send,
+{
+    #[cfg(feature = "rayon")]
+    return Zip::from(array).par_map_collect(f);
+    #[cfg(not(feature = "rayon"))]
+    array.map(f)
+}
+
+/// Call `f` with every item along the outer axis of `input` and the matching item of
+/// `output`, like the images of a batch.
+pub(crate) fn for_each_outer<A, B>(
+    input: ArrayView4<'_, A>,
+    mut output: ArrayViewMut4<'_, B>,
+    f: impl Fn(ArrayView3<'_, A>, ArrayViewMut3<'_, B>) + Sync + Send,
+) where
+    A: Sync,
+    B: Send,
+{
+    let zip = Zip::from(input.outer_iter()).and(output.outer_iter_mut());
+    #[cfg(feature = "rayon")]
+    zip.par_for_each(f);
+    #[cfg(not(feature = "rayon"))]
+    zip.for_each(f);
+}
+
+/// Call `f` with every band of `size` rows of `input` and the matching band of `size`
+/// columns of `output`, like the tiles of a transpose.
+///
+/// Arrays of less than a chunk of samples are not split over tasks.
+pub(crate) fn for_each_band<A, B>(
+    input: ArrayView2<'_, A>,
+    mut output: ArrayViewMut2<'_, B>,
+    size: usize,
+    f: impl Fn(ArrayView2<'_, A>, ArrayViewMut2<'_, B>) + Sync + Send,
+) where
+    A: Sync,
+    B: Send + Sync,
+{
+    #[cfg(feature = "rayon")]
+    if input.len() >= CHUNK_SIZE {
+        input
+            .axis_chunks_iter(Axis(0), size)
+            .into_par_iter()
+            .zip(output.axis_chunks_iter_mut(Axis(1), size))
+            .for_each(|(input, output)| f(input, output));
+        return;
+    }
+    for (input, output) in input
+        .axis_chunks_iter(Axis(0), size)
+        .zip(output.axis_chunks_iter_mut(Axis(1), size))
+    {
+        f(input, output);
+    }
+}
+
+/// Build an array of the shape, calling `f` with the index of every sample.
+#[cfg(feature = "image")]
+pub(crate) fn from_shape_fn<B>(
+    shape: (usize, usize, usize),
+    f: impl Fn((usize, usize, usize)) -> B + Sync + Send,
+) -> Array3<B>
+where
+    B: Send,
+{
+    #[cfg(feature = "rayon")]
+    return Zip::from(ndarray::indices(shape)).par_map_collect(f);
+    #[cfg(not(feature = "rayon"))]
+    Array3::from_shape_fn(shape, f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array2;
+    use rstest::*;
+
+    #[test]
+    fn test_convert_chunks_first_failure() {
+        let values = [1u32, 2, 0, 4, 0, 6];
+        let mut output = [0u32; 6];
+
+        let result = convert_chunks(&values, &mut output, |values, output| {
+            for (index, (value, output)) in values.iter().zip(output).enumerate() {
+                *output = 12u32.checked_div(*value).ok_or(index)?;
+            }
+            Ok(())
+        });
+
+        assert_eq!(result, Err(2));
+        assert_eq!(output[..2], [12, 6]);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_map_and_from_shape_fn() {
+        let array = Array3::from_shape_fn((4, 8, 3), |(y, x, z)| (y * 24 + x * 3 + z) as u16);
+
+        let mapped = map(array.t(), |value| u32::from(*value) * 2);
+        let built = from_shape_fn((3, 8, 4), |(z, x, y)| u32::from(array[[y, x, z]]) * 2);
+
+        assert_eq!(mapped, built);
+    }
+
+    #[rstest]
+    #[case(4, 6)]
+    #[case(300, 517)]
+    fn test_for_each_band(#[case] rows: usize, #[case] columns: usize) {
+        let input = Array2::from_shape_fn((rows, columns), |(y, x)| y * columns + x);
+        let mut output = Array2::zeros((columns, rows));
+
+        for_each_band(input.view(), output.view_mut(), 64, |input, mut output| {
+            assert!(input.nrows() <= 64);
+            output.assign(&input.t());
+        });
+
+        assert_eq!(output, input.t());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_convert_chunks_across_tasks() {
+        let mut values = vec![1u32; 4 * CHUNK_SIZE];
+        values[3 * CHUNK_SIZE + 5] = 0;
+        values[CHUNK_SIZE + 7] = 0;
+        let mut output = vec![0u32; values.len()];
+
+        let result = convert_chunks(&values, &mut output, |values, output| {
+            for (index, (value, output)) in values.iter().zip(output).enumerate() {
+                *output = 12u32.checked_div(*value).ok_or(index)?;
+            }
+            Ok(())
+        });
+
+        assert_eq!(result, Err(CHUNK_SIZE + 7));
+        assert!(output[..CHUNK_SIZE + 7].iter().all(|value| *value == 12));
+    }
+}
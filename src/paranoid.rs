@@ -0,0 +1,214 @@
+//! Validation of conversions, enabled with the `paranoid` feature
+//!
+//! The checks assert invariants the conversions are built on: borrowed views match the
+//! shape and strides of the buffer they point into, buffers hold exactly one sample per
+//! channel of every pixel, float inputs are normalized and accelerated float conversions
+//! match the scalar one. They
+//! cost at least one extra pass over the data and panic with the offending shape or value,
+//! so layout mix-ups surface at the conversion instead of as a garbled image further down
+//! a pipeline. Enable the feature in tests and debug builds, not in production.
+
+#[cfg(feature = "paranoid")]
+use {
+    crate::traits::NormalizedFloat,
+    core::any::TypeId,
+    ndarray::{ArrayView, Dimension},
+    num_traits::AsPrimitive,
+};
+#[cfg(all(feature = "paranoid", feature = "image"))]
+use {core::fmt::Display, ndarray::ArrayView3, num_traits::Float};
+
+/// Run the checks in the block only with the `paranoid` feature.
+///
+/// Without the feature this expands to nothing, so the block is not evaluated.
+macro_rules! paranoid {
+    ($($body:tt)*) => {
+        #[cfg(feature = "paranoid")]
+        {
+            $($body)*
+        }
+    };
+}
+
+pub(crate) use paranoid;
+
+#[cfg(all(feature = "paranoid", feature = "image"))]
+/// Assert the borrowed view has the shape and the strides of the buffer it points into.
+///
+/// Strides of axes with a single element are not checked, as they are never followed.
+pub(crate) fn check_view<C, D: Dimension>(
+    view: &ArrayView<'_, C, D>,
+    shape: &[usize],
+    strides: &[usize],
+) {
+    assert_eq!(
+        view.shape(),
+        shape,
+        "view does not have the shape of the image"
+    );
+    for (axis, (&length, (&stride, &expected))) in view
+        .shape()
+        .iter()
+        .zip(view.strides().iter().zip(strides))
+        .enumerate()
+    {
+        assert!(
+            length <= 1 || stride == expected as isize,
+            "view of shape {shape:?} has stride {stride} on axis {axis} instead of {expected}"
+        );
+    }
+}
+
+#[cfg(all(feature = "paranoid", feature = "image"))]
+/// Assert a buffer holds one sample for every channel of every pixel.
+pub(crate) fn check_samples(samples: usize, (height, width, channels): (usize, usize, usize)) {
+    assert_eq!(
+        samples,
+        height * width * channels,
+        "buffer of {samples} samples does not fit the shape {:?}",
+        (height, width, channels)
+    );
+}
+
+#[cfg(all(feature = "paranoid", feature = "image"))]
+/// Assert the normalized floats match the scalar conversion of every source sample.
+///
+/// Samples that can not be represented have to be NaN.
+pub(crate) fn check_normalized<C, F>(
+    source: &ArrayView3<'_, C>,
+    normalized: &ArrayView3<'_, F>,
+    convert: impl Fn(&C) -> Option<F>,
+) where
+    F: Float + Display,
+{
+    assert_eq!(source.dim(), normalized.dim(), "normalized shape differs");
+    for ((index, sample), value) in source.indexed_iter().zip(normalized) {
+        let expected = convert(sample).unwrap_or_else(F::nan);
+        assert!(
+            *value == expected || (value.is_nan() && expected.is_nan()),
+            "sample at {index:?} normalized to {value} instead of {expected}"
+        );
+    }
+}
+
+#[cfg(feature = "paranoid")]
+/// Assert float samples are normalized, so not NaN and within 0.0 and 1.0.
+///
+/// Integer samples are always in range, so they are not checked.
+pub(crate) fn check_normalized_range<T, D>(array: &ArrayView<'_, T, D>)
+where
+    T: NormalizedFloat<T> + AsPrimitive<f32> + AsPrimitive<f64>,
+    D: Dimension,
+{
+    if !is_float::<T>() {
+        return;
+    }
+    for (index, sample) in array.indexed_iter() {
+        let value = sample.to_f64_normalized().unwrap_or(f64::NAN);
+        assert!(
+            (0.0..=1.0).contains(&value),
+            "sample at {index:?} is {value}, outside of the normalized range"
+        );
+    }
+}
+
+#[cfg(feature = "paranoid")]
+/// Whether the samples are floats, which are stored normalized as is.
+fn is_float<T: 'static>() -> bool {
+    let float = [TypeId::of::<f32>(), TypeId::of::<f64>()];
+    #[cfg(feature = "half")]
+    let float = [
+        float[0],
+        float[1],
+        TypeId::of::<half::f16>(),
+        TypeId::of::<half::bf16>(),
+    ];
+    float.contains(&TypeId::of::<T>())
+}
+
+#[cfg(all(test, feature = "paranoid", feature = "image"))]
+mod tests {
+    use super::*;
+    use ndarray::{Array3, s};
+    use rstest::*;
+
+    #[test]
+    fn test_check_view_region() {
+        let array = Array3::<u8>::zeros((4, 6, 3));
+        let region = array.slice(s![1..3, 2..5, ..]);
+
+        check_view(&region, &[2, 3, 3], &[18, 3, 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "stride 3 on axis 0 instead of 18")]
+    fn test_check_view_strides() {
+        let array = Array3::<u8>::zeros((6, 4, 3));
+        let transposed = array.view().permuted_axes([1, 0, 2]);
+
+        check_view(&transposed, &[4, 6, 3], &[18, 3, 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "does not have the shape")]
+    fn test_check_view_shape() {
+        let array = Array3::<u8>::zeros((2, 3, 4));
+
+        check_view(&array.view(), &[2, 3, 3], &[12, 4, 1]);
+    }
+
+    #[rstest]
+    #[case(f32::NAN)]
+    #[case(-0.1)]
+    #[case(1.5)]
+    #[should_panic(expected = "outside of the normalized range")]
+    fn test_check_normalized_range(#[case] value: f32) {
+        let array = Array3::from_shape_vec((1, 2, 1), vec![0.5, value]).unwrap();
+
+        check_normalized_range(&array.view());
+    }
+
+    #[test]
+    fn test_check_normalized_range_valid() {
+        let integers = Array3::from_shape_vec((1, 2, 1), vec![i16::MIN, i16::MAX]).unwrap();
+        let floats = Array3::from_shape_vec((1, 3, 1), vec![0.0, 0.5, 1.0]).unwrap();
+
+        check_normalized_range(&integers.view());
+        check_normalized_range(&floats.view());
+    }
+
+    #[test]
+    #[should_panic(expected = "normalized to")]
+    fn test_check_normalized() {
+        let source = Array3::from_elem((1, 2, 1), 255u8);
+        let normalized = Array3::from_elem((1, 2, 1), 0.5f32);
+
+        check_normalized(&source.view(), &normalized.view(), |value| {
+            value.to_f32_normalized()
+        });
+    }
+
+    #[test]
+    fn test_round_trip() {
+        use crate::prelude::*;
+        use crate::{Clamping, Rounding};
+        use image::{ImageBuffer, Rgba};
+
+        let test_image = ImageBuffer::<Rgba<u16>, _>::from_fn(16, 8, |x, y| {
+            Rgba([x as u16 * 4096, y as u16 * 8192, 65535, (x * y) as u16])
+        });
+
+        let normalized = test_image.to_ndarray_f32();
+        check_normalized(&test_image.as_ndarray(), &normalized.view(), |value| {
+            value.to_f32_normalized()
+        });
+        let result = ImageBuffer::<Rgba<u16>, _>::from_ndarray_f32(
+            normalized.view(),
+            Rounding::Nearest,
+            Clamping::Error,
+        )
+        .unwrap();
+
+        assert_eq!(result, test_image);
+    }
+}
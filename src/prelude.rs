@@ -1,4 +1,21 @@
-pub use crate::traits::NormalizedFloat;
+pub use crate::traits::{CowNdarray, NormalizedArray, NormalizedFloat};
 
 #[cfg(all(feature = "image", feature = "std"))]
-pub use crate::traits::ImageArray;
+pub use crate::traits::{ImageArray, LumaArray, NdarrayImageExt, SubImageArray, SubImageArrayMut};
+
+#[cfg(feature = "image")]
+pub use crate::flat::{FlatSamplesArray, FlatSamplesArrayOwned, NdarrayFlatSamplesExt};
+
+#[cfg(feature = "image")]
+pub use crate::convert::ConvertWith;
+
+#[cfg(feature = "rgb")]
+pub use crate::rgb_interop::{NdarrayRgbExt, RgbPixelArray};
+
+#[cfg(feature = "imgref")]
+pub use crate::imgref_interop::{ImgArray, ImgArrayOwned, NdarrayImgExt};
+
+#[cfg(all(feature = "nalgebra", feature = "image"))]
+pub use crate::nalgebra_interop::LumaMatrix;
+#[cfg(feature = "nalgebra")]
+pub use crate::nalgebra_interop::{DMatrixStridedView, MatrixArray, NdarrayMatrixExt};
@@ -0,0 +1,244 @@
+//! Validated shape and data pair shared by ImageBuffers and ndarrays
+
+use crate::error::{Error, Result};
+#[cfg(feature = "image")]
+use image::{ImageBuffer, Pixel};
+use ndarray::Array3;
+
+/// Owned samples together with the `(height, width, channels)` shape they describe.
+///
+/// The samples are stored row by row with interleaved channels, which is the memory
+/// layout of both an ImageBuffer and a standard layout Array3. The length of the data
+/// always matches the shape, so moving between the two representations never copies.
+///
+/// This is the building block for conversions into other containers, like tensors of
+/// other crates, without depending on unsafe code.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawParts<C> {
+    height: usize,
+    width: usize,
+    channels: usize,
+    data: Vec<C>,
+}
+
+impl<C> RawParts<C> {
+    /// Combine the shape with the data.
+    ///
+    /// Returns [`Error::LengthMismatch`] if the length of the data does not match the shape.
+    pub fn new(height: usize, width: usize, channels: usize, data: Vec<C>) -> Result<Self> {
+        let size = height
+            .checked_mul(width)
+            .and_then(|size| size.checked_mul(channels));
+        if size != Some(data.len()) {
+            return Err(Error::LengthMismatch {
+                shape: (height, width, channels),
+                got: data.len(),
+            });
+        }
+        Ok(Self {
+            height,
+            width,
+            channels,
+            data,
+        })
+    }
+
+    /// The `(height, width, channels)` shape of the data.
+    pub fn dim(&self) -> (usize, usize, usize) {
+        (self.height, self.width, self.channels)
+    }
+
+    /// Reference the samples.
+    pub fn data(&self) -> &[C] {
+        &self.data
+    }
+
+    /// Split into the `(height, width, channels)` shape and the samples.
+    pub fn into_inner(self) -> ((usize, usize, usize), Vec<C>) {
+        (self.dim(), self.data)
+    }
+
+    /// Take the samples out of the array.
+    ///
+    /// This does not copy the data when the array is in standard layout and starts at
+    /// the beginning of its allocation, otherwise it is repacked once.
+    pub fn from_ndarray(array: Array3<C>) -> Self
+    where
+        C: Clone,
+    {
+        let (height, width, channels) = array.dim();
+        let array = if array.is_standard_layout() {
+            array
+        } else {
+            array.as_standard_layout().into_owned()
+        };
+        let size = array.len();
+        let (data, offset) = array.into_raw_vec_and_offset();
+        let data = match offset {
+            Some(0) | None if data.len() == size => data,
+            offset => {
+                let offset = offset.unwrap_or_default();
+                data[offset..offset + size].to_vec()
+            }
+        };
+        Self {
+            height,
+            width,
+            channels,
+            data,
+        }
+    }
+
+    /// Convert into an Array3.
+    ///
+    /// This does not copy the data.
+    pub fn into_ndarray(self) -> Array3<C> {
+        Array3::from_shape_vec((self.height, self.width, self.channels), self.data)
+            .expect("shape is validated on construction")
+    }
+
+    /// Take the samples out of the ImageBuffer.
+    ///
+    /// This does not copy the data, but it does consume the buffer.
+    #[cfg(feature = "image")]
+    pub fn from_image<P>(image: ImageBuffer<P, Vec<C>>) -> Self
+    where
+        P: Pixel<Subpixel = C>,
+    {
+        let (width, height) = image.dimensions();
+        let (height, width, channels) =
+            (height as usize, width as usize, P::CHANNEL_COUNT as usize);
+        let mut data = image.into_raw();
+        data.truncate(height * width * channels);
+        Self {
+            height,
+            width,
+            channels,
+            data,
+        }
+    }
+
+    /// Convert into an ImageBuffer.
+    ///
+    /// Returns [`Error::ChannelMismatch`] if the channels do not match the pixel and
+    /// [`Error::ImageConstructFailed`] if the dimensions do not fit in an ImageBuffer.
+    ///
+    /// This does not copy the data.
+    #[cfg(feature = "image")]
+    pub fn into_image<P>(self) -> Result<ImageBuffer<P, Vec<C>>>
+    where
+        P: Pixel<Subpixel = C>,
+    {
+        if self.channels != P::CHANNEL_COUNT.into() {
+            return Err(Error::ChannelMismatch {
+                expected: P::CHANNEL_COUNT.into(),
+                got: self.channels,
+            });
+        }
+        let width = u32::try_from(self.width).or(Err(Error::ImageConstructFailed))?;
+        let height = u32::try_from(self.height).or(Err(Error::ImageConstructFailed))?;
+        ImageBuffer::from_raw(width, height, self.data).ok_or(Error::ImageConstructFailed)
+    }
+}
+
+impl<C: Clone> From<Array3<C>> for RawParts<C> {
+    fn from(array: Array3<C>) -> Self {
+        Self::from_ndarray(array)
+    }
+}
+
+impl<C> From<RawParts<C>> for Array3<C> {
+    fn from(parts: RawParts<C>) -> Self {
+        parts.into_ndarray()
+    }
+}
+
+#[cfg(feature = "image")]
+impl<P> From<ImageBuffer<P, Vec<P::Subpixel>>> for RawParts<P::Subpixel>
+where
+    P: Pixel,
+{
+    fn from(image: ImageBuffer<P, Vec<P::Subpixel>>) -> Self {
+        Self::from_image(image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::s;
+
+    #[test]
+    fn test_new() {
+        let parts = RawParts::new(2, 3, 4, vec![0u8; 24]).unwrap();
+        assert_eq!(parts.dim(), (2, 3, 4));
+
+        let result = RawParts::new(2, 3, 4, vec![0u8; 23]).err().unwrap();
+        assert_eq!(
+            result,
+            Error::LengthMismatch {
+                shape: (2, 3, 4),
+                got: 23
+            }
+        );
+
+        let result = RawParts::new(usize::MAX, 2, 1, vec![0u8; 2]).err().unwrap();
+        assert_eq!(
+            result,
+            Error::LengthMismatch {
+                shape: (usize::MAX, 2, 1),
+                got: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_ndarray_round_trip() {
+        let array = Array3::from_shape_fn((8, 16, 3), |(y, x, c)| y * 48 + x * 3 + c);
+        let input = array.clone();
+        let pointer = input.as_ptr();
+
+        let parts = RawParts::from(input);
+        assert_eq!(parts.data().as_ptr(), pointer);
+
+        assert_eq!(Array3::from(parts), array);
+    }
+
+    #[test]
+    fn test_from_ndarray_repacks() {
+        let array = Array3::from_shape_fn((16, 8, 3), |(x, y, c)| y * 48 + x * 3 + c);
+        let mut transposed = array.permuted_axes([1, 0, 2]);
+        transposed.slice_collapse(s![2..6, .., ..]);
+
+        let parts = RawParts::from_ndarray(transposed.clone());
+
+        assert_eq!(parts.dim(), (4, 16, 3));
+        assert_eq!(parts.into_ndarray(), transposed);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_image_round_trip() {
+        use image::{Rgb, RgbImage};
+
+        let test_image = RgbImage::from_fn(16, 8, |x, y| Rgb([x as u8, y as u8, 0]));
+        let input = test_image.clone();
+        let pointer = input.as_ptr();
+
+        let parts = RawParts::from(input);
+        assert_eq!(parts.dim(), (8, 16, 3));
+        assert_eq!(parts.data().as_ptr(), pointer);
+
+        let result = parts.clone().into_image::<Rgb<u8>>().unwrap();
+        assert_eq!(result, test_image);
+
+        let result = parts.into_image::<image::Rgba<u8>>().err().unwrap();
+        assert_eq!(
+            result,
+            Error::ChannelMismatch {
+                expected: 4,
+                got: 3
+            }
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! Interoperability with the pixel types of the rgb crate
+
+use crate::error::{Error, Result};
+use bytemuck::Pod;
+use ndarray::{ArrayBase, ArrayView2, ArrayView3, Data, Ix2, Ix3};
+
+/// Pixel type of the rgb crate that is made up of `CHANNEL_COUNT` components.
+///
+/// The pixels are plain `#[repr(C)]` structs, so a slice of pixels has the exact same
+/// memory as the interleaved components.
+pub trait RgbPixel: Pod {
+    /// Type of a single component.
+    type Component: Pod;
+    /// Amount of components in the pixel.
+    const CHANNEL_COUNT: usize;
+}
+
+macro_rules! impl_rgb_pixel {
+    ($type:ident, $channels:expr) => {
+        impl<T: Pod> RgbPixel for rgb::$type<T> {
+            type Component = T;
+            const CHANNEL_COUNT: usize = $channels;
+        }
+    };
+}
+
+impl_rgb_pixel!(Rgb, 3);
+impl_rgb_pixel!(Rgba, 4);
+impl_rgb_pixel!(Bgr, 3);
+impl_rgb_pixel!(Bgra, 4);
+
+/// Conversion methods for `(y, x)` ndarrays of rgb crate pixels.
+pub trait RgbPixelArray<Component> {
+    /// Cast the pixels as an ArrayView3 of their components.
+    ///
+    /// * `Y` index is the row
+    /// * `X` index is the columns
+    /// * `Z` index is the channel
+    ///
+    /// So when referencing:
+    /// `array[[y, x, z]]`
+    ///
+    /// Returns [`Error::NotContiguous`] if the array is not contiguous in standard layout.
+    ///
+    /// This does not copy the data, as it is a reference to the actual data in the array.
+    fn as_channel_ndarray(&self) -> Result<ArrayView3<'_, Component>>;
+}
+
+impl<S, Px> RgbPixelArray<Px::Component> for ArrayBase<S, Ix2>
+where
+    S: Data<Elem = Px>,
+    Px: RgbPixel,
+{
+    fn as_channel_ndarray(&self) -> Result<ArrayView3<'_, Px::Component>> {
+        let (height, width) = self.dim();
+        let pixels = self.as_slice().ok_or(Error::NotContiguous)?;
+        Ok(ArrayView3::from_shape(
+            (height, width, Px::CHANNEL_COUNT),
+            bytemuck::cast_slice(pixels),
+        )?)
+    }
+}
+
+/// Conversion methods from `(y, x, z)` ndarrays into rgb crate pixels.
+pub trait NdarrayRgbExt<Component> {
+    /// Cast the array as an ArrayView2 of rgb crate pixels.
+    ///
+    /// * `Y` index is the row
+    /// * `X` index is the columns
+    ///
+    /// So when referencing:
+    /// `array[[y, x]]`, which returns the whole pixel.
+    ///
+    /// Returns [`Error::ChannelMismatch`] if the channel axis does not match the pixel
+    /// and [`Error::NotContiguous`] if the array is not contiguous in standard layout.
+    ///
+    /// This does not copy the data, as it is a reference to the actual data in the array.
+    fn as_rgb_pixels<Px>(&self) -> Result<ArrayView2<'_, Px>>
+    where
+        Px: RgbPixel<Component = Component>;
+
+    /// Copy the array into a row major Vec of rgb crate pixels.
+    ///
+    /// This is the form most encoders of the rgb ecosystem accept.
+    ///
+    /// Returns [`Error::ChannelMismatch`] if the channel axis does not match the pixel.
+    fn to_rgb_vec<Px>(&self) -> Result<Vec<Px>>
+    where
+        Px: RgbPixel<Component = Component>;
+}
+
+impl<S, C> NdarrayRgbExt<C> for ArrayBase<S, Ix3>
+where
+    S: Data<Elem = C>,
+    C: Pod,
+{
+    fn as_rgb_pixels<Px>(&self) -> Result<ArrayView2<'_, Px>>
+    where
+        Px: RgbPixel<Component = C>,
+    {
+        let (height, width, channels) = self.dim();
+        if channels != Px::CHANNEL_COUNT {
+            return Err(Error::ChannelMismatch {
+                expected: Px::CHANNEL_COUNT,
+                got: channels,
+            });
+        }
+        let components = self.as_slice().ok_or(Error::NotContiguous)?;
+        Ok(ArrayView2::from_shape(
+            (height, width),
+            bytemuck::cast_slice(components),
+        )?)
+    }
+
+    fn to_rgb_vec<Px>(&self) -> Result<Vec<Px>>
+    where
+        Px: RgbPixel<Component = C>,
+    {
+        if self.dim().2 != Px::CHANNEL_COUNT {
+            return Err(Error::ChannelMismatch {
+                expected: Px::CHANNEL_COUNT,
+                got: self.dim().2,
+            });
+        }
+        let components = self.as_standard_layout();
+        let components = components
+            .as_slice()
+            .expect("standard layout is contiguous");
+        Ok(bytemuck::cast_slice(components).to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::{Array2, Array3};
+    use rgb::{RGB8, RGBA8, Rgba};
+
+    #[test]
+    fn test_as_channel_ndarray() {
+        let pixels = Array2::from_shape_fn((8, 16), |(y, x)| RGB8::new(x as u8, y as u8, 7));
+
+        let array = pixels.as_channel_ndarray().unwrap();
+
+        assert_eq!(array.dim(), (8, 16, 3));
+        assert_eq!(array.as_ptr(), pixels.as_ptr().cast());
+        for ((y, x), pixel) in pixels.indexed_iter() {
+            assert_eq!(
+                array.slice(ndarray::s![y, x, ..]).to_vec(),
+                [pixel.r, pixel.g, 7]
+            );
+        }
+    }
+
+    #[test]
+    fn test_as_rgb_pixels() {
+        let array = Array3::from_shape_fn((8, 16, 4), |(y, x, c)| (y * 64 + x * 4 + c) as u16);
+
+        let pixels = array.as_rgb_pixels::<Rgba<u16>>().unwrap();
+
+        assert_eq!(pixels.dim(), (8, 16));
+        assert_eq!(pixels[[2, 3]], Rgba::new(140, 141, 142, 143));
+        assert_eq!(pixels.as_channel_ndarray().unwrap(), array);
+    }
+
+    #[test]
+    fn test_to_rgb_vec() {
+        let array = Array3::from_shape_fn((16, 8, 4), |(x, y, c)| (y * 64 + x * 4 + c) as u8);
+        let transposed = array.view().permuted_axes([1, 0, 2]);
+
+        let pixels = transposed.to_rgb_vec::<RGBA8>().unwrap();
+
+        assert_eq!(pixels.len(), 16 * 8);
+        assert_eq!(pixels[16 + 2], RGBA8::new(72, 73, 74, 75));
+    }
+
+    #[test]
+    fn test_rgb_errors() {
+        let array = Array3::<u8>::zeros((8, 16, 4));
+
+        assert_eq!(
+            array.as_rgb_pixels::<RGB8>().err(),
+            Some(Error::ChannelMismatch {
+                expected: 3,
+                got: 4
+            })
+        );
+        assert_eq!(
+            array.to_rgb_vec::<RGB8>().err(),
+            Some(Error::ChannelMismatch {
+                expected: 3,
+                got: 4
+            })
+        );
+
+        let transposed = array.view().permuted_axes([1, 0, 2]);
+        assert_eq!(
+            transposed.as_rgb_pixels::<RGBA8>().err(),
+            Some(Error::NotContiguous)
+        );
+    }
+}
@@ -0,0 +1,175 @@
+//! Detection and replacement of NaN and infinite values in float arrays
+
+use crate::error::{Error, Result};
+use crate::trace::span;
+use ndarray::{Array2, ArrayView3, ArrayViewMut2, ArrayViewMut3, Axis};
+use std::collections::VecDeque;
+
+/// Handling of NaN and infinite values by [`sanitize`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sanitize {
+    /// Replace the values with the constant.
+    Replace(f32),
+    /// Replace the values with the closest finite value of the same channel.
+    ///
+    /// Distances are measured in steps between horizontal and vertical neighbours. A
+    /// channel without any finite value is filled with 0.0.
+    Nearest,
+    /// Leave the array as is and reject it.
+    Error,
+}
+
+/// Replace the NaN and infinite values of the `(y, x, z)` array as described by `policy`.
+///
+/// Renderers regularly output NaN, which otherwise silently turns into zero, or spreads
+/// over the image in filters, further down a pipeline.
+///
+/// Returns the amount of values that were replaced, or [`Error::NonFinite`] with the
+/// `[y, x, z]` index of the first offending value for [`Sanitize::Error`].
+pub fn sanitize(mut array: ArrayViewMut3<'_, f32>, policy: Sanitize) -> Result<usize> {
+    span!(DEBUG, "sanitize", shape = ?array.dim(), ?policy);
+    match policy {
+        Sanitize::Replace(replacement) => {
+            let mut count = 0;
+            array.mapv_inplace(|value| match value.is_finite() {
+                true => value,
+                false => {
+                    count += 1;
+                    replacement
+                }
+            });
+            Ok(count)
+        }
+        Sanitize::Nearest => Ok(array.axis_iter_mut(Axis(2)).map(fill_nearest).sum()),
+        Sanitize::Error => match array.indexed_iter().find(|(_, value)| !value.is_finite()) {
+            Some(((y, x, z), _)) => Err(Error::NonFinite([y, x, z])),
+            None => Ok(0),
+        },
+    }
+}
+
+/// Collect the `[y, x, z]` indices of all NaN and infinite values of the array.
+pub fn find_non_finite(array: ArrayView3<'_, f32>) -> Vec<[usize; 3]> {
+    array
+        .indexed_iter()
+        .filter(|(_, value)| !value.is_finite())
+        .map(|((y, x, z), _)| [y, x, z])
+        .collect()
+}
+
+/// Fill the non finite values of the channel with their closest finite value, by growing
+/// the finite values outwards one neighbour at a time.
+fn fill_nearest(mut channel: ArrayViewMut2<'_, f32>) -> usize {
+    let (height, width) = channel.dim();
+    let mut filled: Array2<bool> = channel.map(|value| value.is_finite());
+    let count = filled.iter().filter(|filled| !**filled).count();
+    let mut queue: VecDeque<(usize, usize)> = filled
+        .indexed_iter()
+        .filter(|(_, filled)| **filled)
+        .map(|(index, _)| index)
+        .collect();
+    if queue.is_empty() {
+        channel.fill(0.0);
+        return count;
+    }
+
+    while let Some((y, x)) = queue.pop_front() {
+        let value = channel[[y, x]];
+        let neighbours = [
+            (y.wrapping_sub(1), x),
+            (y + 1, x),
+            (y, x.wrapping_sub(1)),
+            (y, x + 1),
+        ];
+        for (y, x) in neighbours {
+            if y < height && x < width && !filled[[y, x]] {
+                filled[[y, x]] = true;
+                channel[[y, x]] = value;
+                queue.push_back((y, x));
+            }
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::{Array3, arr3};
+
+    fn array() -> Array3<f32> {
+        let mut array = Array3::from_shape_fn((3, 4, 2), |(y, x, z)| (y * 8 + x * 2 + z) as f32);
+        array[[0, 1, 0]] = f32::NAN;
+        array[[2, 3, 0]] = f32::INFINITY;
+        array[[1, 2, 1]] = f32::NEG_INFINITY;
+        array
+    }
+
+    #[test]
+    fn test_find_non_finite() {
+        assert_eq!(
+            find_non_finite(array().view()),
+            [[0, 1, 0], [1, 2, 1], [2, 3, 0]]
+        );
+        assert!(find_non_finite(Array3::zeros((2, 2, 1)).view()).is_empty());
+    }
+
+    #[test]
+    fn test_sanitize_replace() {
+        let mut array = array();
+
+        assert_eq!(sanitize(array.view_mut(), Sanitize::Replace(-1.0)), Ok(3));
+
+        assert_eq!(array[[0, 1, 0]], -1.0);
+        assert_eq!(array[[2, 3, 0]], -1.0);
+        assert_eq!(array[[1, 2, 1]], -1.0);
+        assert_eq!(array[[1, 2, 0]], 12.0);
+    }
+
+    #[test]
+    fn test_sanitize_nearest() {
+        let nan = f32::NAN;
+        let mut array = arr3(&[
+            [[1.0], [nan], [nan], [nan], [nan]],
+            [[nan], [nan], [nan], [nan], [5.0]],
+        ]);
+
+        assert_eq!(sanitize(array.view_mut(), Sanitize::Nearest), Ok(8));
+
+        assert_eq!(
+            array,
+            arr3(&[
+                [[1.0], [1.0], [1.0], [5.0], [5.0]],
+                [[1.0], [1.0], [5.0], [5.0], [5.0]],
+            ])
+        );
+    }
+
+    #[test]
+    fn test_sanitize_nearest_without_finite_values() {
+        let mut array = Array3::from_shape_fn((2, 2, 2), |(_, _, z)| match z {
+            0 => f32::NAN,
+            _ => 3.0,
+        });
+
+        assert_eq!(sanitize(array.view_mut(), Sanitize::Nearest), Ok(4));
+
+        assert_eq!(array.index_axis(Axis(2), 0), Array2::zeros((2, 2)));
+        assert_eq!(array.index_axis(Axis(2), 1), Array2::from_elem((2, 2), 3.0));
+    }
+
+    #[test]
+    fn test_sanitize_error() {
+        let mut array = array();
+
+        assert_eq!(
+            sanitize(array.view_mut(), Sanitize::Error),
+            Err(Error::NonFinite([0, 1, 0]))
+        );
+        assert!(array[[0, 1, 0]].is_nan());
+        assert_eq!(
+            sanitize(Array3::zeros((2, 2, 1)).view_mut(), Sanitize::Error),
+            Ok(0)
+        );
+    }
+}
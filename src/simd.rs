@@ -0,0 +1,127 @@
+//! Vectorized normalization kernels for 8 and 16 bit samples
+
+use num_traits::float::FloatCore;
+use wide::{CmpGe, f32x8, i32x8};
+
+/// Amount of samples processed per vector.
+const LANES: usize = 8;
+
+macro_rules! impl_simd_kernels {
+    ($to:ident, $from:ident, $type:ty) => {
+        /// Normalize the samples into `output`, matching the scalar division exactly.
+        ///
+        /// `output` needs to have the length of `values`.
+        pub(crate) fn $to(values: &[$type], output: &mut [f32]) {
+            debug_assert_eq!(values.len(), output.len());
+            let max = <$type>::MAX as f32;
+            let mut values = values.chunks_exact(LANES);
+            let mut output = output.chunks_exact_mut(LANES);
+            for (values, output) in (&mut values).zip(&mut output) {
+                let ints = i32x8::new(core::array::from_fn(|index| i32::from(values[index])));
+                output.copy_from_slice(&(f32x8::from_i32x8(ints) / f32x8::splat(max)).to_array());
+            }
+            for (value, output) in values.remainder().iter().zip(output.into_remainder()) {
+                *output = f32::from(*value) / max;
+            }
+        }
+
+        /// Scale normalized floats into `output`, rounding to the nearest value and
+        /// saturating at the range of the type. NaN becomes zero.
+        ///
+        /// `output` needs to have the length of `values`.
+        pub(crate) fn $from(values: &[f32], output: &mut [$type]) {
+            debug_assert_eq!(values.len(), output.len());
+            let max = <$type>::MAX as f32;
+            let mut values = values.chunks_exact(LANES);
+            let mut output = output.chunks_exact_mut(LANES);
+            for (values, output) in (&mut values).zip(&mut output) {
+                let scaled = f32x8::new(values.try_into().expect("chunk has the size of a vector"))
+                    * f32x8::splat(max);
+                let scaled = scaled
+                    .is_nan()
+                    .blend(f32x8::ZERO, scaled)
+                    .max(f32x8::ZERO)
+                    .min(f32x8::splat(max));
+                // Halfway cases round away from zero like the scalar path, which the
+                // vector `round` does not do. The fraction is exact below 2^23.
+                let floor = scaled.floor();
+                let up = (scaled - floor).cmp_ge(f32x8::splat(0.5)) & f32x8::ONE;
+                for (output, value) in output.iter_mut().zip((floor + up).trunc_int().to_array()) {
+                    *output = value as $type;
+                }
+            }
+            for (value, output) in values.remainder().iter().zip(output.into_remainder()) {
+                *output = FloatCore::round(value * max) as $type;
+            }
+        }
+    };
+}
+
+impl_simd_kernels!(u8_to_f32, f32_to_u8, u8);
+impl_simd_kernels!(u16_to_f32, f32_to_u16, u16);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_u8_matches_scalar() {
+        let values: [u8; 269] = core::array::from_fn(|index| (index % 256) as u8);
+        let mut output = [0.0; 269];
+
+        u8_to_f32(&values, &mut output);
+
+        for (value, result) in values.iter().zip(&output) {
+            assert_eq!(*result, *value as f32 / u8::MAX as f32);
+        }
+    }
+
+    #[test]
+    fn test_u16_matches_scalar() {
+        let values: [u16; 9363] = core::array::from_fn(|index| (index * 7).min(65535) as u16);
+        let mut output = [0.0; 9363];
+
+        u16_to_f32(&values, &mut output);
+
+        for (value, result) in values.iter().zip(&output) {
+            assert_eq!(*result, *value as f32 / u16::MAX as f32);
+        }
+    }
+
+    #[test]
+    fn test_from_f32_matches_scalar() {
+        let values = [
+            0.0,
+            0.5,
+            1.0,
+            0.999,
+            -0.1,
+            1.5,
+            f32::NAN,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            1e12,
+            -1e12,
+            0.25,
+            0.75,
+            -0.001,
+            0.5 / 255.0,
+            1.5 / 255.0,
+            0.49999997 / 65535.0,
+            2.5 / 65535.0,
+        ];
+        let mut bytes = [0u8; 18];
+        let mut words = [0u16; 18];
+
+        f32_to_u8(&values, &mut bytes);
+        f32_to_u16(&values, &mut words);
+
+        for (index, value) in values.iter().enumerate() {
+            assert_eq!(bytes[index], FloatCore::round(value * u8::MAX as f32) as u8);
+            assert_eq!(
+                words[index],
+                FloatCore::round(value * u16::MAX as f32) as u16
+            );
+        }
+    }
+}
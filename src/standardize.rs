@@ -0,0 +1,374 @@
+//! Standardization and scaling of samples with parameters per channel
+
+use crate::error::{Error, Result};
+use crate::layout::ChannelLayout;
+use crate::normalize::{Clamping, ConvertSample, Rounding};
+use crate::parallel;
+use crate::trace::span;
+use crate::traits::NormalizedFloat;
+use ndarray::{Array3, Array4, ArrayView1, ArrayView3, ArrayView4, Zip};
+use num_traits::{AsPrimitive, Zero};
+
+/// Normalize and standardize the `(y, x, z)` array in a single pass, keeping the layout.
+///
+/// Every value is converted with [`NormalizedFloat::to_f32_normalized`], after which the
+/// channel `mean` is subtracted and the result is divided by the channel `std`:
+///
+/// `output[[y, x, z]] = (normalized(array[[y, x, z]]) - mean[z]) / std[z]`
+///
+/// Values that can not be represented result in NaN. Use
+/// [`hwc_to_chw_normalized`](crate::hwc_to_chw_normalized) for a planar result.
+///
+/// Returns [`Error::ChannelMismatch`] if the array does not have `C` channels.
+pub fn standardize<T, const C: usize>(
+    array: ArrayView3<'_, T>,
+    mean: [f32; C],
+    std: [f32; C],
+) -> Result<Array3<f32>>
+where
+    T: NormalizedFloat<T> + AsPrimitive<f32> + AsPrimitive<f64>,
+{
+    span!(DEBUG, "standardize", shape = ?array.dim(), dtype = core::any::type_name::<T>());
+    check_channels(C, array.dim().2)?;
+    Ok(Zip::indexed(array)
+        .map_collect(|(_, _, z), value| standardize_sample(*value, mean[z], std[z])))
+}
+
+/// Undo [`standardize`], converting the values back into samples of `T`.
+///
+/// `output[[y, x, z]] = denormalized(array[[y, x, z]] * std[z] + mean[z])`
+///
+/// Values are converted with [`NormalizedFloat::from_f32_normalized`], so integers are
+/// rounded to the nearest value and clamped. Values that can not be represented become
+/// zero.
+///
+/// Returns [`Error::ChannelMismatch`] if the array does not have `C` channels.
+pub fn destandardize<T, const C: usize>(
+    array: ArrayView3<'_, f32>,
+    mean: [f32; C],
+    std: [f32; C],
+) -> Result<Array3<T>>
+where
+    T: NormalizedFloat<T> + AsPrimitive<f32> + AsPrimitive<f64> + Zero,
+{
+    span!(DEBUG, "destandardize", shape = ?array.dim(), dtype = core::any::type_name::<T>());
+    check_channels(C, array.dim().2)?;
+    Ok(Zip::indexed(array).map_collect(|(_, _, z), value| {
+        T::from_f32_normalized(value * std[z] + mean[z]).unwrap_or_else(T::zero)
+    }))
+}
+
+/// Normalize and standardize a batch of images in a single pass, keeping the layout.
+///
+/// The batch is either `(n, y, x, z)` for [`ChannelLayout::Hwc`] or `(n, z, y, x)` for
+/// [`ChannelLayout::Chw`], and every image is standardized like [`standardize`]. With the
+/// `rayon` feature, the images are processed in parallel.
+///
+/// Returns [`Error::ChannelMismatch`] if the images do not have `C` channels.
+pub fn standardize_batch<T, const C: usize>(
+    batch: ArrayView4<'_, T>,
+    layout: ChannelLayout,
+    mean: [f32; C],
+    std: [f32; C],
+) -> Result<Array4<f32>>
+where
+    T: NormalizedFloat<T> + AsPrimitive<f32> + AsPrimitive<f64> + Sync,
+{
+    span!(DEBUG, "standardize_batch", shape = ?batch.dim(), ?layout, dtype = core::any::type_name::<T>());
+    check_channels(C, batch_channels(batch.dim(), layout))?;
+    let mut output = Array4::zeros(batch.dim());
+    parallel::for_each_outer(batch, output.view_mut(), |image, mut output| {
+        Zip::indexed(&mut output)
+            .and(&image)
+            .for_each(|index, output, value| {
+                let z = channel(index, layout);
+                *output = standardize_sample(*value, mean[z], std[z]);
+            });
+    });
+    Ok(output)
+}
+
+/// Undo [`standardize_batch`], converting the values back into samples of `T`.
+///
+/// Every image is converted like [`destandardize`]. With the `rayon` feature, the images
+/// are processed in parallel.
+///
+/// Returns [`Error::ChannelMismatch`] if the images do not have `C` channels.
+pub fn destandardize_batch<T, const C: usize>(
+    batch: ArrayView4<'_, f32>,
+    layout: ChannelLayout,
+    mean: [f32; C],
+    std: [f32; C],
+) -> Result<Array4<T>>
+where
+    T: NormalizedFloat<T> + AsPrimitive<f32> + AsPrimitive<f64> + Zero + Send,
+{
+    span!(DEBUG, "destandardize_batch", shape = ?batch.dim(), ?layout, dtype = core::any::type_name::<T>());
+    check_channels(C, batch_channels(batch.dim(), layout))?;
+    let mut output = Array4::zeros(batch.dim());
+    parallel::for_each_outer(batch, output.view_mut(), |image, mut output| {
+        Zip::indexed(&mut output)
+            .and(&image)
+            .for_each(|index, output, value| {
+                let z = channel(index, layout);
+                *output = T::from_f32_normalized(value * std[z] + mean[z]).unwrap_or_else(T::zero);
+            });
+    });
+    Ok(output)
+}
+
+/// Normalize and standardize a single sample, resulting in NaN if it can not be represented.
+///
+/// Shared by [`standardize`], [`standardize_batch`] and
+/// [`hwc_to_chw_normalized`](crate::hwc_to_chw_normalized).
+pub(crate) fn standardize_sample<T>(value: T, mean: f32, std: f32) -> f32
+where
+    T: NormalizedFloat<T> + AsPrimitive<f32> + AsPrimitive<f64>,
+{
+    (value.to_f32_normalized().unwrap_or(f32::NAN) - mean) / std
+}
+
+/// Verify the amount of channels matches the expected amount.
+pub(crate) fn check_channels(expected: usize, got: usize) -> Result<()> {
+    match expected == got {
+        true => Ok(()),
+        false => Err(Error::ChannelMismatch { expected, got }),
+    }
+}
+
+/// Amount of channels of the images in the `(n, ...)` batch.
+fn batch_channels(dim: (usize, usize, usize, usize), layout: ChannelLayout) -> usize {
+    match layout {
+        ChannelLayout::Hwc => dim.3,
+        ChannelLayout::Chw => dim.1,
+    }
+}
+
+/// Channel of the index within a single image of the layout.
+fn channel((first, _, last): (usize, usize, usize), layout: ChannelLayout) -> usize {
+    match layout {
+        ChannelLayout::Hwc => last,
+        ChannelLayout::Chw => first,
+    }
+}
+
+/// Scale the plain values of the `(y, x, z)` array with a `scale` and `offset` per channel.
+///
+/// `output[[y, x, z]] = array[[y, x, z]] * scale[z] + offset[z]`
+///
+/// The parameters have a shape of `(C,)` and broadcast over the height and width, so
+/// channels with different ranges, like a depth next to an alpha or an ID channel, are
+/// normalized in one call.
+///
+/// Returns [`Error::ChannelMismatch`] if the parameters do not have a value per channel.
+pub fn normalize_channels<T>(
+    array: ArrayView3<'_, T>,
+    scale: ArrayView1<'_, f32>,
+    offset: ArrayView1<'_, f32>,
+) -> Result<Array3<f32>>
+where
+    T: AsPrimitive<f32>,
+{
+    span!(DEBUG, "normalize_channels", shape = ?array.dim(), dtype = core::any::type_name::<T>());
+    let channels = array.dim().2;
+    check_channels(channels, scale.len())?;
+    check_channels(channels, offset.len())?;
+    Ok(Zip::indexed(array).map_collect(|(_, _, z), value| value.as_() * scale[z] + offset[z]))
+}
+
+/// Undo [`normalize_channels`], converting the values back into samples of `T`.
+///
+/// `output[[y, x, z]] = (array[[y, x, z]] - offset[z]) / scale[z]`
+///
+/// Integers are rounded to the nearest value and clamped to the range of `T`.
+///
+/// Returns [`Error::ChannelMismatch`] if the parameters do not have a value per channel.
+pub fn denormalize_channels<T>(
+    array: ArrayView3<'_, f32>,
+    scale: ArrayView1<'_, f32>,
+    offset: ArrayView1<'_, f32>,
+) -> Result<Array3<T>>
+where
+    T: ConvertSample,
+{
+    span!(DEBUG, "denormalize_channels", shape = ?array.dim(), dtype = core::any::type_name::<T>());
+    let channels = array.dim().2;
+    check_channels(channels, scale.len())?;
+    check_channels(channels, offset.len())?;
+    Ok(Zip::indexed(array).map_collect(|(_, _, z), value| {
+        let value = f64::from((value - offset[z]) / scale[z]);
+        T::from_f64_with(value, false, Rounding::Nearest, Clamping::Clamp)
+            .expect("clamping never rejects a value")
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::{arr1, arr3};
+    use rstest::*;
+
+    const MEAN: [f32; 3] = [0.485, 0.456, 0.406];
+    const STD: [f32; 3] = [0.229, 0.224, 0.225];
+
+    #[test]
+    fn test_standardize() {
+        let array = Array3::from_shape_fn((8, 16, 3), |(y, x, c)| (y * 16 + x + c * 50) as u8);
+
+        let result = standardize(array.view(), MEAN, STD).unwrap();
+
+        assert_eq!(result.dim(), (8, 16, 3));
+        for ((y, x, c), value) in result.indexed_iter() {
+            let expected = (array[[y, x, c]] as f32 / 255.0 - MEAN[c]) / STD[c];
+            assert_eq!(*value, expected);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let array = Array3::from_shape_fn((16, 8, 3), |(x, y, c)| (y * 16 + x + c * 50) as u8);
+        let transposed = array.view().permuted_axes([1, 0, 2]);
+
+        let standardized = standardize(transposed, MEAN, STD).unwrap();
+        let result = destandardize::<u8, 3>(standardized.view(), MEAN, STD).unwrap();
+
+        assert_eq!(result, transposed);
+    }
+
+    #[test]
+    fn test_channel_mismatch() {
+        let array = Array3::<u16>::zeros((8, 16, 4));
+
+        assert_eq!(
+            standardize(array.view(), MEAN, STD).err(),
+            Some(Error::ChannelMismatch {
+                expected: 3,
+                got: 4
+            })
+        );
+        assert_eq!(
+            destandardize::<u16, 1>(array.mapv(f32::from).view(), [0.0], [1.0]).err(),
+            Some(Error::ChannelMismatch {
+                expected: 1,
+                got: 4
+            })
+        );
+    }
+
+    #[rstest]
+    #[case(ChannelLayout::Hwc)]
+    #[case(ChannelLayout::Chw)]
+    fn test_standardize_batch(#[case] layout: ChannelLayout) {
+        let images = Array4::from_shape_fn((4, 8, 16, 3), |(n, y, x, c)| {
+            (n * 40 + y * 16 + x + c * 50) as u8
+        });
+        let batch = match layout {
+            ChannelLayout::Hwc => images.clone(),
+            ChannelLayout::Chw => images
+                .view()
+                .permuted_axes([0, 3, 1, 2])
+                .as_standard_layout()
+                .into_owned(),
+        };
+
+        let result = standardize_batch(batch.view(), layout, MEAN, STD).unwrap();
+
+        assert_eq!(result.dim(), batch.dim());
+        for (item, image) in result.outer_iter().zip(images.outer_iter()) {
+            let item = match layout {
+                ChannelLayout::Hwc => item,
+                ChannelLayout::Chw => item.permuted_axes([1, 2, 0]),
+            };
+            assert_eq!(item, standardize(image, MEAN, STD).unwrap());
+        }
+        assert_eq!(
+            destandardize_batch::<u8, 3>(result.view(), layout, MEAN, STD).unwrap(),
+            batch
+        );
+    }
+
+    #[test]
+    fn test_batch_channel_mismatch() {
+        let batch = Array4::<u8>::zeros((2, 3, 4, 4));
+
+        assert_eq!(
+            standardize_batch(batch.view(), ChannelLayout::Hwc, MEAN, STD).err(),
+            Some(Error::ChannelMismatch {
+                expected: 3,
+                got: 4
+            })
+        );
+        assert!(standardize_batch(batch.view(), ChannelLayout::Chw, MEAN, STD).is_ok());
+        assert_eq!(
+            destandardize_batch::<u8, 3>(
+                batch.mapv(f32::from).view(),
+                ChannelLayout::Hwc,
+                MEAN,
+                STD
+            )
+            .err(),
+            Some(Error::ChannelMismatch {
+                expected: 3,
+                got: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_normalize_channels() {
+        let array = Array3::from_shape_fn((4, 8, 3), |(y, x, c)| match c {
+            0 => (y * 1000 + x) as u16,
+            1 => u16::MAX,
+            _ => (x % 4) as u16,
+        });
+        let scale = arr1(&[1.0 / 4000.0, 1.0 / 65535.0, 1.0]);
+        let offset = arr1(&[0.0, 0.0, -1.0]);
+
+        let result = normalize_channels(array.view(), scale.view(), offset.view()).unwrap();
+
+        assert_eq!(result[[2, 5, 0]], 2005.0 / 4000.0);
+        assert_eq!(result[[2, 5, 1]], 1.0);
+        assert_eq!(result[[2, 5, 2]], 0.0);
+        assert_eq!(
+            denormalize_channels::<u16>(result.view(), scale.view(), offset.view()).unwrap(),
+            array
+        );
+    }
+
+    #[test]
+    fn test_denormalize_channels_clamps() {
+        let array = Array3::from_shape_fn((2, 2, 2), |(y, x, _)| (y * 2 + x) as f32 - 1.5);
+        let scale = arr1(&[1.0 / 256.0, 0.5]);
+        let offset = arr1(&[0.0, 0.0]);
+
+        let result = denormalize_channels::<u8>(array.view(), scale.view(), offset.view()).unwrap();
+
+        assert_eq!(result, arr3(&[[[0, 0], [0, 0]], [[128, 1], [255, 3]]]));
+    }
+
+    #[test]
+    fn test_channels_mismatch() {
+        let array = Array3::<u8>::zeros((2, 2, 3));
+        let parameters = arr1(&[1.0, 1.0]);
+
+        assert_eq!(
+            normalize_channels(array.view(), parameters.view(), parameters.view()).err(),
+            Some(Error::ChannelMismatch {
+                expected: 3,
+                got: 2
+            })
+        );
+        assert_eq!(
+            denormalize_channels::<u8>(
+                array.mapv(f32::from).view(),
+                parameters.view(),
+                parameters.view()
+            )
+            .err(),
+            Some(Error::ChannelMismatch {
+                expected: 3,
+                got: 2
+            })
+        );
+    }
+}
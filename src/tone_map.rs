@@ -0,0 +1,155 @@
+//! Tone mapping of high dynamic range linear light into the displayable range
+
+use crate::trace::span;
+use crate::traits::NormalizedFloat;
+use crate::transfer::linear_to_srgb;
+use ndarray::{Array3, ArrayView3, ArrayViewMut3};
+use num_traits::{AsPrimitive, Zero};
+
+/// Operator that compresses high dynamic range values into `[0.0, 1.0]`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ToneMap {
+    /// Reinhard's `value / (1 + value)`, which never fully reaches white.
+    #[default]
+    Reinhard,
+    /// John Hable's filmic curve from Uncharted 2, with white at a linear value of 11.2.
+    Filmic,
+    /// Krzysztof Narkowicz's fit of the ACES filmic curve.
+    Aces,
+}
+
+impl ToneMap {
+    /// Map a single linear value, which is already scaled by the exposure.
+    ///
+    /// Negative values and NaN become 0.0.
+    pub fn apply(self, value: f32) -> f32 {
+        let value = value.max(0.0);
+        match self {
+            Self::Reinhard => value / (1.0 + value),
+            Self::Filmic => hable(value) / hable(FILMIC_WHITE),
+            Self::Aces => (value * (2.51 * value + 0.03)) / (value * (2.43 * value + 0.59) + 0.14),
+        }
+        .clamp(0.0, 1.0)
+    }
+}
+
+/// Linear value that the filmic curve maps onto white.
+const FILMIC_WHITE: f32 = 11.2;
+
+/// Hable's curve with the shoulder, linear and toe parameters of Uncharted 2.
+fn hable(value: f32) -> f32 {
+    const A: f32 = 0.15;
+    const B: f32 = 0.50;
+    const C: f32 = 0.10;
+    const D: f32 = 0.20;
+    const E: f32 = 0.02;
+    const F: f32 = 0.30;
+    (value * (A * value + C * B) + D * E) / (value * (A * value + B) + D * F) - E / F
+}
+
+/// Tone map the linear `(y, x, z)` array into linear values between 0.0 and 1.0.
+///
+/// The values are first multiplied by `2^exposure`, so an exposure of 1.0 brightens the
+/// image by a stop. All channels are mapped, so slice off an alpha channel first if it
+/// should stay as is. Encode the result with [`linear_to_srgb`] for display.
+pub fn tone_map(array: ArrayView3<'_, f32>, operator: ToneMap, exposure: f32) -> Array3<f32> {
+    span!(DEBUG, "tone_map", shape = ?array.dim(), ?operator);
+    let scale = exposure.exp2();
+    array.mapv(|value| operator.apply(value * scale))
+}
+
+/// Tone map the linear values in place, like [`tone_map`].
+pub fn tone_map_in_place(mut array: ArrayViewMut3<'_, f32>, operator: ToneMap, exposure: f32) {
+    span!(DEBUG, "tone_map_in_place", shape = ?array.dim(), ?operator);
+    let scale = exposure.exp2();
+    array.mapv_inplace(|value| operator.apply(value * scale));
+}
+
+/// Tone map the linear values straight into sRGB encoded samples of `T`, ready to save.
+///
+/// This combines [`tone_map`] with [`linear_to_srgb`]. Integers are rounded to the nearest
+/// value, so a `u8` output can directly be written to a PNG.
+pub fn tone_map_quantized<T>(
+    array: ArrayView3<'_, f32>,
+    operator: ToneMap,
+    exposure: f32,
+) -> Array3<T>
+where
+    T: NormalizedFloat<T> + AsPrimitive<f32> + AsPrimitive<f64> + Zero,
+{
+    span!(DEBUG, "tone_map_quantized", shape = ?array.dim(), ?operator, dtype = core::any::type_name::<T>());
+    let scale = exposure.exp2();
+    array.mapv(|value| {
+        let value = linear_to_srgb(operator.apply(value * scale));
+        T::from_f32_normalized(value).unwrap_or_else(T::zero)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case(ToneMap::Reinhard)]
+    #[case(ToneMap::Filmic)]
+    #[case(ToneMap::Aces)]
+    fn test_tone_map_range(#[case] operator: ToneMap) {
+        let array = Array3::from_shape_fn((8, 16, 3), |(y, x, z)| {
+            ((y * 48 + x * 3 + z) as f32 / 16.0).powi(2)
+        });
+
+        let result = tone_map(array.view(), operator, 0.0);
+
+        assert!(result[[0, 0, 0]] < 1e-6);
+        assert!(result.iter().all(|value| (0.0..=1.0).contains(value)));
+        assert!(
+            result
+                .iter()
+                .zip(result.iter().skip(1))
+                .all(|(a, b)| a <= b)
+        );
+    }
+
+    #[rstest]
+    #[case(ToneMap::Reinhard, 1.0, 0.5)]
+    #[case(ToneMap::Reinhard, 3.0, 0.75)]
+    #[case(ToneMap::Filmic, FILMIC_WHITE, 1.0)]
+    #[case(ToneMap::Filmic, 100.0, 1.0)]
+    #[case(ToneMap::Aces, 100.0, 1.0)]
+    #[case(ToneMap::Aces, -1.0, 0.0)]
+    #[case(ToneMap::Aces, f32::NAN, 0.0)]
+    fn test_tone_map_values(#[case] operator: ToneMap, #[case] value: f32, #[case] expected: f32) {
+        assert!((operator.apply(value) - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_tone_map_exposure() {
+        let array = Array3::from_elem((2, 2, 1), 0.5);
+        let mut in_place = array.clone();
+
+        tone_map_in_place(in_place.view_mut(), ToneMap::Reinhard, 1.0);
+
+        assert_eq!(in_place, Array3::from_elem((2, 2, 1), 0.5));
+        assert_eq!(tone_map(array.view(), ToneMap::Reinhard, 1.0), in_place);
+        assert_eq!(
+            tone_map(array.view(), ToneMap::Reinhard, -1.0)[[0, 0, 0]],
+            0.2
+        );
+    }
+
+    #[test]
+    fn test_tone_map_quantized() {
+        let array = Array3::from_shape_fn((4, 4, 3), |(y, x, _)| (y * 4 + x) as f32);
+
+        let result = tone_map_quantized::<u8>(array.view(), ToneMap::Reinhard, 0.0);
+
+        assert_eq!(result[[0, 0, 0]], 0);
+        assert_eq!(result[[0, 1, 0]], 188);
+        assert_eq!(
+            result,
+            tone_map(array.view(), ToneMap::Reinhard, 0.0)
+                .mapv(|value| (linear_to_srgb(value) * 255.0).round() as u8)
+        );
+    }
+}
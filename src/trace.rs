@@ -0,0 +1,80 @@
+//! Spans around conversions and IO, emitted with the `tracing` feature
+//!
+//! Every span carries the shape of the array and the sample types involved. The time spent
+//! in an operation is the time its span is entered, which subscribers report when the span
+//! closes, for example `time.busy` of the fmt subscriber with `FmtSpan::CLOSE`.
+
+/// Enter a span until the end of the enclosing block.
+///
+/// Without the `tracing` feature this expands to nothing, so the fields are not evaluated.
+macro_rules! span {
+    ($level:ident, $name:literal $(, $($fields:tt)*)?) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::span!(tracing::Level::$level, $name $(, $($fields)*)?).entered();
+    };
+}
+
+/// Record a field that is only known later on the current span.
+#[cfg(feature = "image")]
+macro_rules! record {
+    ($field:literal, $value:expr) => {
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record($field, tracing::field::debug($value));
+    };
+}
+
+#[cfg(feature = "image")]
+pub(crate) use record;
+pub(crate) use span;
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use ndarray::Array3;
+    use std::sync::Mutex;
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    /// Subscriber that keeps the names and fields of the created spans.
+    #[derive(Default)]
+    struct Spans(Mutex<Vec<String>>);
+
+    impl Subscriber for Spans {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut spans = self.0.lock().unwrap();
+            spans.push(format!("{} {:?}", span.metadata().name(), span.values()));
+            Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, _: &Event<'_>) {}
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[test]
+    fn test_spans() {
+        let spans = std::sync::Arc::new(Spans::default());
+        let array = Array3::from_shape_fn((4, 8, 3), |(y, x, z)| (y * 24 + x * 3 + z) as u8);
+
+        tracing::subscriber::with_default(spans.clone(), || {
+            span!(INFO, "outer", shape = ?array.dim());
+            crate::scale_depth::<u8, u16>(array.view());
+        });
+
+        let spans = spans.0.lock().unwrap();
+        assert_eq!(spans.len(), 2);
+        assert!(spans[0].starts_with("outer"));
+        assert!(spans[0].contains("(4, 8, 3)"));
+        assert!(spans[1].starts_with("scale_depth"));
+        assert!(spans[1].contains("u16"));
+    }
+}
@@ -1,17 +1,32 @@
 //! Implementations for ndarray casting and conversions for the ImageBuffer
 
 #[cfg(feature = "image")]
-use crate::error::{Error, Result};
+use crate::channels::{AlphaMode, ChannelCount, adapt_channels};
+use crate::error::{Error, NormalizeError, Result};
+use crate::normalize::SignedNormalization;
+use crate::normalize::{Clamping, Rounding, denormalize};
 #[cfg(feature = "image")]
-use image::{ImageBuffer, Pixel};
+use crate::paranoid::paranoid;
 #[cfg(feature = "image")]
-use ndarray::{Array, Array3, ArrayView3, ArrayViewMut, ArrayViewMut3, Dimension};
-use num_traits::{AsPrimitive, ToPrimitive};
+use crate::raw_parts::RawParts;
+use crate::trace::span;
+#[cfg(feature = "image")]
+use image::{GenericImageView, ImageBuffer, Luma, Pixel, Primitive, SubImage};
+#[cfg(feature = "image")]
+use ndarray::{
+    Array, Array2, ArrayView2, ArrayViewMut2, ArrayViewMut3, Dimension, ShapeBuilder, s,
+};
+use ndarray::{Array3, ArrayBase, ArrayView, ArrayView3, Axis, CowArray, Data, Ix2, Ix3, Zip};
+#[cfg(feature = "image")]
+use num_traits::PrimInt;
+use num_traits::{AsPrimitive, ToPrimitive, Zero};
+#[cfg(feature = "image")]
+use std::ops::{Deref, DerefMut};
 
 #[cfg(feature = "image")]
 /// Conversion methods for working with ndarrays.
 ///
-/// All methods work without copying any data.
+/// Methods work without copying any data, unless documented otherwise.
 pub trait ImageArray<P: image::Pixel, ImageContainer> {
     /// Cast the ImageBuffer as an ArrayView3.
     ///
@@ -49,6 +64,39 @@ pub trait ImageArray<P: image::Pixel, ImageContainer> {
     /// This does not copy the data, but it does consume the buffer.
     fn to_ndarray(self) -> Array3<ImageContainer>;
 
+    /// Copy the ImageBuffer into a caller provided ArrayViewMut3.
+    ///
+    /// * `Y` index is the row
+    /// * `X` index is the columns
+    /// * `Z` index is the channel
+    ///
+    /// So when referencing:
+    /// `array[[y, x, z]]`
+    ///
+    /// The target can be backed by any buffer, like a stack allocated array, so repeatedly
+    /// converting small images does not need to allocate.
+    ///
+    /// Returns [`Error::ShapeMismatch`] if the target does not have the shape of the image.
+    fn copy_to_ndarray(&self, target: ArrayViewMut3<'_, ImageContainer>) -> Result<()>;
+
+    /// Copy the ImageBuffer into an Array3 whose data pointer meets the requested alignment.
+    ///
+    /// * `Y` index is the row
+    /// * `X` index is the columns
+    /// * `Z` index is the channel
+    ///
+    /// So when referencing:
+    /// `array[[y, x, z]]`
+    ///
+    /// The alignment is in bytes, for example 64 for AVX-512 loads or 4096 for page
+    /// aligned transfers. The array is placed at an aligned offset within a slightly
+    /// larger allocation, so it stays a regular owned Array3. This also holds for empty
+    /// images.
+    ///
+    /// Returns [`Error::InvalidAlignment`] if the alignment is not a power of two or can not
+    /// be met within the allocation.
+    fn to_ndarray_aligned(&self, alignment: usize) -> Result<Array3<ImageContainer>>;
+
     /// Convert the provided array into the ImageBuffer
     ///
     /// * `Y` index is the row
@@ -58,10 +106,230 @@ pub trait ImageArray<P: image::Pixel, ImageContainer> {
     /// So when referencing:
     /// `array[[y, x, z]]`
     ///
-    /// This does not copy the data, but it does consume the buffer.
+    /// This does not copy the data of arrays in standard layout that start at the beginning
+    /// of their allocation, but it does consume the array. Other arrays, like sliced,
+    /// transposed or inverted ones, are copied once in logical order, like
+    /// [`RawParts::from_ndarray`] does.
+    ///
+    /// Returns [`Error::TooLarge`] if the width or height does not fit in a u32.
     fn from_ndarray<D: Dimension>(
         array: Array<ImageContainer, D>,
     ) -> Result<ImageBuffer<P, Vec<ImageContainer>>>;
+
+    /// Convert the ImageBuffer into a normalized Array3 of 32 bit floats.
+    ///
+    /// * `Y` index is the row
+    /// * `X` index is the columns
+    /// * `Z` index is the channel
+    ///
+    /// So when referencing:
+    /// `array[[y, x, z]]`
+    ///
+    /// Values are normalized with [`NormalizedFloat::to_f32_normalized`], so for example
+    /// a u8 value of 255 becomes 1.0. Values that can not be represented become NaN.
+    ///
+    /// This allocates the output once and converts in a single pass over the buffer.
+    fn to_ndarray_f32(&self) -> Array3<f32>
+    where
+        ImageContainer: NormalizedFloat<ImageContainer> + AsPrimitive<f32> + AsPrimitive<f64>;
+
+    /// Convert the ImageBuffer into a normalized Array3 of 64 bit floats.
+    ///
+    /// * `Y` index is the row
+    /// * `X` index is the columns
+    /// * `Z` index is the channel
+    ///
+    /// So when referencing:
+    /// `array[[y, x, z]]`
+    ///
+    /// Values are normalized with [`NormalizedFloat::to_f64_normalized`], so for example
+    /// a u8 value of 255 becomes 1.0. Values that can not be represented become NaN.
+    ///
+    /// This allocates the output once and converts in a single pass over the buffer.
+    fn to_ndarray_f64(&self) -> Array3<f64>
+    where
+        ImageContainer: NormalizedFloat<ImageContainer> + AsPrimitive<f32> + AsPrimitive<f64>;
+
+    /// Convert a normalized float array into the ImageBuffer with integer subpixels.
+    ///
+    /// * `Y` index is the row
+    /// * `X` index is the columns
+    /// * `Z` index is the channel
+    ///
+    /// So when referencing:
+    /// `array[[y, x, z]]`
+    ///
+    /// Values are scaled so 1.0 becomes the maximum value of the subpixel type, after
+    /// which `rounding` is applied and values outside of the range are handled with
+    /// `clamping`.
+    ///
+    /// Returns [`Error::OutOfRange`] with the `[y, x, z]` index of the first value that
+    /// is rejected by [`Clamping::Error`].
+    ///
+    /// This allocates the buffer once and converts in a single pass over the array.
+    fn from_ndarray_f32<D: Dimension>(
+        array: ArrayView<'_, f32, D>,
+        rounding: Rounding,
+        clamping: Clamping,
+    ) -> Result<ImageBuffer<P, Vec<ImageContainer>>>
+    where
+        ImageContainer: PrimInt + AsPrimitive<f64>,
+        f64: AsPrimitive<ImageContainer>,
+        i128: AsPrimitive<ImageContainer>;
+
+    /// Convert the provided array into the ImageBuffer, adapting the channel count if needed.
+    ///
+    /// * `Y` index is the row
+    /// * `X` index is the columns
+    /// * `Z` index is the channel
+    ///
+    /// So when referencing:
+    /// `array[[y, x, z]]`
+    ///
+    /// Instead of returning [`Error::ChannelMismatch`], an opaque alpha channel is added,
+    /// the alpha channel is dropped, gray is replicated into RGB or RGB is reduced to its
+    /// luminance. The `alpha` mode
+    /// defines how the color channels are handled when alpha gets dropped.
+    ///
+    /// This does not copy the data when the channel count already matches,
+    /// otherwise the adapted data is copied into a new buffer.
+    fn from_ndarray_adapted<D: Dimension>(
+        array: Array<ImageContainer, D>,
+        alpha: AlphaMode,
+    ) -> Result<ImageBuffer<P, Vec<ImageContainer>>>;
+
+    /// Cast the ImageBuffer as an ArrayView2 where every element is a whole pixel.
+    ///
+    /// * `Y` index is the row
+    /// * `X` index is the columns
+    ///
+    /// So when referencing:
+    /// `array[[y, x]]`, which returns all channels of that pixel as `[C; N]`.
+    ///
+    /// `N` has to match the channel count of the pixel, otherwise
+    /// [`Error::ChannelMismatch`] is returned.
+    ///
+    /// This does not copy the data, as it is a reference to the actual data in the buffer.
+    fn as_pixel_ndarray<'a, const N: usize>(
+        &'a self,
+    ) -> Result<ArrayView2<'a, [ImageContainer; N]>>;
+
+    /// Cast the ImageBuffer as an ArrayViewMut2 where every element is a whole pixel.
+    ///
+    /// * `Y` index is the row
+    /// * `X` index is the columns
+    ///
+    /// So when referencing:
+    /// `array[[y, x]]`, which returns all channels of that pixel as `[C; N]`.
+    ///
+    /// `N` has to match the channel count of the pixel, otherwise
+    /// [`Error::ChannelMismatch`] is returned.
+    ///
+    /// This does not copy the data, as it is a reference to the actual data in the buffer.
+    fn as_pixel_ndarray_mut<'a, const N: usize>(
+        &'a mut self,
+    ) -> Result<ArrayViewMut2<'a, [ImageContainer; N]>>;
+
+    /// Cast the ImageBuffer as an ArrayView2 where every element is a whole pixel.
+    ///
+    /// Same as [`ImageArray::as_pixel_ndarray`], but `N` is checked against the pixel
+    /// at compile time, so this can not fail.
+    ///
+    /// This does not copy the data, as it is a reference to the actual data in the buffer.
+    fn as_pixel_ndarray_const<'a, const N: usize>(&'a self) -> ArrayView2<'a, [ImageContainer; N]>
+    where
+        P: ChannelCount<N>;
+
+    /// Cast the ImageBuffer as an ArrayViewMut2 where every element is a whole pixel.
+    ///
+    /// Same as [`ImageArray::as_pixel_ndarray_mut`], but `N` is checked against the pixel
+    /// at compile time, so this can not fail.
+    ///
+    /// This does not copy the data, as it is a reference to the actual data in the buffer.
+    fn as_pixel_ndarray_const_mut<'a, const N: usize>(
+        &'a mut self,
+    ) -> ArrayViewMut2<'a, [ImageContainer; N]>
+    where
+        P: ChannelCount<N>;
+
+    /// Create an ImageBuffer from an Array2 where every element is a whole pixel.
+    ///
+    /// `N` is checked against the pixel at compile time, so only
+    /// [`Error::ImageConstructFailed`] is returned if the dimensions do not fit.
+    ///
+    /// This does not copy the data when the array is in standard layout and starts at
+    /// the beginning of its allocation, otherwise it is repacked once.
+    fn from_pixel_ndarray<const N: usize>(
+        array: Array2<[ImageContainer; N]>,
+    ) -> Result<ImageBuffer<P, Vec<ImageContainer>>>
+    where
+        P: ChannelCount<N>;
+
+    /// Cast a rectangular region of the ImageBuffer as an ArrayView3.
+    ///
+    /// * `Y` index is the row
+    /// * `X` index is the columns
+    /// * `Z` index is the channel
+    ///
+    /// So when referencing:
+    /// `array[[y, x, z]]`
+    ///
+    /// Index `[[0, 0, 0]]` is the top left corner of the region.
+    ///
+    /// Returns [`Error::OutOfBounds`] if the region does not fit within the image.
+    ///
+    /// This does not copy the data, as it is a reference to the actual data in the buffer.
+    fn as_ndarray_region<'a>(
+        &'a self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<ArrayView3<'a, ImageContainer>>;
+
+    /// Cast a rectangular region of the ImageBuffer as an ArrayViewMut3.
+    ///
+    /// * `Y` index is the row
+    /// * `X` index is the columns
+    /// * `Z` index is the channel
+    ///
+    /// So when referencing:
+    /// `array[[y, x, z]]`
+    ///
+    /// Index `[[0, 0, 0]]` is the top left corner of the region.
+    ///
+    /// Returns [`Error::OutOfBounds`] if the region does not fit within the image.
+    ///
+    /// This does not copy the data, as it is a reference to the actual data in the buffer.
+    fn as_ndarray_region_mut<'a>(
+        &'a mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<ArrayViewMut3<'a, ImageContainer>>;
+
+    /// Iterate over the scanlines of the ImageBuffer as ArrayView2 rows.
+    ///
+    /// Every row has the shape `(width, channels)`, so when referencing:
+    /// `row[[x, z]]`
+    ///
+    /// This does not copy the data, as every row is a reference to the actual data in the buffer.
+    fn rows_ndarray<'a>(&'a self) -> impl Iterator<Item = ArrayView2<'a, ImageContainer>>
+    where
+        ImageContainer: 'a;
+
+    /// Iterate over the scanlines of the ImageBuffer as ArrayViewMut2 rows.
+    ///
+    /// Every row has the shape `(width, channels)`, so when referencing:
+    /// `row[[x, z]]`
+    ///
+    /// This does not copy the data, as every row is a reference to the actual data in the buffer.
+    fn rows_ndarray_mut<'a>(
+        &'a mut self,
+    ) -> impl Iterator<Item = ArrayViewMut2<'a, ImageContainer>>
+    where
+        ImageContainer: 'a;
 }
 
 #[cfg(feature = "image")]
@@ -71,263 +339,2017 @@ where
     C: Clone + Copy,
 {
     fn as_ndarray<'a>(&'a self) -> ArrayView3<'a, C> {
+        let shape = buffer_shape(self);
+        ArrayView3::from_shape(shape, &self.as_raw()[..shape_size(shape)])
+            .expect("buffer holds a sample for every pixel")
+    }
+
+    fn to_ndarray(self) -> Array3<C> {
+        let shape = buffer_shape(&self);
+        let mut data = self.into_raw();
+        data.truncate(shape_size(shape));
+        Array3::from_shape_vec(shape, data).expect("buffer holds a sample for every pixel")
+    }
+    fn copy_to_ndarray(&self, mut target: ArrayViewMut3<'_, C>) -> Result<()> {
+        let source = self.as_ndarray();
+        if source.dim() != target.dim() {
+            return Err(Error::ShapeMismatch {
+                expected: source.dim(),
+                got: target.dim(),
+            });
+        }
+        target.assign(&source);
+        Ok(())
+    }
+
+    fn to_ndarray_aligned(&self, alignment: usize) -> Result<Array3<C>> {
+        if !alignment.is_power_of_two() {
+            return Err(Error::InvalidAlignment);
+        }
+        let source = self.as_ndarray();
+        let padding = alignment.div_ceil(std::mem::size_of::<C>().max(1));
+        // Room for a padding sample to point at when the image is empty.
+        let mut data = Vec::<C>::with_capacity(source.len().max(1) + padding);
+        let offset = data.as_ptr().align_offset(alignment);
+        if offset > padding {
+            return Err(Error::InvalidAlignment);
+        }
+        let fill = subpixel_min::<P>();
+        data.extend(std::iter::repeat_n(fill, offset));
+        data.extend_from_slice(&self.as_raw()[..source.len()]);
+
+        let array = if source.is_empty() {
+            // Empty slices keep the start of the allocation, so first move to a padding
+            // sample at the aligned offset and only then drop it.
+            data.push(fill);
+            Array::from_vec(data)
+                .slice_move(s![offset..offset + 1])
+                .slice_move(s![..0])
+        } else {
+            Array::from_vec(data).slice_move(s![offset..])
+        };
+        Ok(array.into_shape_with_order(source.dim())?)
+    }
+
+    fn from_ndarray<D: Dimension>(array: Array<C, D>) -> Result<ImageBuffer<P, Vec<C>>> {
+        let (width, height, _) = image_shape::<P>(array.shape())?;
+        image_dimensions(width, height)?;
+        let array = match array.ndim() {
+            2 => array.into_dimensionality::<Ix2>()?.insert_axis(Axis(2)),
+            _ => array.into_dimensionality::<Ix3>()?,
+        };
+
+        let image = RawParts::from_ndarray(array).into_image::<P>()?;
+        paranoid! {
+            crate::paranoid::check_samples(image.len(), buffer_shape(&image));
+        }
+        Ok(image)
+    }
+
+    fn to_ndarray_f32(&self) -> Array3<f32>
+    where
+        C: NormalizedFloat<C> + AsPrimitive<f32> + AsPrimitive<f64>,
+    {
+        let array = self.as_ndarray();
+        let normalized = array
+            .to_f32_normalized()
+            .unwrap_or_else(|_| array.mapv(|value| value.to_f32_normalized().unwrap_or(f32::NAN)));
+        paranoid! {
+            crate::paranoid::check_normalized(&array, &normalized.view(), |value| {
+                value.to_f32_normalized()
+            });
+        }
+        normalized
+    }
+
+    fn to_ndarray_f64(&self) -> Array3<f64>
+    where
+        C: NormalizedFloat<C> + AsPrimitive<f32> + AsPrimitive<f64>,
+    {
+        let array = self.as_ndarray();
+        let normalized = array.mapv(|value| value.to_f64_normalized().unwrap_or(f64::NAN));
+        paranoid! {
+            crate::paranoid::check_normalized(&array, &normalized.view(), |value| {
+                value.to_f64_normalized()
+            });
+        }
+        normalized
+    }
+
+    fn from_ndarray_f32<D: Dimension>(
+        array: ArrayView<'_, f32, D>,
+        rounding: Rounding,
+        clamping: Clamping,
+    ) -> Result<ImageBuffer<P, Vec<C>>>
+    where
+        C: PrimInt + AsPrimitive<f64>,
+        f64: AsPrimitive<C>,
+        i128: AsPrimitive<C>,
+    {
+        span!(DEBUG, "from_ndarray_f32", shape = ?array.shape(), dtype = core::any::type_name::<C>());
+        paranoid! {
+            crate::paranoid::check_normalized_range(&array);
+        }
+        let (width, height, channels) = image_shape::<P>(array.shape())?;
+        let data = array
+            .iter()
+            .enumerate()
+            .map(|(position, value)| {
+                denormalize(f64::from(*value), rounding, clamping).ok_or(Error::OutOfRange([
+                    position / (width * channels),
+                    position / channels % width,
+                    position % channels,
+                ]))
+            })
+            .collect::<Result<_>>()?;
+        let (width, height) = image_dimensions(width, height)?;
+        let image = Self::from_raw(width, height, data).ok_or(Error::ImageConstructFailed)?;
+        paranoid! {
+            crate::paranoid::check_samples(image.len(), buffer_shape(&image));
+        }
+        Ok(image)
+    }
+
+    fn from_ndarray_adapted<D: Dimension>(
+        array: Array<C, D>,
+        alpha: AlphaMode,
+    ) -> Result<ImageBuffer<P, Vec<C>>> {
+        let view = match array.ndim() {
+            2 => array
+                .view()
+                .into_dimensionality::<Ix2>()?
+                .insert_axis(Axis(2)),
+            3 => array.view().into_dimensionality::<Ix3>()?,
+            _ => return Err(Error::Dimensions),
+        };
+        if view.len_of(Axis(2)) == P::CHANNEL_COUNT.into() {
+            return Self::from_ndarray(array);
+        }
+        Self::from_ndarray(adapt_channels::<P>(view, alpha)?)
+    }
+
+    fn as_ndarray_mut<'a>(&'a mut self) -> ArrayViewMut3<'a, C> {
+        let shape = buffer_shape(self);
+        ArrayViewMut3::from_shape(shape, &mut self.deref_mut()[..shape_size(shape)])
+            .expect("buffer holds a sample for every pixel")
+    }
+
+    fn as_pixel_ndarray<'a, const N: usize>(&'a self) -> Result<ArrayView2<'a, [C; N]>> {
+        if N != P::CHANNEL_COUNT.into() {
+            return Err(Error::ChannelMismatch {
+                expected: P::CHANNEL_COUNT.into(),
+                got: N,
+            });
+        }
+        let (width, height) = self.dimensions();
+        let size = height as usize * width as usize * N;
+
+        let (pixels, _) = self.as_raw()[..size].as_chunks::<N>();
+        let view = ArrayView2::from_shape((height as usize, width as usize), pixels)?;
+        paranoid! {
+            crate::paranoid::check_view(&view, &[height as usize, width as usize], &[width as usize, 1]);
+        }
+        Ok(view)
+    }
+
+    fn as_pixel_ndarray_mut<'a, const N: usize>(&'a mut self) -> Result<ArrayViewMut2<'a, [C; N]>> {
+        if N != P::CHANNEL_COUNT.into() {
+            return Err(Error::ChannelMismatch {
+                expected: P::CHANNEL_COUNT.into(),
+                got: N,
+            });
+        }
         let (width, height) = self.dimensions();
-        unsafe {
-            ArrayView3::from_shape_ptr(
-                (height as usize, width as usize, P::CHANNEL_COUNT as usize),
-                self.as_raw().as_ptr(),
-            )
+        let size = height as usize * width as usize * N;
+
+        let (pixels, _) = (**self)[..size].as_chunks_mut::<N>();
+        let view = ArrayViewMut2::from_shape((height as usize, width as usize), pixels)?;
+        paranoid! {
+            crate::paranoid::check_view(&view.view(), &[height as usize, width as usize], &[width as usize, 1]);
+        }
+        Ok(view)
+    }
+
+    fn as_pixel_ndarray_const<'a, const N: usize>(&'a self) -> ArrayView2<'a, [C; N]>
+    where
+        P: ChannelCount<N>,
+    {
+        self.as_pixel_ndarray()
+            .expect("channel count is checked at compile time")
+    }
+
+    fn as_pixel_ndarray_const_mut<'a, const N: usize>(&'a mut self) -> ArrayViewMut2<'a, [C; N]>
+    where
+        P: ChannelCount<N>,
+    {
+        self.as_pixel_ndarray_mut()
+            .expect("channel count is checked at compile time")
+    }
+
+    fn from_pixel_ndarray<const N: usize>(array: Array2<[C; N]>) -> Result<ImageBuffer<P, Vec<C>>>
+    where
+        P: ChannelCount<N>,
+    {
+        let (height, width) = array.dim();
+        let (_, pixels) = RawParts::from_ndarray(array.insert_axis(Axis(2))).into_inner();
+        let image: Self = RawParts::new(height, width, N, pixels.into_flattened())?.into_image()?;
+        paranoid! {
+            crate::paranoid::check_samples(image.len(), (height, width, N));
+        }
+        Ok(image)
+    }
+
+    fn as_ndarray_region<'a>(
+        &'a self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<ArrayView3<'a, C>> {
+        let (image_width, image_height) = self.dimensions();
+        check_region(x, y, width, height, image_width, image_height)?;
+        let (x, y, width, height) = (x as usize, y as usize, width as usize, height as usize);
+
+        let view = self
+            .as_ndarray()
+            .slice_move(s![y..y + height, x..x + width, ..]);
+        paranoid! {
+            let channels = P::CHANNEL_COUNT as usize;
+            crate::paranoid::check_view(
+                &view,
+                &[height, width, channels],
+                &[image_width as usize * channels, channels, 1],
+            );
+        }
+        Ok(view)
+    }
+
+    fn as_ndarray_region_mut<'a>(
+        &'a mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<ArrayViewMut3<'a, C>> {
+        let (image_width, image_height) = self.dimensions();
+        check_region(x, y, width, height, image_width, image_height)?;
+        let (x, y, width, height) = (x as usize, y as usize, width as usize, height as usize);
+
+        let view = self
+            .as_ndarray_mut()
+            .slice_move(s![y..y + height, x..x + width, ..]);
+        paranoid! {
+            let channels = P::CHANNEL_COUNT as usize;
+            crate::paranoid::check_view(
+                &view.view(),
+                &[height, width, channels],
+                &[image_width as usize * channels, channels, 1],
+            );
+        }
+        Ok(view)
+    }
+
+    fn rows_ndarray<'a>(&'a self) -> impl Iterator<Item = ArrayView2<'a, C>>
+    where
+        C: 'a,
+    {
+        let array = self.as_ndarray();
+        (0..array.len_of(Axis(0))).map(move |y| {
+            let row = array.index_axis_move(Axis(0), y);
+            paranoid! {
+                let (_, width, channels) = array.dim();
+                crate::paranoid::check_view(&row, &[width, channels], &[channels, 1]);
+            }
+            row
+        })
+    }
+
+    fn rows_ndarray_mut<'a>(&'a mut self) -> impl Iterator<Item = ArrayViewMut2<'a, C>>
+    where
+        C: 'a,
+    {
+        let mut remaining = Some(self.as_ndarray_mut());
+        std::iter::from_fn(move || {
+            let array = remaining.take()?;
+            if array.len_of(Axis(0)) == 0 {
+                return None;
+            }
+            let (row, rest) = array.split_at(Axis(0), 1);
+            remaining = Some(rest);
+            let row = row.index_axis_move(Axis(0), 0);
+            paranoid! {
+                let (width, channels) = row.dim();
+                crate::paranoid::check_view(&row.view(), &[width, channels], &[channels, 1]);
+            }
+            Some(row)
+        })
+    }
+}
+
+#[cfg(feature = "image")]
+/// Shape of the buffer as `(y, x, z)`.
+///
+/// ImageBuffer guarantees its samples hold at least this many values, any surplus at the
+/// end of the buffer is not part of the image.
+fn buffer_shape<P, C>(buffer: &ImageBuffer<P, Vec<C>>) -> (usize, usize, usize)
+where
+    P: Pixel<Subpixel = C>,
+{
+    let (width, height) = buffer.dimensions();
+    (height as usize, width as usize, P::CHANNEL_COUNT as usize)
+}
+
+#[cfg(feature = "image")]
+/// Smallest value of the samples of the pixel.
+fn subpixel_min<P: Pixel>() -> P::Subpixel {
+    P::Subpixel::DEFAULT_MIN_VALUE
+}
+
+#[cfg(feature = "image")]
+/// Amount of samples of the `(y, x, z)` shape.
+fn shape_size((height, width, channels): (usize, usize, usize)) -> usize {
+    height * width * channels
+}
+
+#[cfg(feature = "image")]
+/// Interpret the `(y, x)` or `(y, x, z)` shape as `(width, height, channels)` for the pixel.
+fn image_shape<P: Pixel>(shape: &[usize]) -> Result<(usize, usize, usize)> {
+    if shape.len() < 2 {
+        return Err(Error::Dimensions);
+    };
+
+    let (width, height) = (shape[1], shape[0]);
+    let channels = match shape.len() {
+        2 => 1,
+        3 => shape[2],
+        _ => return Err(Error::Dimensions),
+    };
+
+    if channels != P::CHANNEL_COUNT.into() {
+        return Err(Error::ChannelMismatch {
+            expected: P::CHANNEL_COUNT.into(),
+            got: channels,
+        });
+    }
+    Ok((width, height, channels))
+}
+
+#[cfg(feature = "image")]
+/// Convert the width and height into the dimensions of an ImageBuffer.
+///
+/// Returns [`Error::TooLarge`] if either does not fit in a u32.
+fn image_dimensions(width: usize, height: usize) -> Result<(u32, u32)> {
+    match (u32::try_from(width), u32::try_from(height)) {
+        (Ok(width), Ok(height)) => Ok((width, height)),
+        _ => Err(Error::TooLarge),
+    }
+}
+
+/// Verify the region is fully contained by an image of the provided dimensions.
+pub(crate) fn check_region(
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    image_width: u32,
+    image_height: u32,
+) -> Result<()> {
+    let fits_x = u64::from(x) + u64::from(width) <= u64::from(image_width);
+    let fits_y = u64::from(y) + u64::from(height) <= u64::from(image_height);
+    if !(fits_x && fits_y) {
+        return Err(Error::OutOfBounds);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "image")]
+/// Conversion methods for turning ndarrays into ImageBuffers.
+///
+/// This is the array side counterpart of [`ImageArray`], which reads more naturally
+/// when the array is the primary object in a pipeline.
+///
+/// Both 2d `(y, x)` and 3d `(y, x, z)` arrays are supported.
+pub trait NdarrayImageExt<ImageContainer> {
+    /// Convert the array into an ImageBuffer.
+    ///
+    /// This does not copy the data when the array is owned and in standard layout,
+    /// but it does consume the array.
+    fn into_image<P>(self) -> Result<ImageBuffer<P, Vec<ImageContainer>>>
+    where
+        P: Pixel<Subpixel = ImageContainer>;
+
+    /// Copy the array into a new ImageBuffer.
+    fn to_image<P>(&self) -> Result<ImageBuffer<P, Vec<ImageContainer>>>
+    where
+        P: Pixel<Subpixel = ImageContainer>;
+
+    /// Reference the array as an ImageBuffer backed by a slice.
+    ///
+    /// Returns [`Error::NotContiguous`] if the array is not contiguous in standard layout.
+    ///
+    /// This does not copy the data, as it is a reference to the actual data in the array.
+    fn as_image_view<P>(&self) -> Result<ImageBuffer<P, &[ImageContainer]>>
+    where
+        P: Pixel<Subpixel = ImageContainer>;
+}
+
+#[cfg(feature = "image")]
+impl<S, C, D> NdarrayImageExt<C> for ArrayBase<S, D>
+where
+    S: Data<Elem = C>,
+    C: Clone + Copy,
+    D: Dimension,
+{
+    fn into_image<P>(self) -> Result<ImageBuffer<P, Vec<C>>>
+    where
+        P: Pixel<Subpixel = C>,
+    {
+        if self.is_standard_layout() {
+            ImageBuffer::from_ndarray(self.into_owned())
+        } else {
+            ImageBuffer::from_ndarray(self.as_standard_layout().into_owned())
         }
     }
 
-    fn to_ndarray(self) -> Array3<C> {
-        let (width, height) = self.dimensions();
-        unsafe {
-            Array3::from_shape_vec_unchecked(
-                (height as usize, width as usize, P::CHANNEL_COUNT as usize),
-                self.into_raw(),
-            )
+    fn to_image<P>(&self) -> Result<ImageBuffer<P, Vec<C>>>
+    where
+        P: Pixel<Subpixel = C>,
+    {
+        ImageBuffer::from_ndarray(self.as_standard_layout().into_owned())
+    }
+
+    fn as_image_view<P>(&self) -> Result<ImageBuffer<P, &[C]>>
+    where
+        P: Pixel<Subpixel = C>,
+    {
+        let (width, height, _) = image_shape::<P>(self.shape())?;
+        let data = self.as_slice().ok_or(Error::NotContiguous)?;
+        let (width, height) = image_dimensions(width, height)?;
+        ImageBuffer::from_raw(width, height, data).ok_or(Error::ImageConstructFailed)
+    }
+}
+
+#[cfg(feature = "image")]
+/// Conversion methods for working with ndarrays on a SubImage.
+///
+/// The views are strided views into the parent ImageBuffer, so no data is copied.
+pub trait SubImageArray<ImageContainer> {
+    /// Cast the SubImage as an ArrayView3.
+    ///
+    /// * `Y` index is the row
+    /// * `X` index is the columns
+    /// * `Z` index is the channel
+    ///
+    /// So when referencing:
+    /// `array[[y, x, z]]`
+    ///
+    /// Index `[[0, 0, 0]]` is the top left corner of the SubImage, not of the parent buffer.
+    ///
+    /// This does not copy the data, as it is a reference to the actual data in the parent buffer.
+    fn as_ndarray<'a>(&'a self) -> ArrayView3<'a, ImageContainer>;
+
+    /// Copy the SubImage into an owned Array3.
+    ///
+    /// * `Y` index is the row
+    /// * `X` index is the columns
+    /// * `Z` index is the channel
+    ///
+    /// So when referencing:
+    /// `array[[y, x, z]]`
+    ///
+    /// The region is not contiguous in the parent buffer, so this does copy the data.
+    fn to_ndarray(&self) -> Array3<ImageContainer>;
+}
+
+#[cfg(feature = "image")]
+/// Mutable conversion methods for working with ndarrays on a SubImage.
+pub trait SubImageArrayMut<ImageContainer>: SubImageArray<ImageContainer> {
+    /// Cast the SubImage as an ArrayViewMut3.
+    ///
+    /// * `Y` index is the row
+    /// * `X` index is the columns
+    /// * `Z` index is the channel
+    ///
+    /// So when referencing:
+    /// `array[[y, x, z]]`
+    ///
+    /// This does not copy the data, as it is a reference to the actual data in the parent buffer.
+    fn as_ndarray_mut<'a>(&'a mut self) -> ArrayViewMut3<'a, ImageContainer>;
+}
+
+#[cfg(feature = "image")]
+impl<I, P, C> SubImageArray<C> for SubImage<I>
+where
+    I: Deref<Target = ImageBuffer<P, Vec<C>>>,
+    P: Pixel<Subpixel = C> + 'static,
+    C: Clone + Copy,
+{
+    fn as_ndarray<'a>(&'a self) -> ArrayView3<'a, C> {
+        let (x, y) = self.offsets();
+        let (width, height) = self.dimensions();
+        let (x, y, width, height) = (x as usize, y as usize, width as usize, height as usize);
+
+        let view = self
+            .inner()
+            .as_ndarray()
+            .slice_move(s![y..y + height, x..x + width, ..]);
+        paranoid! {
+            let channels = P::CHANNEL_COUNT as usize;
+            crate::paranoid::check_view(
+                &view,
+                &[height, width, channels],
+                &[self.inner().width() as usize * channels, channels, 1],
+            );
+        }
+        view
+    }
+
+    fn to_ndarray(&self) -> Array3<C> {
+        self.as_ndarray().to_owned()
+    }
+}
+
+#[cfg(feature = "image")]
+impl<I, P, C> SubImageArrayMut<C> for SubImage<I>
+where
+    I: DerefMut<Target = ImageBuffer<P, Vec<C>>>,
+    P: Pixel<Subpixel = C> + 'static,
+    C: Clone + Copy,
+{
+    fn as_ndarray_mut<'a>(&'a mut self) -> ArrayViewMut3<'a, C> {
+        let (x, y) = self.offsets();
+        let (width, height) = self.dimensions();
+        let (x, y, width, height) = (x as usize, y as usize, width as usize, height as usize);
+
+        #[cfg(feature = "paranoid")]
+        let image_width = self.inner().width() as usize;
+        let view =
+            self.inner_mut()
+                .as_ndarray_mut()
+                .slice_move(s![y..y + height, x..x + width, ..]);
+        paranoid! {
+            let channels = P::CHANNEL_COUNT as usize;
+            crate::paranoid::check_view(
+                &view.view(),
+                &[height, width, channels],
+                &[image_width * channels, channels, 1],
+            );
+        }
+        view
+    }
+}
+
+#[cfg(feature = "image")]
+/// Conversion methods specific to grayscale ImageBuffers.
+pub trait LumaArray<ImageContainer> {
+    /// Cast the grayscale ImageBuffer as an RGB shaped ArrayView3.
+    ///
+    /// * `Y` index is the row
+    /// * `X` index is the columns
+    /// * `Z` index is the channel, all three referencing the same gray value
+    ///
+    /// So when referencing:
+    /// `array[[y, x, z]]`
+    ///
+    /// The channel axis has a stride of zero, so this does not copy or triple the data.
+    fn as_rgb_ndarray<'a>(&'a self) -> ArrayView3<'a, ImageContainer>;
+}
+
+#[cfg(feature = "image")]
+impl<C> LumaArray<C> for ImageBuffer<Luma<C>, Vec<C>>
+where
+    Luma<C>: Pixel<Subpixel = C>,
+    C: Clone + Copy,
+{
+    fn as_rgb_ndarray<'a>(&'a self) -> ArrayView3<'a, C> {
+        let (width, height) = (self.width() as usize, self.height() as usize);
+        ArrayView3::from_shape(
+            (height, width, 3).strides((width, 1, 0)),
+            &self.as_raw()[..height * width],
+        )
+        .expect("gray buffer always fits the broadcast shape")
+    }
+}
+
+/// Conversion into a CowArray in standard `(y, x, z)` layout.
+///
+/// This allows generic code to accept images, sub images and arrays alike.
+/// When the source already is contiguous in standard layout, the result borrows
+/// the data without copying. Otherwise the data is repacked into an owned array.
+pub trait CowNdarray<ImageContainer> {
+    /// Represent the data as a CowArray with the shape `(y, x, z)`.
+    ///
+    /// * `Y` index is the row
+    /// * `X` index is the columns
+    /// * `Z` index is the channel
+    ///
+    /// So when referencing:
+    /// `array[[y, x, z]]`
+    ///
+    /// This only copies the data when the source is not in standard layout.
+    fn as_cow_ndarray<'a>(&'a self) -> CowArray<'a, ImageContainer, Ix3>;
+}
+
+/// Borrow the view when it already is in standard layout, otherwise repack it.
+fn standard_layout<'a, C: Clone>(view: ArrayView<'a, C, Ix3>) -> CowArray<'a, C, Ix3> {
+    if view.is_standard_layout() {
+        CowArray::from(view)
+    } else {
+        CowArray::from(view.as_standard_layout().into_owned())
+    }
+}
+
+impl<S, C> CowNdarray<C> for ArrayBase<S, Ix3>
+where
+    S: Data<Elem = C>,
+    C: Clone,
+{
+    fn as_cow_ndarray<'a>(&'a self) -> CowArray<'a, C, Ix3> {
+        standard_layout(self.view())
+    }
+}
+
+impl<S, C> CowNdarray<C> for ArrayBase<S, Ix2>
+where
+    S: Data<Elem = C>,
+    C: Clone,
+{
+    fn as_cow_ndarray<'a>(&'a self) -> CowArray<'a, C, Ix3> {
+        standard_layout(self.view().insert_axis(Axis(2)))
+    }
+}
+
+#[cfg(feature = "image")]
+impl<P, C> CowNdarray<C> for ImageBuffer<P, Vec<C>>
+where
+    P: Pixel<Subpixel = C>,
+    C: Clone + Copy,
+{
+    fn as_cow_ndarray<'a>(&'a self) -> CowArray<'a, C, Ix3> {
+        CowArray::from(self.as_ndarray())
+    }
+}
+
+#[cfg(feature = "image")]
+impl<I, P, C> CowNdarray<C> for SubImage<I>
+where
+    I: Deref<Target = ImageBuffer<P, Vec<C>>>,
+    P: Pixel<Subpixel = C> + 'static,
+    C: Clone + Copy,
+{
+    fn as_cow_ndarray<'a>(&'a self) -> CowArray<'a, C, Ix3> {
+        standard_layout(SubImageArray::as_ndarray(self))
+    }
+}
+
+/// Trait for converting the provided value to a normalized float.
+///
+/// This is used for image processing where a lot of operations rely on floating values.
+pub trait NormalizedFloat<T>
+where
+    T: AsPrimitive<f32> + AsPrimitive<f64>,
+{
+    /// Convert the value to a 32 bit float.
+    ///
+    /// The value will be in a normalized range according to color depths.
+    ///
+    /// For example in u8, a value of 255 would be represented as 1.0.
+    ///
+    /// Returns None if it overflows and could not be represented.
+    fn to_f32_normalized(&self) -> Option<f32>;
+    /// Convert the value to a 64 bit float
+    ///
+    /// The value will be in a normalized range according to color depths.
+    ///
+    /// For example in u8, a value of 255 would be represented as 1.0.
+    ///
+    /// Returns None if it overflows and could not be represented.
+    fn to_f64_normalized(&self) -> Option<f64>;
+
+    /// Converts the f32 value to the provided type
+    ///
+    /// Integer types round to the nearest value and saturate at their range.
+    ///
+    /// Returns None if it overflows and could not be represented.
+    fn from_f32_normalized(value: f32) -> Option<T>;
+
+    /// Converts the f64 value to the provided type
+    ///
+    /// Integer types round to the nearest value and saturate at their range.
+    ///
+    /// Returns None if it overflows and could not be represented.
+    fn from_f64_normalized(value: f64) -> Option<T>;
+
+    /// Converts the f32 value to the provided type with the rounding and clamping of
+    /// integer types.
+    ///
+    /// Float types ignore both policies.
+    ///
+    /// Returns None if it overflows and could not be represented, or if the value is
+    /// rejected by [`Clamping::Error`].
+    fn from_f32_normalized_with(value: f32, rounding: Rounding, clamping: Clamping) -> Option<T> {
+        let _ = (rounding, clamping);
+        Self::from_f32_normalized(value)
+    }
+
+    /// Converts the f64 value to the provided type with the rounding and clamping of
+    /// integer types.
+    ///
+    /// Float types ignore both policies.
+    ///
+    /// Returns None if it overflows and could not be represented, or if the value is
+    /// rejected by [`Clamping::Error`].
+    fn from_f64_normalized_with(value: f64, rounding: Rounding, clamping: Clamping) -> Option<T> {
+        let _ = (rounding, clamping);
+        Self::from_f64_normalized(value)
+    }
+
+    /// Convert the value to a 64 bit float, mapping signed integers as described by `signed`.
+    ///
+    /// With [`SignedNormalization::Legacy`] this is the same as [`Self::to_f64_normalized`].
+    /// Unsigned and float types ignore the mode.
+    ///
+    /// Returns None if it overflows and could not be represented.
+    fn to_f64_normalized_signed(&self, signed: SignedNormalization) -> Option<f64> {
+        let _ = signed;
+        self.to_f64_normalized()
+    }
+
+    /// Converts the f64 value to the provided type, mapping it onto signed integers as
+    /// described by `signed`.
+    ///
+    /// With [`SignedNormalization::Legacy`] this is the same as [`Self::from_f64_normalized`].
+    /// The other modes round to the nearest value and saturate at the range of the type.
+    ///
+    /// Returns None if it overflows and could not be represented.
+    fn from_f64_normalized_signed(value: f64, signed: SignedNormalization) -> Option<T> {
+        let _ = signed;
+        Self::from_f64_normalized(value)
+    }
+
+    /// Convert the value to a 32 bit float, like [`Self::to_f32_normalized`].
+    ///
+    /// Returns [`NormalizeError::NotANumber`] for NaN and [`NormalizeError::Overflow`] if the
+    /// result is infinite, for example when narrowing a large f64.
+    fn try_to_f32_normalized(&self) -> core::result::Result<f32, NormalizeError> {
+        let converted = self.to_f32_normalized().ok_or(NormalizeError::Overflow)?;
+        check_finite(converted.into())?;
+        Ok(converted)
+    }
+
+    /// Convert the value to a 64 bit float, like [`Self::to_f64_normalized`].
+    ///
+    /// Returns [`NormalizeError::NotANumber`] for NaN and [`NormalizeError::Overflow`] if the
+    /// result is infinite.
+    fn try_to_f64_normalized(&self) -> core::result::Result<f64, NormalizeError> {
+        check_finite(self.to_f64_normalized().ok_or(NormalizeError::Overflow)?)
+    }
+
+    /// Converts the f32 value to the provided type, rejecting values instead of saturating.
+    ///
+    /// Integer types round to the nearest value.
+    ///
+    /// Returns [`NormalizeError::NotANumber`] for NaN, [`NormalizeError::Overflow`] for
+    /// infinite values or results and [`NormalizeError::OutOfRange`] for values outside of
+    /// the range of an integer type.
+    fn try_from_f32_normalized(value: f32) -> core::result::Result<T, NormalizeError> {
+        check_finite(value.into())?;
+        let converted = Self::from_f32_normalized_with(value, Rounding::Nearest, Clamping::Error)
+            .ok_or(NormalizeError::OutOfRange)?;
+        check_finite(converted.as_())?;
+        Ok(converted)
+    }
+
+    /// Converts the f64 value to the provided type, rejecting values instead of saturating.
+    ///
+    /// See [`Self::try_from_f32_normalized`] for the errors.
+    fn try_from_f64_normalized(value: f64) -> core::result::Result<T, NormalizeError> {
+        check_finite(value)?;
+        let converted = Self::from_f64_normalized_with(value, Rounding::Nearest, Clamping::Error)
+            .ok_or(NormalizeError::OutOfRange)?;
+        check_finite(converted.as_())?;
+        Ok(converted)
+    }
+
+    /// Convert the values to 32 bit floats in `output`, like [`Self::to_f32_normalized`].
+    ///
+    /// With the `simd` feature, u8 and u16 use vectorized kernels, otherwise they look up
+    /// every value in a table that is built once. With the `rayon` feature, the integer
+    /// types up to 32 bits convert in parallel. All give identical results.
+    ///
+    /// `output` needs to have the same length as `values`, which is checked in debug builds.
+    ///
+    /// Returns the position of the first value that could not be represented.
+    fn to_f32_normalized_slice(
+        values: &[Self],
+        output: &mut [f32],
+    ) -> core::result::Result<(), usize>
+    where
+        Self: Sized,
+    {
+        to_f32_slice(values, output)
+    }
+
+    /// Convert the 32 bit floats to the provided type in `output`, like
+    /// [`Self::from_f32_normalized`].
+    ///
+    /// With the `simd` feature, u8 and u16 use vectorized kernels and with the `rayon`
+    /// feature, the integer types up to 32 bits convert in parallel. Both give identical
+    /// results.
+    ///
+    /// `output` needs to have the same length as `values`, which is checked in debug builds.
+    ///
+    /// Returns the position of the first value that could not be represented.
+    fn from_f32_normalized_slice(
+        values: &[f32],
+        output: &mut [T],
+    ) -> core::result::Result<(), usize>
+    where
+        Self: Sized,
+    {
+        from_f32_slice::<Self, T>(values, output)
+    }
+}
+
+impl NormalizedFloat<f32> for f32 {
+    fn to_f32_normalized(&self) -> Option<f32> {
+        Some(*self)
+    }
+
+    fn to_f64_normalized(&self) -> Option<f64> {
+        self.to_f64()
+    }
+    fn from_f32_normalized(value: f32) -> Option<f32> {
+        Some(value)
+    }
+
+    fn from_f64_normalized(value: f64) -> Option<f32> {
+        value.to_f32()
+    }
+}
+
+impl NormalizedFloat<f64> for f64 {
+    fn to_f32_normalized(&self) -> Option<f32> {
+        self.to_f32()
+    }
+
+    fn to_f64_normalized(&self) -> Option<f64> {
+        Some(*self)
+    }
+    fn from_f32_normalized(value: f32) -> Option<f64> {
+        value.to_f64()
+    }
+
+    fn from_f64_normalized(value: f64) -> Option<f64> {
+        Some(value)
+    }
+}
+
+/// Reject NaN and infinite values with the matching [`NormalizeError`].
+fn check_finite(value: f64) -> core::result::Result<f64, NormalizeError> {
+    if value.is_nan() {
+        Err(NormalizeError::NotANumber)
+    } else if value.is_infinite() {
+        Err(NormalizeError::Overflow)
+    } else {
+        Ok(value)
+    }
+}
+
+/// Scalar kernel of [`NormalizedFloat::to_f32_normalized_slice`].
+fn to_f32_slice<S, T>(values: &[S], output: &mut [f32]) -> core::result::Result<(), usize>
+where
+    S: NormalizedFloat<T>,
+    T: AsPrimitive<f32> + AsPrimitive<f64>,
+{
+    for (index, (value, output)) in values.iter().zip(output).enumerate() {
+        *output = value.to_f32_normalized().ok_or(index)?;
+    }
+    Ok(())
+}
+
+/// Scalar kernel of [`NormalizedFloat::from_f32_normalized_slice`].
+fn from_f32_slice<S, T>(values: &[f32], output: &mut [T]) -> core::result::Result<(), usize>
+where
+    S: NormalizedFloat<T>,
+    T: AsPrimitive<f32> + AsPrimitive<f64>,
+{
+    for (index, (value, output)) in values.iter().zip(output).enumerate() {
+        *output = S::from_f32_normalized(*value).ok_or(index)?;
+    }
+    Ok(())
+}
+
+/// Implement [`NormalizedFloat`] for a primitive integer, scaling by its maximum value.
+///
+/// The slice conversions use the default scalar kernels of the trait. The arms starting
+/// with `@` are used by the expansion and are not meant to be called directly.
+#[macro_export]
+macro_rules! impl_as_float {
+    ($type:ty) => {
+        impl $crate::prelude::NormalizedFloat<$type> for $type {
+            $crate::impl_as_float!(@methods $type);
+        }
+    };
+    (@methods $type:ty) => {
+        fn to_f32_normalized(&self) -> Option<f32> {
+            $crate::__private::ToPrimitive::to_f32(self)
+                .map(|converted| converted / <$type>::MAX as f32)
+        }
+
+        fn to_f64_normalized(&self) -> Option<f64> {
+            $crate::__private::ToPrimitive::to_f64(self)
+                .map(|converted| converted / <$type>::MAX as f64)
+        }
+
+        fn from_f32_normalized(value: f32) -> Option<$type> {
+            Self::from_f32_normalized_with(value, $crate::Rounding::Nearest, $crate::Clamping::Clamp)
+        }
+
+        fn from_f64_normalized(value: f64) -> Option<$type> {
+            Self::from_f64_normalized_with(value, $crate::Rounding::Nearest, $crate::Clamping::Clamp)
+        }
+
+        fn from_f32_normalized_with(
+            value: f32,
+            rounding: $crate::Rounding,
+            clamping: $crate::Clamping,
+        ) -> Option<$type> {
+            let scaled = value * <$type>::MAX as f32;
+            $crate::__private::round_to(scaled.into(), rounding, clamping)
+        }
+
+        fn from_f64_normalized_with(
+            value: f64,
+            rounding: $crate::Rounding,
+            clamping: $crate::Clamping,
+        ) -> Option<$type> {
+            let scaled = value * <$type>::MAX as f64;
+            $crate::__private::round_to(scaled, rounding, clamping)
+        }
+
+        $crate::impl_as_float!(@signed $type);
+    };
+    (@signed $type:ty) => {
+        fn to_f64_normalized_signed(
+            &self,
+            signed: $crate::SignedNormalization,
+        ) -> Option<f64> {
+            let (min, max) = (<$type>::MIN as f64, <$type>::MAX as f64);
+            $crate::__private::ToPrimitive::to_f64(self)
+                .map(|converted| $crate::__private::normalize_signed(converted, min, max, signed))
+        }
+
+        fn from_f64_normalized_signed(
+            value: f64,
+            signed: $crate::SignedNormalization,
+        ) -> Option<$type> {
+            match signed {
+                $crate::SignedNormalization::Legacy => Self::from_f64_normalized(value),
+                _ => {
+                    let (min, max) = (<$type>::MIN as f64, <$type>::MAX as f64);
+                    $crate::__private::round_to(
+                        $crate::__private::scale_signed(value, min, max, signed),
+                        $crate::Rounding::Nearest,
+                        $crate::Clamping::Clamp,
+                    )
+                }
+            }
+        }
+    };
+}
+
+/// Same as [`impl_as_float`], but with the slice kernels of this crate, which convert in
+/// parallel with the `rayon` feature.
+///
+/// The optional paths are the vectorized slice kernels used with the `simd` feature.
+/// Without it, the types with a path normalize slices with a lookup table when `std` is
+/// enabled.
+macro_rules! impl_normalized_int {
+    ($type:ty) => {
+        impl NormalizedFloat<$type> for $type {
+            impl_as_float!(@methods $type);
+            impl_normalized_int!(
+                @slices $type,
+                to_f32_slice::<$type, $type>,
+                from_f32_slice::<$type, $type>
+            );
+        }
+    };
+    ($type:ty, $to:path, $from:path) => {
+        impl NormalizedFloat<$type> for $type {
+            impl_as_float!(@methods $type);
+            #[cfg(feature = "simd")]
+            impl_normalized_int!(
+                @slices $type,
+                |values: &[$type], output: &mut [f32]| {
+                    $to(values, output);
+                    Ok(())
+                },
+                |values: &[f32], output: &mut [$type]| {
+                    $from(values, output);
+                    Ok(())
+                }
+            );
+            #[cfg(all(not(feature = "simd"), feature = "std"))]
+            impl_normalized_int!(
+                @slices $type,
+                crate::lut::normalize_slice::<$type>,
+                from_f32_slice::<$type, $type>
+            );
+            #[cfg(not(any(feature = "simd", feature = "std")))]
+            impl_normalized_int!(
+                @slices $type,
+                to_f32_slice::<$type, $type>,
+                from_f32_slice::<$type, $type>
+            );
+        }
+    };
+    (@slices $type:ty, $to:expr, $from:expr) => {
+        fn to_f32_normalized_slice(
+            values: &[$type],
+            output: &mut [f32],
+        ) -> core::result::Result<(), usize> {
+            crate::parallel::convert_chunks(values, output, $to)
+        }
+
+        fn from_f32_normalized_slice(
+            values: &[f32],
+            output: &mut [$type],
+        ) -> core::result::Result<(), usize> {
+            crate::parallel::convert_chunks(values, output, $from)
+        }
+    };
+}
+
+impl_normalized_int!(i32);
+impl_normalized_int!(u32);
+impl_normalized_int!(i16);
+impl_normalized_int!(u16, crate::simd::u16_to_f32, crate::simd::f32_to_u16);
+impl_normalized_int!(i8);
+impl_normalized_int!(u8, crate::simd::u8_to_f32, crate::simd::f32_to_u8);
+
+/// Same as [`impl_as_float`], but for integers wider than the mantissa of an f64.
+///
+/// The normalization is always done in f64 and only then narrowed to f32, as the f32
+/// division would round the integer to 24 bits before scaling. Values are still only
+/// exact up to 53 bits of precision, so the round trip of large values is lossy.
+macro_rules! impl_as_float_wide {
+    ($type:ty) => {
+        impl NormalizedFloat<$type> for $type {
+            fn to_f32_normalized(&self) -> Option<f32> {
+                self.to_f64_normalized()?.to_f32()
+            }
+
+            fn to_f64_normalized(&self) -> Option<f64> {
+                self.to_f64()
+                    .map(|converted| converted / <$type>::MAX as f64)
+            }
+
+            fn from_f32_normalized(value: f32) -> Option<$type> {
+                Self::from_f64_normalized(value.into())
+            }
+
+            fn from_f64_normalized(value: f64) -> Option<$type> {
+                Self::from_f64_normalized_with(value, Rounding::Nearest, Clamping::Clamp)
+            }
+
+            fn from_f32_normalized_with(
+                value: f32,
+                rounding: Rounding,
+                clamping: Clamping,
+            ) -> Option<$type> {
+                Self::from_f64_normalized_with(value.into(), rounding, clamping)
+            }
+
+            fn from_f64_normalized_with(
+                value: f64,
+                rounding: Rounding,
+                clamping: Clamping,
+            ) -> Option<$type> {
+                denormalize(value, rounding, clamping)
+            }
+
+            impl_as_float!(@signed $type);
+        }
+    };
+}
+
+impl_as_float_wide!(i64);
+impl_as_float_wide!(u64);
+impl_as_float_wide!(usize);
+
+/// Half precision floats are already normalized, so these convert without scaling.
+///
+/// Converting to the half type returns None if a finite value overflows.
+#[cfg(feature = "half")]
+macro_rules! impl_half_float {
+    ($type:ty) => {
+        impl NormalizedFloat<$type> for $type {
+            fn to_f32_normalized(&self) -> Option<f32> {
+                Some(<$type>::to_f32(*self))
+            }
+
+            fn to_f64_normalized(&self) -> Option<f64> {
+                Some(<$type>::to_f64(*self))
+            }
+
+            fn from_f32_normalized(value: f32) -> Option<$type> {
+                let converted = <$type>::from_f32(value);
+                (converted.is_finite() || !value.is_finite()).then_some(converted)
+            }
+
+            fn from_f64_normalized(value: f64) -> Option<$type> {
+                let converted = <$type>::from_f64(value);
+                (converted.is_finite() || !value.is_finite()).then_some(converted)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "half")]
+impl_half_float!(half::f16);
+#[cfg(feature = "half")]
+impl_half_float!(half::bf16);
+
+/// Normalization of whole `(y, x, z)` arrays.
+///
+/// This applies [`NormalizedFloat`] to every sample in a single pass, instead of
+/// converting scalar by scalar.
+pub trait NormalizedArray<T>
+where
+    T: NormalizedFloat<T> + AsPrimitive<f32> + AsPrimitive<f64>,
+{
+    /// Convert the samples to normalized 32 bit floats.
+    ///
+    /// For example in u8, a value of 255 would be represented as 1.0.
+    ///
+    /// Returns [`Error::NormalizationFailed`] with the `[y, x, z]` index of the first
+    /// sample that could not be represented.
+    fn to_f32_normalized(&self) -> Result<Array3<f32>>;
+
+    /// Convert normalized 32 bit floats to the samples of `T`.
+    ///
+    /// For example in u8, a value of 1.0 would be represented as 255.
+    ///
+    /// Returns [`Error::NormalizationFailed`] with the `[y, x, z]` index of the first
+    /// value that could not be represented.
+    fn from_f32_normalized(array: ArrayView3<'_, f32>) -> Result<Array3<T>>
+    where
+        T: Zero;
+
+    /// Convert normalized 32 bit floats to the samples of `T` with the rounding and
+    /// clamping of integer types.
+    ///
+    /// Rounding to the nearest value and clamping is the same as
+    /// [`Self::from_f32_normalized`].
+    ///
+    /// Returns [`Error::NormalizationFailed`] with the `[y, x, z]` index of the first
+    /// value that could not be represented or was rejected by [`Clamping::Error`].
+    fn from_f32_normalized_with(
+        array: ArrayView3<'_, f32>,
+        rounding: Rounding,
+        clamping: Clamping,
+    ) -> Result<Array3<T>>
+    where
+        T: Zero;
+}
+
+impl<S, T> NormalizedArray<T> for ArrayBase<S, Ix3>
+where
+    S: Data<Elem = T>,
+    T: NormalizedFloat<T> + AsPrimitive<f32> + AsPrimitive<f64>,
+{
+    fn to_f32_normalized(&self) -> Result<Array3<f32>> {
+        map_normalized(
+            self.view(),
+            T::to_f32_normalized_slice,
+            NormalizedFloat::to_f32_normalized,
+        )
+    }
+
+    fn from_f32_normalized(array: ArrayView3<'_, f32>) -> Result<Array3<T>>
+    where
+        T: Zero,
+    {
+        map_normalized(array, T::from_f32_normalized_slice, |value| {
+            T::from_f32_normalized(*value)
+        })
+    }
+
+    fn from_f32_normalized_with(
+        array: ArrayView3<'_, f32>,
+        rounding: Rounding,
+        clamping: Clamping,
+    ) -> Result<Array3<T>>
+    where
+        T: Zero,
+    {
+        if (rounding, clamping) == (Rounding::Nearest, Clamping::Clamp) {
+            return Self::from_f32_normalized(array);
+        }
+        let convert = |value: &f32| T::from_f32_normalized_with(*value, rounding, clamping);
+        map_normalized(
+            array,
+            |values, output| {
+                for (index, (value, output)) in values.iter().zip(output).enumerate() {
+                    *output = convert(value).ok_or(index)?;
+                }
+                Ok(())
+            },
+            convert,
+        )
+    }
+}
+
+/// Map every sample, keeping the first index in logical order where the conversion failed.
+///
+/// Arrays in standard layout are converted as a whole slice, so vectorized kernels apply.
+fn map_normalized<A, B>(
+    array: ArrayView3<'_, A>,
+    convert_slice: impl FnOnce(&[A], &mut [B]) -> core::result::Result<(), usize>,
+    mut convert: impl FnMut(&A) -> Option<B>,
+) -> Result<Array3<B>>
+where
+    B: Zero + Clone,
+{
+    span!(
+        DEBUG,
+        "normalize",
+        shape = ?array.dim(),
+        source = core::any::type_name::<A>(),
+        dtype = core::any::type_name::<B>(),
+    );
+    if let Some(values) = array.as_slice() {
+        let (_, width, channels) = array.dim();
+        let mut result = Array3::zeros(array.dim());
+        let output = result
+            .as_slice_mut()
+            .expect("new array is in standard layout");
+        return match convert_slice(values, output) {
+            Ok(()) => Ok(result),
+            Err(position) => Err(Error::NormalizationFailed([
+                position / (width * channels),
+                position / channels % width,
+                position % channels,
+            ])),
+        };
+    }
+
+    let mut failed: Option<(usize, usize, usize)> = None;
+    let result = Zip::indexed(array).map_collect(|index, value| {
+        convert(value).unwrap_or_else(|| {
+            failed = Some(failed.map_or(index, |failed| failed.min(index)));
+            B::zero()
+        })
+    });
+    match failed {
+        Some((y, x, z)) => Err(Error::NormalizationFailed([y, x, z])),
+        None => Ok(result),
+    }
+}
+
+#[cfg(feature = "image")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::normalize::ConvertSample;
+    use image::{GenericImage, Rgb, Rgb32FImage, Rgba, Rgba32FImage};
+    use ndarray::Array2;
+    use rstest::*;
+
+    #[test]
+    fn test_as_ndarray_rgba() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let test_image = Rgba32FImage::from_vec(256, 128, data).unwrap();
+
+        let array = test_image.as_ndarray();
+
+        for ((y, x, channel), value) in array.indexed_iter() {
+            assert_eq!(test_image.get_pixel(x as u32, y as u32)[channel], *value);
+        }
+    }
+
+    #[test]
+    fn test_as_ndarray_luma() {
+        let (width, height, channels) = (256, 128, 1);
+        let data = create_test_data(width, height, channels);
+        let test_image: ImageBuffer<Luma<f32>, Vec<f32>> =
+            ImageBuffer::from_vec(256, 128, data).unwrap();
+
+        let array = test_image.as_ndarray();
+
+        for ((y, x, channel), value) in array.indexed_iter() {
+            assert_eq!(test_image.get_pixel(x as u32, y as u32)[channel], *value);
+        }
+    }
+
+    #[test]
+    fn test_as_ndarray_mut() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let mut test_image = Rgba32FImage::from_vec(256, 128, data).unwrap();
+        let compare = test_image.clone();
+
+        let mut array = test_image.as_ndarray_mut();
+        array += 1.0;
+
+        for (x, y, pixel) in test_image.enumerate_pixels() {
+            let compare_pixel = compare.get_pixel(x, y);
+            for (channel, value) in pixel.channels().iter().enumerate() {
+                assert_eq!(*value, compare_pixel[channel] + 1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_to_ndarray() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let test_image = Rgba32FImage::from_vec(256, 128, data).unwrap();
+
+        let mut array = test_image.clone().to_ndarray();
+
+        array += 1.0;
+        for ((y, x, channel), value) in array.indexed_iter() {
+            assert_eq!(
+                test_image.get_pixel(x as u32, y as u32)[channel] + 1.0,
+                *value
+            );
+        }
+    }
+
+    #[test]
+    fn test_views_of_oversized_buffer() {
+        let data: Vec<u8> = (0..20).collect();
+        let mut image = ImageBuffer::<Rgb<u8>, _>::from_raw(3, 2, data).unwrap();
+
+        assert_eq!(image.as_ndarray().dim(), (2, 3, 3));
+        assert_eq!(image.as_ndarray()[[1, 2, 2]], 17);
+
+        image.as_ndarray_mut().fill(1);
+        assert_eq!(image.as_raw()[17..], [1, 18, 19]);
+
+        let array = image.to_ndarray();
+        assert_eq!(array, Array3::from_elem((2, 3, 3), 1));
+    }
+
+    #[test]
+    fn test_views_of_empty_buffer() {
+        let mut image = ImageBuffer::<Rgb<u16>, Vec<u16>>::new(0, 4);
+
+        assert_eq!(image.as_ndarray().dim(), (4, 0, 3));
+        assert_eq!(image.as_ndarray_mut().dim(), (4, 0, 3));
+        assert_eq!(image.to_ndarray().dim(), (4, 0, 3));
+    }
+
+    #[test]
+    fn test_from_ndarray() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let test_image = Array3::from_shape_vec((height, width, channels), data).unwrap();
+        let compare_data = test_image.clone();
+
+        let result = Rgba32FImage::from_ndarray(test_image).unwrap();
+
+        for (x, y, pixel) in result.enumerate_pixels() {
+            for (channel, value) in pixel.channels().iter().enumerate() {
+                assert_eq!(*value, compare_data[[y as usize, x as usize, channel]]);
+            }
+        }
+    }
+
+    #[rstest]
+    #[case::standard(s![.., .., ..])]
+    #[case::offset(s![1.., .., ..])]
+    #[case::cropped(s![1..3, 1..4, ..])]
+    #[case::inverted(s![..;-1, .., ..])]
+    #[case::strided(s![.., ..;2, ..])]
+    #[case::empty(s![2..2, .., ..])]
+    fn test_from_ndarray_layout(
+        #[case] slice: ndarray::SliceInfo<[ndarray::SliceInfoElem; 3], Ix3, Ix3>,
+    ) {
+        let array = Array3::from_shape_fn((4, 6, 3), |(y, x, z)| (y * 18 + x * 3 + z) as u8);
+        let expected = array.slice(slice).to_owned();
+
+        let result =
+            ImageBuffer::<Rgb<u8>, Vec<u8>>::from_ndarray(array.slice_move(slice)).unwrap();
+
+        assert_eq!(result.as_ndarray(), expected);
+        assert_eq!(result.as_raw().len(), expected.len());
+    }
+
+    #[test]
+    fn test_from_ndarray_too_large() {
+        let array = Array3::<u8>::zeros((0, 1 << 32, 3));
+
+        let result = ImageBuffer::<Rgb<u8>, Vec<u8>>::from_ndarray(array);
+
+        assert_eq!(result, Err(Error::TooLarge));
+    }
+
+    #[test]
+    fn test_from_ndarray_transposed() {
+        let array = Array3::from_shape_fn((5, 3, 1), |(y, x, _)| (y * 3 + x) as u16);
+        let transposed = array.view().permuted_axes([1, 0, 2]).to_owned();
+        let owned = array.permuted_axes([1, 0, 2]);
+
+        let result = ImageBuffer::<Luma<u16>, Vec<u16>>::from_ndarray(owned).unwrap();
+
+        assert_eq!(result.dimensions(), (5, 3));
+        assert_eq!(result.as_ndarray(), transposed);
+    }
+
+    #[test]
+    fn test_from_ndarray_2d() {
+        let (width, height, channels) = (256, 128, 1);
+        let data = create_test_data(width, height, channels);
+        let test_image = Array2::from_shape_vec((height, width), data).unwrap();
+        println!("{}", test_image.shape().len());
+        let compare_data = test_image.clone();
+
+        let result = ImageBuffer::<Luma<f32>, Vec<f32>>::from_ndarray(test_image).unwrap();
+
+        for (x, y, pixel) in result.enumerate_pixels() {
+            for value in pixel.channels().iter() {
+                assert_eq!(*value, compare_data[[y as usize, x as usize]]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_as_pixel_ndarray() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let test_image = Rgba32FImage::from_vec(256, 128, data).unwrap();
+
+        let array = test_image.as_pixel_ndarray::<4>().unwrap();
+
+        assert_eq!(array.shape(), &[height, width]);
+        for ((y, x), pixel) in array.indexed_iter() {
+            assert_eq!(test_image.get_pixel(x as u32, y as u32).0, *pixel);
+        }
+    }
+
+    #[test]
+    fn test_as_pixel_ndarray_mut() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let mut test_image = Rgba32FImage::from_vec(256, 128, data).unwrap();
+        let compare = test_image.clone();
+
+        let mut array = test_image.as_pixel_ndarray_mut::<4>().unwrap();
+        array.map_inplace(|pixel| pixel.reverse());
+
+        for (x, y, pixel) in test_image.enumerate_pixels() {
+            let mut expected = compare.get_pixel(x, y).0;
+            expected.reverse();
+            assert_eq!(pixel.0, expected);
+        }
+    }
+
+    #[test]
+    fn test_as_pixel_ndarray_with_invalid_channels() {
+        let test_image = Rgba32FImage::new(16, 8);
+
+        let result = test_image.as_pixel_ndarray::<3>().err().unwrap();
+
+        assert_eq!(
+            result,
+            Error::ChannelMismatch {
+                expected: 4,
+                got: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_as_pixel_ndarray_const() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let mut test_image = Rgba32FImage::from_vec(256, 128, data).unwrap();
+        let compare = test_image.as_pixel_ndarray::<4>().unwrap().to_owned();
+
+        assert_eq!(test_image.as_pixel_ndarray_const(), compare);
+
+        test_image.as_pixel_ndarray_const_mut()[[2, 3]] = [1.0; 4];
+        assert_eq!(test_image.get_pixel(3, 2).0, [1.0; 4]);
+    }
+
+    #[test]
+    fn test_from_pixel_ndarray() {
+        let array = Array2::from_shape_fn((8, 16), |(y, x)| [x as f32, y as f32, 0.0]);
+        let input = array.clone();
+        let pointer = input.as_ptr();
+
+        let image = Rgb32FImage::from_pixel_ndarray(input).unwrap();
+
+        assert_eq!(image.get_pixel(3, 2).0, [3.0, 2.0, 0.0]);
+        assert_eq!(image.as_ptr(), pointer.cast());
+        assert_eq!(image.as_pixel_ndarray_const(), array);
+
+        let image = Rgb32FImage::from_pixel_ndarray(array.reversed_axes()).unwrap();
+        assert_eq!(image.dimensions(), (8, 16));
+        assert_eq!(image.get_pixel(2, 3).0, [3.0, 2.0, 0.0]);
+    }
+
+    #[test]
+    fn test_sub_image_as_ndarray() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let test_image = Rgba32FImage::from_vec(256, 128, data).unwrap();
+        let sub_image = test_image.view(10, 20, 30, 40);
+
+        let array = sub_image.as_ndarray();
+
+        assert_eq!(array.shape(), &[40, 30, 4]);
+        for ((y, x, channel), value) in array.indexed_iter() {
+            assert_eq!(
+                test_image.get_pixel(x as u32 + 10, y as u32 + 20)[channel],
+                *value
+            );
+        }
+        assert_eq!(sub_image.to_ndarray(), array);
+    }
+
+    #[test]
+    fn test_sub_image_as_ndarray_mut() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let mut test_image = Rgba32FImage::from_vec(256, 128, data).unwrap();
+        let compare = test_image.clone();
+
+        let mut sub_image = test_image.sub_image(10, 20, 30, 40);
+        let mut array = sub_image.as_ndarray_mut();
+        array += 1.0;
+
+        for (x, y, pixel) in test_image.enumerate_pixels() {
+            let inside = (10..40).contains(&x) && (20..60).contains(&y);
+            let offset = if inside { 1.0 } else { 0.0 };
+            for (channel, value) in pixel.channels().iter().enumerate() {
+                assert_eq!(*value, compare.get_pixel(x, y)[channel] + offset);
+            }
+        }
+    }
+
+    #[test]
+    fn test_as_ndarray_region() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let test_image = Rgba32FImage::from_vec(256, 128, data).unwrap();
+
+        let array = test_image.as_ndarray_region(10, 20, 30, 40).unwrap();
+
+        assert_eq!(array.shape(), &[40, 30, 4]);
+        for ((y, x, channel), value) in array.indexed_iter() {
+            assert_eq!(
+                test_image.get_pixel(x as u32 + 10, y as u32 + 20)[channel],
+                *value
+            );
+        }
+    }
+
+    #[test]
+    fn test_as_ndarray_region_mut() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let mut test_image = Rgba32FImage::from_vec(256, 128, data).unwrap();
+        let compare = test_image.clone();
+
+        let mut array = test_image.as_ndarray_region_mut(10, 20, 30, 40).unwrap();
+        array += 1.0;
+
+        for (x, y, pixel) in test_image.enumerate_pixels() {
+            let inside = (10..40).contains(&x) && (20..60).contains(&y);
+            let offset = if inside { 1.0 } else { 0.0 };
+            for (channel, value) in pixel.channels().iter().enumerate() {
+                assert_eq!(*value, compare.get_pixel(x, y)[channel] + offset);
+            }
+        }
+    }
+
+    #[rstest]
+    #[case(0, 0, 257, 128)]
+    #[case(0, 0, 256, 129)]
+    #[case(200, 0, 57, 1)]
+    #[case(0, 100, 1, 29)]
+    #[case(u32::MAX, 0, 1, 1)]
+    fn test_as_ndarray_region_out_of_bounds(
+        #[case] x: u32,
+        #[case] y: u32,
+        #[case] width: u32,
+        #[case] height: u32,
+    ) {
+        let test_image = Rgba32FImage::new(256, 128);
+
+        let result = test_image
+            .as_ndarray_region(x, y, width, height)
+            .err()
+            .unwrap();
+
+        assert_eq!(result, Error::OutOfBounds);
+    }
+
+    #[test]
+    fn test_rows_ndarray() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let test_image = Rgba32FImage::from_vec(256, 128, data).unwrap();
+
+        let rows: Vec<_> = test_image.rows_ndarray().collect();
+
+        assert_eq!(rows.len(), height);
+        for (y, row) in rows.into_iter().enumerate() {
+            assert_eq!(row.shape(), &[width, channels]);
+            for ((x, channel), value) in row.indexed_iter() {
+                assert_eq!(test_image.get_pixel(x as u32, y as u32)[channel], *value);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rows_ndarray_mut() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let mut test_image = Rgba32FImage::from_vec(256, 128, data).unwrap();
+        let compare = test_image.clone();
+
+        for (y, mut row) in test_image.rows_ndarray_mut().enumerate() {
+            row += y as f32;
+        }
+
+        for (x, y, pixel) in test_image.enumerate_pixels() {
+            for (channel, value) in pixel.channels().iter().enumerate() {
+                assert_eq!(*value, compare.get_pixel(x, y)[channel] + y as f32);
+            }
+        }
+    }
+
+    #[test]
+    fn test_as_cow_ndarray_borrows_image() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let test_image = Rgba32FImage::from_vec(256, 128, data).unwrap();
+
+        let array = test_image.as_cow_ndarray();
+
+        assert!(array.is_view());
+        assert_eq!(array, test_image.as_ndarray());
+    }
+
+    #[test]
+    fn test_as_cow_ndarray_sub_image() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let test_image = Rgba32FImage::from_vec(256, 128, data).unwrap();
+
+        let full_rows = test_image.view(0, 20, 256, 40);
+        let array = full_rows.as_cow_ndarray();
+        assert!(array.is_view());
+        assert_eq!(array, SubImageArray::as_ndarray(&full_rows));
+
+        let region = test_image.view(10, 20, 30, 40);
+        let array = region.as_cow_ndarray();
+        assert!(array.is_owned());
+        assert!(array.is_standard_layout());
+        assert_eq!(array, SubImageArray::as_ndarray(&region));
+    }
+
+    #[test]
+    fn test_as_cow_ndarray_array() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let array = Array3::from_shape_vec((height, width, channels), data).unwrap();
+
+        let standard = array.as_cow_ndarray();
+        assert!(standard.is_view());
+
+        let transposed = array.clone().permuted_axes([1, 0, 2]);
+        let repacked = transposed.as_cow_ndarray();
+        assert!(repacked.is_owned());
+        assert!(repacked.is_standard_layout());
+        assert_eq!(repacked, transposed);
+
+        let luma =
+            Array2::from_shape_vec((height, width), create_test_data(width, height, 1)).unwrap();
+        let expanded = luma.as_cow_ndarray();
+        assert!(expanded.is_view());
+        assert_eq!(expanded.shape(), &[height, width, 1]);
+    }
+
+    #[test]
+    fn test_into_image() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let array = Array3::from_shape_vec((height, width, channels), data).unwrap();
+        let compare = array.clone();
+
+        let result = array.into_image::<Rgba<f32>>().unwrap();
+
+        assert_eq!(result.as_ndarray(), compare);
+    }
+
+    #[test]
+    fn test_into_image_non_standard_layout() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let array = Array3::from_shape_vec((width, height, channels), data)
+            .unwrap()
+            .permuted_axes([1, 0, 2]);
+        let compare = array.clone();
+
+        let result = array.into_image::<Rgba<f32>>().unwrap();
+
+        assert_eq!(result.as_ndarray(), compare);
+    }
+
+    #[test]
+    fn test_to_image() {
+        let (width, height, channels) = (256, 128, 1);
+        let data = create_test_data(width, height, channels);
+        let array = Array2::from_shape_vec((height, width), data).unwrap();
+
+        let result = array.view().to_image::<Luma<f32>>().unwrap();
+
+        for (x, y, pixel) in result.enumerate_pixels() {
+            assert_eq!(pixel[0], array[[y as usize, x as usize]]);
         }
     }
-    fn from_ndarray<D: Dimension>(mut array: Array<C, D>) -> Result<ImageBuffer<P, Vec<C>>> {
-        let shape = array.shape();
-        if shape.len() < 2 {
-            return Err(Error::Dimensions);
-        };
 
-        let (width, height) = (shape[1], shape[0]);
-        let channels = match shape.len() {
-            2 => 1,
-            3 => shape[2],
-            _ => return Err(Error::Dimensions),
-        };
+    #[test]
+    fn test_as_image_view() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let array = Array3::from_shape_vec((height, width, channels), data).unwrap();
+
+        let result = array.as_image_view::<Rgba<f32>>().unwrap();
 
-        if channels != P::CHANNEL_COUNT.into() {
-            return Err(Error::ChannelMismatch);
+        assert_eq!(result.dimensions(), (256, 128));
+        assert_eq!(result.as_raw().as_ptr(), array.as_ptr());
+        for (x, y, pixel) in result.enumerate_pixels() {
+            for (channel, value) in pixel.channels().iter().enumerate() {
+                assert_eq!(*value, array[[y as usize, x as usize, channel]]);
+            }
         }
+    }
 
-        let data = array.as_mut_ptr();
+    #[test]
+    fn test_as_image_view_errors() {
+        let array = Array3::<f32>::zeros((128, 256, 4));
 
-        std::mem::forget(array);
-        let size = height * width * channels;
+        let result = array.as_image_view::<Rgb<f32>>().err().unwrap();
+        assert_eq!(
+            result,
+            Error::ChannelMismatch {
+                expected: 3,
+                got: 4
+            }
+        );
 
-        let vec_data = unsafe { Vec::from_raw_parts(data, size, size) };
-        Self::from_raw(width as u32, height as u32, vec_data).ok_or(Error::ImageConstructFailed)
+        let transposed = array.view().permuted_axes([1, 0, 2]);
+        let result = transposed.as_image_view::<Rgba<f32>>().err().unwrap();
+        assert_eq!(result, Error::NotContiguous);
     }
 
-    fn as_ndarray_mut<'a>(&'a mut self) -> ArrayViewMut3<'a, C> {
-        let (width, height) = self.dimensions();
+    #[test]
+    fn test_from_ndarray_adapted_adds_alpha() {
+        let (width, height, channels) = (256, 128, 3);
+        let data = create_test_data(width, height, channels);
+        let array = Array3::from_shape_vec((height, width, channels), data).unwrap();
 
-        unsafe {
-            ArrayViewMut::from_shape_ptr(
-                (height as usize, width as usize, P::CHANNEL_COUNT as usize),
-                self.as_mut_ptr(),
-            )
+        let result =
+            Rgba32FImage::from_ndarray_adapted(array.clone(), AlphaMode::Straight).unwrap();
+
+        for (x, y, pixel) in result.enumerate_pixels() {
+            let (x, y) = (x as usize, y as usize);
+            assert_eq!(
+                pixel.0,
+                [array[[y, x, 0]], array[[y, x, 1]], array[[y, x, 2]], 1.0]
+            );
         }
     }
-}
-
-/// Trait for converting the provided value to a normalized float.
-///
-/// This is used for image processing where a lot of operations rely on floating values.
-pub trait NormalizedFloat<T>
-where
-    T: AsPrimitive<f32> + AsPrimitive<f64>,
-{
-    /// Convert the value to a 32 bit float.
-    ///
-    /// The value will be in a normalized range according to color depths.
-    ///
-    /// For example in u8, a value of 255 would be represented as 1.0.
-    ///
-    /// Returns None if it overflows and could not be represented.
-    fn to_f32_normalized(&self) -> Option<f32>;
-    /// Convert the value to a 64 bit float
-    ///
-    /// The value will be in a normalized range according to color depths.
-    ///
-    /// For example in u8, a value of 255 would be represented as 1.0.
-    ///
-    /// Returns None if it overflows and could not be represented.
-    fn to_f64_normalized(&self) -> Option<f64>;
 
-    /// Converts the f32 value to the provided type
-    ///
-    /// Returns None if it overflows and could not be represented.
-    fn from_f32_normalized(value: f32) -> Option<T>;
+    #[test]
+    fn test_from_ndarray_adapted_replicates_gray() {
+        let (width, height, channels) = (256, 128, 1);
+        let data = create_test_data(width, height, channels);
+        let array = Array2::from_shape_vec((height, width), data).unwrap();
 
-    /// Converts the f64 value to the provided type
-    ///
-    /// Returns None if it overflows and could not be represented.
-    fn from_f64_normalized(value: f64) -> Option<T>;
-}
+        let result = Rgb32FImage::from_ndarray_adapted(array.clone(), AlphaMode::Straight).unwrap();
 
-impl NormalizedFloat<f32> for f32 {
-    fn to_f32_normalized(&self) -> Option<f32> {
-        Some(*self)
+        for (x, y, pixel) in result.enumerate_pixels() {
+            assert_eq!(pixel.0, [array[[y as usize, x as usize]]; 3]);
+        }
     }
 
-    fn to_f64_normalized(&self) -> Option<f64> {
-        self.to_f64()
-    }
-    fn from_f32_normalized(value: f32) -> Option<f32> {
-        Some(value)
-    }
+    #[test]
+    fn test_from_ndarray_adapted_matching_channels() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let array = Array3::from_shape_vec((height, width, channels), data).unwrap();
+        let pointer = array.as_ptr();
 
-    fn from_f64_normalized(value: f64) -> Option<f32> {
-        value.to_f32()
-    }
-}
+        let result = Rgba32FImage::from_ndarray_adapted(array, AlphaMode::Straight).unwrap();
 
-impl NormalizedFloat<f64> for f64 {
-    fn to_f32_normalized(&self) -> Option<f32> {
-        self.to_f32()
+        assert_eq!(result.as_ptr(), pointer);
     }
 
-    fn to_f64_normalized(&self) -> Option<f64> {
-        Some(*self)
-    }
-    fn from_f32_normalized(value: f32) -> Option<f64> {
-        value.to_f64()
-    }
+    #[test]
+    fn test_copy_to_ndarray() {
+        let test_image = ImageBuffer::<Rgba<u8>, Vec<u8>>::from_fn(16, 8, |x, y| {
+            Rgba([x as u8, y as u8, 0, u8::MAX])
+        });
+        let mut buffer = [0u8; 16 * 8 * 4];
 
-    fn from_f64_normalized(value: f64) -> Option<f64> {
-        Some(value)
+        let target = ArrayViewMut3::from_shape((8, 16, 4), &mut buffer).unwrap();
+        test_image.copy_to_ndarray(target).unwrap();
+
+        assert_eq!(buffer.as_slice(), test_image.as_raw().as_slice());
     }
-}
 
-#[macro_export]
-macro_rules! impl_as_float {
-    ($type:ty) => {
-        impl NormalizedFloat<$type> for $type {
-            fn to_f32_normalized(&self) -> Option<f32> {
-                self.to_f32()
-                    .map(|converted| converted / <$type>::MAX as f32)
-            }
+    #[test]
+    fn test_copy_to_ndarray_shape_mismatch() {
+        let test_image = Rgba32FImage::new(16, 8);
+        let mut array = Array3::<f32>::zeros((16, 8, 4));
 
-            fn to_f64_normalized(&self) -> Option<f64> {
-                self.to_f64()
-                    .map(|converted| converted / <$type>::MAX as f64)
-            }
+        let result = test_image.copy_to_ndarray(array.view_mut()).err().unwrap();
 
-            fn from_f32_normalized(value: f32) -> Option<$type> {
-                Some((value * <$type>::MAX as f32).as_())
+        assert_eq!(
+            result,
+            Error::ShapeMismatch {
+                expected: (8, 16, 4),
+                got: (16, 8, 4)
             }
+        );
+    }
 
-            fn from_f64_normalized(value: f64) -> Option<$type> {
-                Some((value * <$type>::MAX as f64).as_())
-            }
+    #[rstest]
+    #[case(1)]
+    #[case(32)]
+    #[case(64)]
+    #[case(4096)]
+    fn test_to_ndarray_aligned(#[case] alignment: usize) {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let test_image = Rgba32FImage::from_vec(256, 128, data).unwrap();
+
+        let array = test_image.to_ndarray_aligned(alignment).unwrap();
+
+        assert_eq!(array.as_ptr() as usize % alignment, 0);
+        assert!(array.is_standard_layout());
+        assert_eq!(array, test_image.as_ndarray());
+    }
+
+    #[rstest]
+    #[case(0, 0)]
+    #[case(0, 8)]
+    #[case(16, 0)]
+    fn test_to_ndarray_aligned_empty(#[case] width: u32, #[case] height: u32) {
+        let test_image = ImageBuffer::<Rgb<u8>, Vec<u8>>::new(width, height);
+
+        for alignment in [1, 64, 4096] {
+            let array = test_image.to_ndarray_aligned(alignment).unwrap();
+
+            assert_eq!(array.as_ptr() as usize % alignment, 0);
+            assert_eq!(array.dim(), (height as usize, width as usize, 3));
         }
-    };
-}
+    }
 
-impl_as_float!(i32);
-impl_as_float!(u32);
-impl_as_float!(i16);
-impl_as_float!(u16);
-impl_as_float!(i8);
-impl_as_float!(u8);
+    #[test]
+    fn test_to_ndarray_aligned_invalid() {
+        let test_image = Rgba32FImage::new(16, 8);
 
-#[cfg(feature = "image")]
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use image::{Luma, Rgb32FImage, Rgba32FImage};
-    use ndarray::Array2;
-    use rstest::*;
+        let result = test_image.to_ndarray_aligned(48).err().unwrap();
+
+        assert_eq!(result, Error::InvalidAlignment);
+    }
 
     #[test]
-    fn test_as_ndarray_rgba() {
-        let (width, height, channels) = (256, 128, 4);
-        let data = create_test_data(width, height, channels);
-        let test_image = Rgba32FImage::from_vec(256, 128, data).unwrap();
+    fn test_from_ndarray_f32() {
+        let array = Array3::from_shape_fn((128, 256, 3), |(y, x, c)| {
+            (y * 256 + x + c) as f32 / (128 * 256 + 1) as f32
+        });
 
-        let array = test_image.as_ndarray();
+        let result = ImageBuffer::<Rgb<u16>, Vec<u16>>::from_ndarray_f32(
+            array.view(),
+            Rounding::Nearest,
+            Clamping::Clamp,
+        )
+        .unwrap();
 
-        for ((y, x, channel), value) in array.indexed_iter() {
-            assert_eq!(test_image.get_pixel(x as u32, y as u32)[channel], *value);
+        for (x, y, pixel) in result.enumerate_pixels() {
+            for channel in 0..3 {
+                let value = array[[y as usize, x as usize, channel]];
+                assert_eq!(pixel[channel], (value as f64 * 65535.0).round() as u16);
+            }
         }
     }
 
     #[test]
-    fn test_as_ndarray_luma() {
-        let (width, height, channels) = (256, 128, 1);
-        let data = create_test_data(width, height, channels);
-        let test_image: ImageBuffer<Luma<f32>, Vec<f32>> =
-            ImageBuffer::from_vec(256, 128, data).unwrap();
+    #[cfg_attr(
+        feature = "paranoid",
+        should_panic(expected = "outside of the normalized range")
+    )]
+    fn test_from_ndarray_f32_luma_and_clamping() {
+        let array = Array2::from_shape_vec((1, 4), vec![-0.5, 0.5, 1.0, 1.5]).unwrap();
 
-        let array = test_image.as_ndarray();
+        let result = ImageBuffer::<Luma<u8>, Vec<u8>>::from_ndarray_f32(
+            array.view(),
+            Rounding::Truncate,
+            Clamping::Clamp,
+        )
+        .unwrap();
 
-        for ((y, x, channel), value) in array.indexed_iter() {
-            assert_eq!(test_image.get_pixel(x as u32, y as u32)[channel], *value);
-        }
+        assert_eq!(result.into_raw(), vec![0, 127, 255, 255]);
     }
 
     #[test]
-    fn test_as_ndarray_mut() {
-        let (width, height, channels) = (256, 128, 4);
-        let data = create_test_data(width, height, channels);
-        let mut test_image = Rgba32FImage::from_vec(256, 128, data).unwrap();
-        let compare = test_image.clone();
+    #[cfg_attr(
+        feature = "paranoid",
+        should_panic(expected = "outside of the normalized range")
+    )]
+    fn test_from_ndarray_f32_clamping_error() {
+        let mut array = Array3::from_elem((4, 8, 3), 0.5f32);
+        array[[2, 5, 1]] = 1.01;
+        array[[3, 0, 0]] = f32::NAN;
 
-        let mut array = test_image.as_ndarray_mut();
-        array += 1.0;
+        let result = ImageBuffer::<Rgb<u8>, Vec<u8>>::from_ndarray_f32(
+            array.view(),
+            Rounding::Nearest,
+            Clamping::Error,
+        )
+        .err();
 
-        for (x, y, pixel) in test_image.enumerate_pixels() {
-            let compare_pixel = compare.get_pixel(x, y);
-            for (channel, value) in pixel.channels().iter().enumerate() {
-                assert_eq!(*value, compare_pixel[channel] + 1.0);
+        assert_eq!(result, Some(Error::OutOfRange([2, 5, 1])));
+    }
+
+    #[test]
+    fn test_from_ndarray_f32_with_invalid_channels() {
+        let array = Array3::<f32>::zeros((8, 16, 4));
+
+        let result = ImageBuffer::<Rgb<u8>, Vec<u8>>::from_ndarray_f32(
+            array.view(),
+            Rounding::Nearest,
+            Clamping::Clamp,
+        )
+        .err()
+        .unwrap();
+
+        assert_eq!(
+            result,
+            Error::ChannelMismatch {
+                expected: 3,
+                got: 4
             }
-        }
+        );
     }
 
     #[test]
-    fn test_to_ndarray() {
-        let (width, height, channels) = (256, 128, 4);
-        let data = create_test_data(width, height, channels);
-        let test_image = Rgba32FImage::from_vec(256, 128, data).unwrap();
+    fn test_to_ndarray_f32() {
+        let test_image = ImageBuffer::<Rgba<u8>, Vec<u8>>::from_fn(256, 128, |x, y| {
+            Rgba([x as u8, y as u8, 0, u8::MAX])
+        });
 
-        let mut array = test_image.clone().to_ndarray();
+        let array = test_image.to_ndarray_f32();
 
-        array += 1.0;
+        assert_eq!(array.dim(), (128, 256, 4));
         for ((y, x, channel), value) in array.indexed_iter() {
-            assert_eq!(
-                test_image.get_pixel(x as u32, y as u32)[channel] + 1.0,
-                *value
-            );
+            let expected = test_image.get_pixel(x as u32, y as u32)[channel] as f32 / 255.0;
+            assert_eq!(*value, expected);
         }
+        assert_eq!(array[[0, 0, 3]], 1.0);
     }
 
     #[test]
-    fn test_from_ndarray() {
-        let (width, height, channels) = (256, 128, 4);
-        let data = create_test_data(width, height, channels);
-        let test_image = Array3::from_shape_vec((height, width, channels), data).unwrap();
-        let compare_data = test_image.clone();
+    fn test_to_ndarray_f64() {
+        let test_image = ImageBuffer::<Rgb<u16>, Vec<u16>>::from_fn(256, 128, |x, y| {
+            Rgb([x as u16 * 256, y as u16 * 512, u16::MAX])
+        });
 
-        let result = Rgba32FImage::from_ndarray(test_image).unwrap();
+        let array = test_image.to_ndarray_f64();
 
-        for (x, y, pixel) in result.enumerate_pixels() {
-            for (channel, value) in pixel.channels().iter().enumerate() {
-                assert_eq!(*value, compare_data[[y as usize, x as usize, channel]]);
-            }
+        assert_eq!(array.dim(), (128, 256, 3));
+        for ((y, x, channel), value) in array.indexed_iter() {
+            let expected = test_image.get_pixel(x as u32, y as u32)[channel] as f64 / 65535.0;
+            assert_eq!(*value, expected);
         }
+        assert_eq!(array[[0, 0, 2]], 1.0);
     }
 
     #[test]
-    fn test_from_ndarray_2d() {
+    fn test_as_rgb_ndarray() {
         let (width, height, channels) = (256, 128, 1);
         let data = create_test_data(width, height, channels);
-        let test_image = Array2::from_shape_vec((height, width), data).unwrap();
-        println!("{}", test_image.shape().len());
-        let compare_data = test_image.clone();
+        let test_image: ImageBuffer<Luma<f32>, Vec<f32>> =
+            ImageBuffer::from_vec(256, 128, data).unwrap();
 
-        let result = ImageBuffer::<Luma<f32>, Vec<f32>>::from_ndarray(test_image).unwrap();
+        let array = test_image.as_rgb_ndarray();
 
-        for (x, y, pixel) in result.enumerate_pixels() {
-            for value in pixel.channels().iter() {
-                assert_eq!(*value, compare_data[[y as usize, x as usize]]);
-            }
+        assert_eq!(array.shape(), &[height, width, 3]);
+        assert_eq!(array.strides()[2], 0);
+        for ((y, x, _), value) in array.indexed_iter() {
+            assert_eq!(test_image.get_pixel(x as u32, y as u32)[0], *value);
         }
     }
 
@@ -349,7 +2371,13 @@ mod tests {
             .err()
             .unwrap();
 
-        assert_eq!(result, Error::ChannelMismatch);
+        assert_eq!(
+            result,
+            Error::ChannelMismatch {
+                expected: 3,
+                got: 4
+            }
+        );
     }
 
     #[rstest]
@@ -373,6 +2401,210 @@ mod tests {
         assert_eq!(converted_back_to_float32, float);
     }
 
+    #[cfg(feature = "half")]
+    #[rstest]
+    #[case(1.0)]
+    #[case(0.5)]
+    #[case(-1.0)]
+    #[case(255.0)]
+    fn test_half(#[case] float: f32) {
+        let half = half::f16::from_f32_normalized(float).unwrap();
+        assert_eq!(half.to_f32_normalized().unwrap(), float);
+        assert_eq!(half.to_f64_normalized().unwrap(), float as f64);
+
+        let brain = half::bf16::from_f64_normalized(float as f64).unwrap();
+        assert_eq!(brain.to_f32_normalized().unwrap(), float);
+    }
+
+    #[cfg(feature = "half")]
+    #[test]
+    fn test_half_overflow() {
+        assert_eq!(half::f16::from_f32_normalized(1.0e6), None);
+        assert_eq!(half::bf16::from_f64_normalized(1.0e300), None);
+        assert!(
+            half::f16::from_f32_normalized(f32::INFINITY)
+                .unwrap()
+                .is_infinite()
+        );
+    }
+
+    #[test]
+    fn test_normalized_array() {
+        let array = Array3::from_shape_fn((4, 8, 3), |(y, x, z)| (y * 24 + x * 3 + z) as u8);
+
+        let result = array.to_f32_normalized().unwrap();
+        assert_eq!(result[[2, 5, 1]], 64.0 / 255.0);
+
+        let result = Array3::<u8>::from_f32_normalized(result.view()).unwrap();
+        assert_eq!(result, array);
+    }
+
+    #[test]
+    fn test_normalized_array_view() {
+        let array = Array3::from_shape_fn((8, 4, 3), |(x, y, z)| (y * 24 + x * 3 + z) as u16);
+        let transposed = array.view().permuted_axes([1, 0, 2]);
+
+        let result = transposed.to_f32_normalized().unwrap();
+
+        assert_eq!(result.dim(), (4, 8, 3));
+        assert_eq!(
+            result[[2, 5, 1]],
+            transposed[[2, 5, 1]] as f32 / u16::MAX as f32
+        );
+    }
+
+    #[cfg(feature = "half")]
+    #[test]
+    fn test_normalized_array_failed() {
+        let mut array = Array3::<f32>::zeros((4, 8, 3));
+        array[[3, 1, 2]] = 1e6;
+        array[[2, 5, 1]] = 1e6;
+
+        let result = Array3::<half::f16>::from_f32_normalized(array.view()).err();
+
+        assert_eq!(result, Some(Error::NormalizationFailed([2, 5, 1])));
+    }
+
+    #[rstest]
+    #[case(Rounding::Nearest, 128, 32768)]
+    #[case(Rounding::Floor, 127, 32767)]
+    #[case(Rounding::Ceil, 128, 32768)]
+    #[case(Rounding::Truncate, 127, 32767)]
+    fn test_from_normalized_rounding(
+        #[case] rounding: Rounding,
+        #[case] expected_u8: u8,
+        #[case] expected_u16: u16,
+    ) {
+        let clamping = Clamping::Clamp;
+        assert_eq!(
+            u8::from_f32_normalized_with(0.5, rounding, clamping),
+            Some(expected_u8)
+        );
+        assert_eq!(
+            u16::from_f64_normalized_with(0.5, rounding, clamping),
+            Some(expected_u16)
+        );
+        assert_eq!(
+            u64::from_f32_normalized_with(0.0, rounding, clamping),
+            Some(0)
+        );
+        assert_eq!(
+            f32::from_f32_normalized_with(0.5, rounding, clamping),
+            Some(0.5)
+        );
+
+        let array = Array3::from_elem((2, 3, 1), 0.5f32);
+        let result =
+            Array3::<u8>::from_f32_normalized_with(array.view(), rounding, clamping).unwrap();
+        assert!(result.iter().all(|value| *value == expected_u8));
+    }
+
+    #[test]
+    fn test_from_normalized_rounds_to_nearest() {
+        assert_eq!(u8::from_f32_normalized(0.999), Some(255));
+        assert_eq!(u8::from_f64_normalized(1.0 / 255.0 - 1e-6), Some(1));
+        assert_eq!(i8::from_f32_normalized(-0.999), Some(-127));
+        assert_eq!(u8::from_f32_normalized(1.2), Some(255));
+        assert_eq!(u8::from_f32_normalized(f32::NAN), Some(0));
+    }
+
+    #[rstest]
+    #[case(1.0, Ok(255))]
+    #[case(0.5, Ok(128))]
+    #[case(1.01, Err(NormalizeError::OutOfRange))]
+    #[case(-0.01, Err(NormalizeError::OutOfRange))]
+    #[case(f64::NAN, Err(NormalizeError::NotANumber))]
+    #[case(f64::INFINITY, Err(NormalizeError::Overflow))]
+    fn test_try_from_normalized(
+        #[case] value: f64,
+        #[case] expected: core::result::Result<u8, NormalizeError>,
+    ) {
+        assert_eq!(u8::try_from_f64_normalized(value), expected);
+        assert_eq!(u8::try_from_f32_normalized(value as f32), expected);
+    }
+
+    #[test]
+    fn test_try_normalized_floats() {
+        assert_eq!(f32::try_from_f64_normalized(1.5), Ok(1.5));
+        assert_eq!(
+            f32::try_from_f64_normalized(1e300),
+            Err(NormalizeError::Overflow)
+        );
+        assert_eq!(
+            f32::try_from_f32_normalized(f32::NEG_INFINITY),
+            Err(NormalizeError::Overflow)
+        );
+        assert_eq!(
+            1e300f64.try_to_f32_normalized(),
+            Err(NormalizeError::Overflow)
+        );
+        assert_eq!(
+            f64::NAN.try_to_f64_normalized(),
+            Err(NormalizeError::NotANumber)
+        );
+        assert_eq!(255u8.try_to_f32_normalized(), Ok(1.0));
+        assert_eq!(
+            i64::MIN.try_to_f64_normalized(),
+            i64::MIN.to_f64_normalized().ok_or(NormalizeError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_from_normalized_clamping_error() {
+        let (rounding, clamping) = (Rounding::Nearest, Clamping::Error);
+        assert_eq!(
+            u8::from_f32_normalized_with(1.0, rounding, clamping),
+            Some(255)
+        );
+        assert_eq!(u8::from_f32_normalized_with(1.01, rounding, clamping), None);
+        assert_eq!(
+            i16::from_f64_normalized_with(-1.5, rounding, clamping),
+            None
+        );
+        assert_eq!(
+            u64::from_f64_normalized_with(f64::NAN, rounding, clamping),
+            None
+        );
+        assert_eq!(
+            u8::from_f32_normalized_with(1.2, rounding, Clamping::Wrap),
+            Some(50)
+        );
+        assert_eq!(
+            f32::from_f32_normalized_with(1.5, rounding, clamping),
+            Some(1.5)
+        );
+
+        let mut array = Array3::from_elem((4, 8, 3), 0.25f32);
+        array[[3, 1, 0]] = -0.1;
+        array[[1, 6, 2]] = 2.0;
+        let result = Array3::<u16>::from_f32_normalized_with(array.view(), rounding, clamping);
+        assert_eq!(result.err(), Some(Error::NormalizationFailed([1, 6, 2])));
+    }
+
+    #[rstest]
+    #[case(SignedNormalization::Legacy, -32768.0 / 32767.0, i16::MIN)]
+    #[case(SignedNormalization::Symmetric, -1.0, -i16::MAX)]
+    #[case(SignedNormalization::Offset, 0.0, i16::MIN)]
+    fn test_signed_normalization(
+        #[case] signed: SignedNormalization,
+        #[case] expected: f64,
+        #[case] expected_int: i16,
+    ) {
+        let normalized = i16::MIN.to_f64_normalized_signed(signed).unwrap();
+        assert_eq!(normalized, expected);
+        assert_eq!(
+            i16::from_f64_normalized_signed(normalized, signed),
+            Some(expected_int)
+        );
+
+        let normalized = 200u8.to_f64_normalized_signed(signed).unwrap();
+        assert_eq!(normalized, 200.0 / 255.0);
+        assert_eq!(
+            u8::from_f64_normalized_signed(normalized, signed),
+            Some(200)
+        );
+    }
+
     #[macro_export]
     macro_rules! test_unsigned_ints {
         ($name:ident, $type:ty) => {
@@ -445,4 +2677,41 @@ mod tests {
     test_unsigned_ints!(test_u32, u32);
     test_unsigned_ints!(test_u16, u16);
     test_unsigned_ints!(test_u8, u8);
+    test_signed_ints!(test_i64, i64);
+    test_unsigned_ints!(test_u64, u64);
+    test_unsigned_ints!(test_usize, usize);
+
+    #[test]
+    fn test_u64_precision() {
+        let value = u64::MAX / 3;
+
+        assert_eq!(value.to_f32_normalized().unwrap(), 1.0 / 3.0);
+        assert_eq!(u64::from_f64_normalized(1.0).unwrap(), u64::MAX);
+    }
+
+    #[rstest]
+    #[case(1.0, Clamping::Error, Some(u64::MAX), Some(i64::MAX))]
+    #[case(1.0, Clamping::Wrap, Some(u64::MAX), Some(i64::MAX))]
+    #[case(-1.0, Clamping::Error, None, Some(i64::MIN))]
+    #[case(-1.0, Clamping::Wrap, Some(0), Some(i64::MIN))]
+    fn test_64_bit_bounds(
+        #[case] value: f64,
+        #[case] clamping: Clamping,
+        #[case] expected_u64: Option<u64>,
+        #[case] expected_i64: Option<i64>,
+    ) {
+        let unsigned = u64::from_f64_normalized_with(value, Rounding::Nearest, clamping);
+        let signed = i64::from_f64_normalized_with(value, Rounding::Nearest, clamping);
+
+        assert_eq!(unsigned, expected_u64);
+        assert_eq!(signed, expected_i64);
+        assert_eq!(
+            u64::from_f64_with(value, true, Rounding::Nearest, clamping),
+            expected_u64
+        );
+        assert_eq!(
+            i64::from_f64_with(value, true, Rounding::Nearest, clamping),
+            expected_i64
+        );
+    }
 }
@@ -0,0 +1,181 @@
+//! Transfer functions between encoded samples and linear light
+
+use crate::lut::{LutSample, NormalizeLut};
+use crate::traits::NormalizedFloat;
+use ndarray::{Array3, ArrayView3, ArrayViewMut3};
+use num_traits::{AsPrimitive, Zero};
+
+/// Decode a normalized sRGB value into linear light, following IEC 61966-2-1.
+pub fn srgb_to_linear(value: f32) -> f32 {
+    match value <= 0.04045 {
+        true => value / 12.92,
+        false => ((value + 0.055) / 1.055).powf(2.4),
+    }
+}
+
+/// Encode a linear light value into normalized sRGB, following IEC 61966-2-1.
+pub fn linear_to_srgb(value: f32) -> f32 {
+    match value <= 0.0031308 {
+        true => value * 12.92,
+        false => 1.055 * value.powf(1.0 / 2.4) - 0.055,
+    }
+}
+
+/// Decode every sample of the normalized sRGB array into linear light.
+///
+/// All channels are decoded, so slice off an alpha channel first if it should stay as is.
+pub fn srgb_to_linear_in_place(mut array: ArrayViewMut3<'_, f32>) {
+    array.mapv_inplace(srgb_to_linear);
+}
+
+/// Encode every sample of the linear light array into normalized sRGB.
+///
+/// All channels are encoded, so slice off an alpha channel first if it should stay as is.
+pub fn linear_to_srgb_in_place(mut array: ArrayViewMut3<'_, f32>) {
+    array.mapv_inplace(linear_to_srgb);
+}
+
+/// Decode sRGB encoded integer samples straight into normalized linear light.
+///
+/// For example a `u8` of 188 becomes about 0.5. The curve is evaluated once per possible
+/// value with a [`NormalizeLut`], so this is as fast as a plain normalization.
+pub fn srgb_to_linear_normalized<T: LutSample>(array: ArrayView3<'_, T>) -> Array3<f32> {
+    NormalizeLut::srgb().apply(array)
+}
+
+/// Encode normalized linear light into sRGB encoded samples of `T`.
+///
+/// This is the inverse of [`srgb_to_linear_normalized`]. Values are rounded to the nearest
+/// integer and clamped to the range of `T`, values that can not be represented become zero.
+pub fn linear_to_srgb_quantized<T>(array: ArrayView3<'_, f32>) -> Array3<T>
+where
+    T: NormalizedFloat<T> + AsPrimitive<f32> + AsPrimitive<f64> + Zero,
+{
+    array.mapv(|value| T::from_f32_normalized(linear_to_srgb(value)).unwrap_or_else(T::zero))
+}
+
+/// Encode linear values with a pure power curve of `gamma`, so `value^(1 / gamma)`.
+///
+/// For example a gamma of 2.2 prepares linear values for a display without sRGB curve.
+/// Negative values are mirrored, so the sign is kept. `gamma` needs to be positive.
+pub fn apply_gamma(array: ArrayView3<'_, f32>, gamma: f32) -> Array3<f32> {
+    let exponent = gamma.recip();
+    array.mapv(|value| power(value, exponent))
+}
+
+/// Decode values encoded with a pure power curve of `gamma` into linear, so `value^gamma`.
+///
+/// This is the inverse of [`apply_gamma`].
+pub fn remove_gamma(array: ArrayView3<'_, f32>, gamma: f32) -> Array3<f32> {
+    array.mapv(|value| power(value, gamma))
+}
+
+/// Encode the linear values with a pure power curve of `gamma`, like [`apply_gamma`].
+pub fn apply_gamma_in_place(mut array: ArrayViewMut3<'_, f32>, gamma: f32) {
+    let exponent = gamma.recip();
+    array.mapv_inplace(|value| power(value, exponent));
+}
+
+/// Decode the values encoded with a pure power curve of `gamma`, like [`remove_gamma`].
+pub fn remove_gamma_in_place(mut array: ArrayViewMut3<'_, f32>, gamma: f32) {
+    array.mapv_inplace(|value| power(value, gamma));
+}
+
+/// Raise the magnitude of the value to the exponent, keeping the sign.
+///
+/// The common exponents of 1, 2 and 0.5 avoid the much slower `powf`.
+fn power(value: f32, exponent: f32) -> f32 {
+    if exponent == 1.0 {
+        value
+    } else if exponent == 2.0 {
+        value * value.abs()
+    } else if exponent == 0.5 {
+        value.abs().sqrt().copysign(value)
+    } else {
+        value.abs().powf(exponent).copysign(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case(0.0, 0.0)]
+    #[case(1.0, 1.0)]
+    #[case(0.04045, 0.0031308)]
+    #[case(0.5, 0.21404)]
+    #[case(0.73536, 0.5)]
+    fn test_srgb_to_linear(#[case] encoded: f32, #[case] linear: f32) {
+        assert!((srgb_to_linear(encoded) - linear).abs() < 1e-5);
+        assert!((linear_to_srgb(linear) - encoded).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for value in 0..=1000 {
+            let value = value as f32 / 1000.0;
+            assert!((linear_to_srgb(srgb_to_linear(value)) - value).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_in_place() {
+        let mut array =
+            Array3::from_shape_fn((4, 8, 3), |(y, x, z)| (y * 24 + x * 3 + z) as f32 / 95.0);
+        let expected = array.clone();
+
+        srgb_to_linear_in_place(array.view_mut());
+        assert_eq!(array[[2, 3, 1]], srgb_to_linear(expected[[2, 3, 1]]));
+
+        linear_to_srgb_in_place(array.view_mut());
+        assert!(
+            array
+                .iter()
+                .zip(&expected)
+                .all(|(a, b)| (a - b).abs() < 1e-6)
+        );
+    }
+
+    #[rstest]
+    #[case(1.0)]
+    #[case(2.0)]
+    #[case(0.5)]
+    #[case(2.2)]
+    #[case(2.4)]
+    fn test_gamma(#[case] gamma: f32) {
+        let array = Array3::from_shape_fn((4, 8, 3), |(y, x, z)| {
+            (y * 24 + x * 3 + z) as f32 / 47.5 - 0.5
+        });
+
+        let encoded = apply_gamma(array.view(), gamma);
+        let decoded = remove_gamma(encoded.view(), gamma);
+
+        for ((value, encoded), decoded) in array.iter().zip(&encoded).zip(&decoded) {
+            assert!((encoded - value.abs().powf(1.0 / gamma).copysign(*value)).abs() < 1e-6);
+            assert!((decoded - value).abs() < 1e-5);
+        }
+
+        let mut in_place = array.clone();
+        apply_gamma_in_place(in_place.view_mut(), gamma);
+        assert_eq!(in_place, encoded);
+        remove_gamma_in_place(in_place.view_mut(), gamma);
+        assert_eq!(in_place, decoded);
+    }
+
+    #[test]
+    fn test_fused_integer_round_trip() {
+        let array = Array3::from_shape_fn((16, 16, 1), |(y, x, _)| (y * 16 + x) as u8);
+
+        let linear = srgb_to_linear_normalized(array.view());
+        assert_eq!(linear[[11, 12, 0]], srgb_to_linear(188.0 / 255.0));
+
+        let result = linear_to_srgb_quantized::<u8>(linear.view());
+        assert_eq!(result, array);
+
+        let array = array.mapv(|value| u16::from(value) * 257);
+        let linear = srgb_to_linear_normalized(array.view());
+        assert_eq!(linear_to_srgb_quantized::<u16>(linear.view()), array);
+    }
+}
@@ -0,0 +1,427 @@
+//! Owned ndarray that is statically tied to a pixel type
+
+use crate::depth::scale_depth;
+use crate::error::{Error, Result};
+use crate::normalize::ConvertSample;
+use crate::raw_parts::RawParts;
+use crate::traits::{ImageArray, NormalizedFloat};
+use image::{ImageBuffer, Luma, LumaA, Pixel, Rgb, Rgba};
+use ndarray::{Array3, ArrayView3, ArrayViewMut3};
+use num_traits::AsPrimitive;
+
+/// Owned `(y, x, z)` array whose channel axis is guaranteed to match the pixel `P`.
+///
+/// * `Y` index is the row
+/// * `X` index is the columns
+/// * `Z` index is the channel
+///
+/// The channel count is validated once on construction, so converting back into an
+/// ImageBuffer can not fail. This removes the [`Error::ChannelMismatch`] class of errors
+/// from pipelines that never change the channel count.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedArray3<P>
+where
+    P: Pixel,
+{
+    array: Array3<P::Subpixel>,
+}
+
+impl<P> TypedArray3<P>
+where
+    P: Pixel,
+{
+    /// Wrap the provided array.
+    ///
+    /// Returns [`Error::ChannelMismatch`] if the channel axis does not match the pixel and
+    /// [`Error::ImageConstructFailed`] if the dimensions do not fit in an ImageBuffer.
+    ///
+    /// This does not copy the data when the array is in standard layout,
+    /// otherwise it is repacked once.
+    pub fn new(array: Array3<P::Subpixel>) -> Result<Self> {
+        let (height, width, channels) = array.dim();
+        if channels != P::CHANNEL_COUNT.into() {
+            return Err(Error::ChannelMismatch {
+                expected: P::CHANNEL_COUNT.into(),
+                got: channels,
+            });
+        }
+        if u32::try_from(width).is_err() || u32::try_from(height).is_err() {
+            return Err(Error::ImageConstructFailed);
+        }
+
+        Ok(Self {
+            array: RawParts::from_ndarray(array).into_ndarray(),
+        })
+    }
+
+    /// Reference the array as an ArrayView3.
+    pub fn as_ndarray(&self) -> ArrayView3<'_, P::Subpixel> {
+        self.array.view()
+    }
+
+    /// Reference the array as an ArrayViewMut3.
+    ///
+    /// The view allows changing the values, but not the shape of the array.
+    pub fn as_ndarray_mut(&mut self) -> ArrayViewMut3<'_, P::Subpixel> {
+        self.array.view_mut()
+    }
+
+    /// Consume the wrapper and return the underlying Array3.
+    pub fn into_ndarray(self) -> Array3<P::Subpixel> {
+        self.array
+    }
+
+    /// Convert into an ImageBuffer.
+    ///
+    /// This does not copy the data, but it does consume the array.
+    pub fn into_image(self) -> ImageBuffer<P, Vec<P::Subpixel>> {
+        let (height, width, _) = self.array.dim();
+        let (data, _) = self.array.into_raw_vec_and_offset();
+        ImageBuffer::from_raw(width as u32, height as u32, data)
+            .expect("shape is validated on construction")
+    }
+
+    /// Scale the samples to the bit depth of the pixel `Q`, for example from `Rgb<u8>`
+    /// to `Rgb<u16>`.
+    ///
+    /// See [`scale_depth`] for how the values are scaled.
+    ///
+    /// Returns [`Error::ChannelMismatch`] if `Q` has another channel count.
+    pub fn scale_depth<Q>(&self) -> Result<TypedArray3<Q>>
+    where
+        P::Subpixel: NormalizedFloat<P::Subpixel> + AsPrimitive<f32> + AsPrimitive<f64> + Sync,
+        Q: Pixel,
+        Q::Subpixel: ConvertSample,
+    {
+        if P::CHANNEL_COUNT != Q::CHANNEL_COUNT {
+            return Err(Error::ChannelMismatch {
+                expected: P::CHANNEL_COUNT.into(),
+                got: Q::CHANNEL_COUNT.into(),
+            });
+        }
+        Ok(TypedArray3 {
+            array: scale_depth(self.array.view()),
+        })
+    }
+}
+
+impl<P> From<ImageBuffer<P, Vec<P::Subpixel>>> for TypedArray3<P>
+where
+    P: Pixel,
+{
+    fn from(image: ImageBuffer<P, Vec<P::Subpixel>>) -> Self {
+        let (width, height) = image.dimensions();
+        let size = width as usize * height as usize * P::CHANNEL_COUNT as usize;
+        let mut data = image.into_raw();
+        data.truncate(size);
+        Self {
+            array: Array3::from_shape_vec(
+                (height as usize, width as usize, P::CHANNEL_COUNT as usize),
+                data,
+            )
+            .expect("ImageBuffer always holds enough data"),
+        }
+    }
+}
+
+impl<P> From<TypedArray3<P>> for ImageBuffer<P, Vec<P::Subpixel>>
+where
+    P: Pixel,
+{
+    fn from(array: TypedArray3<P>) -> Self {
+        array.into_image()
+    }
+}
+
+impl<P> TryFrom<Array3<P::Subpixel>> for TypedArray3<P>
+where
+    P: Pixel,
+{
+    type Error = Error;
+
+    fn try_from(array: Array3<P::Subpixel>) -> Result<Self> {
+        Self::new(array)
+    }
+}
+
+/// Borrowed `(y, x, z)` view whose channel axis is guaranteed to match the pixel `P`.
+///
+/// * `Y` index is the row
+/// * `X` index is the columns
+/// * `Z` index is the channel
+///
+/// This allows function signatures to state which pixel layout they expect, for example
+/// `fn blur(image: RgbaArrayView<f32>)`, instead of accepting any `ArrayView3`.
+#[derive(Debug, Clone, Copy)]
+pub struct PixelArrayView<'a, P>
+where
+    P: Pixel,
+{
+    array: ArrayView3<'a, P::Subpixel>,
+}
+
+/// View of an RGB array.
+pub type RgbArrayView<'a, T> = PixelArrayView<'a, Rgb<T>>;
+/// View of an RGBA array.
+pub type RgbaArrayView<'a, T> = PixelArrayView<'a, Rgba<T>>;
+/// View of a grayscale array.
+pub type LumaArrayView<'a, T> = PixelArrayView<'a, Luma<T>>;
+/// View of a grayscale array with alpha.
+pub type LumaAArrayView<'a, T> = PixelArrayView<'a, LumaA<T>>;
+
+impl<'a, P> PixelArrayView<'a, P>
+where
+    P: Pixel,
+{
+    /// Wrap the provided view.
+    ///
+    /// Returns [`Error::ChannelMismatch`] if the channel axis does not match the pixel.
+    ///
+    /// This does not copy the data, as it is a reference to the actual data in the array.
+    pub fn new(array: ArrayView3<'a, P::Subpixel>) -> Result<Self> {
+        if array.dim().2 != P::CHANNEL_COUNT.into() {
+            return Err(Error::ChannelMismatch {
+                expected: P::CHANNEL_COUNT.into(),
+                got: array.dim().2,
+            });
+        }
+        Ok(Self { array })
+    }
+
+    /// Reference the view as an ArrayView3.
+    pub fn as_ndarray(&self) -> ArrayView3<'_, P::Subpixel> {
+        self.array.view()
+    }
+
+    /// Consume the wrapper and return the underlying ArrayView3.
+    pub fn into_inner(self) -> ArrayView3<'a, P::Subpixel> {
+        self.array
+    }
+}
+
+/// Mutable `(y, x, z)` view whose channel axis is guaranteed to match the pixel `P`.
+///
+/// This is the mutable counterpart of [`PixelArrayView`].
+#[derive(Debug)]
+pub struct PixelArrayViewMut<'a, P>
+where
+    P: Pixel,
+{
+    array: ArrayViewMut3<'a, P::Subpixel>,
+}
+
+/// Mutable view of an RGB array.
+pub type RgbArrayViewMut<'a, T> = PixelArrayViewMut<'a, Rgb<T>>;
+/// Mutable view of an RGBA array.
+pub type RgbaArrayViewMut<'a, T> = PixelArrayViewMut<'a, Rgba<T>>;
+/// Mutable view of a grayscale array.
+pub type LumaArrayViewMut<'a, T> = PixelArrayViewMut<'a, Luma<T>>;
+/// Mutable view of a grayscale array with alpha.
+pub type LumaAArrayViewMut<'a, T> = PixelArrayViewMut<'a, LumaA<T>>;
+
+impl<'a, P> PixelArrayViewMut<'a, P>
+where
+    P: Pixel,
+{
+    /// Wrap the provided view.
+    ///
+    /// Returns [`Error::ChannelMismatch`] if the channel axis does not match the pixel.
+    ///
+    /// This does not copy the data, as it is a reference to the actual data in the array.
+    pub fn new(array: ArrayViewMut3<'a, P::Subpixel>) -> Result<Self> {
+        if array.dim().2 != P::CHANNEL_COUNT.into() {
+            return Err(Error::ChannelMismatch {
+                expected: P::CHANNEL_COUNT.into(),
+                got: array.dim().2,
+            });
+        }
+        Ok(Self { array })
+    }
+
+    /// Reference the view as an ArrayView3.
+    pub fn as_ndarray(&self) -> ArrayView3<'_, P::Subpixel> {
+        self.array.view()
+    }
+
+    /// Reference the view as an ArrayViewMut3.
+    ///
+    /// The view allows changing the values, but not the shape of the array.
+    pub fn as_ndarray_mut(&mut self) -> ArrayViewMut3<'_, P::Subpixel> {
+        self.array.view_mut()
+    }
+
+    /// Consume the wrapper and return the underlying ArrayViewMut3.
+    pub fn into_inner(self) -> ArrayViewMut3<'a, P::Subpixel> {
+        self.array
+    }
+}
+
+impl<P> TypedArray3<P>
+where
+    P: Pixel,
+{
+    /// Reference the array as a [`PixelArrayView`].
+    pub fn pixel_view(&self) -> PixelArrayView<'_, P> {
+        PixelArrayView {
+            array: self.array.view(),
+        }
+    }
+
+    /// Reference the array as a [`PixelArrayViewMut`].
+    pub fn pixel_view_mut(&mut self) -> PixelArrayViewMut<'_, P> {
+        PixelArrayViewMut {
+            array: self.array.view_mut(),
+        }
+    }
+}
+
+impl<'a, P> From<&'a ImageBuffer<P, Vec<P::Subpixel>>> for PixelArrayView<'a, P>
+where
+    P: Pixel + 'static,
+{
+    fn from(image: &'a ImageBuffer<P, Vec<P::Subpixel>>) -> Self {
+        Self {
+            array: image.as_ndarray(),
+        }
+    }
+}
+
+impl<'a, P> From<&'a mut ImageBuffer<P, Vec<P::Subpixel>>> for PixelArrayViewMut<'a, P>
+where
+    P: Pixel + 'static,
+{
+    fn from(image: &'a mut ImageBuffer<P, Vec<P::Subpixel>>) -> Self {
+        Self {
+            array: image.as_ndarray_mut(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{RgbImage, Rgba32FImage};
+    use ndarray::s;
+
+    #[test]
+    fn test_image_round_trip() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let test_image = Rgba32FImage::from_vec(256, 128, data).unwrap();
+
+        let array = TypedArray3::from(test_image.clone());
+        assert_eq!(array.as_ndarray(), test_image.as_ndarray());
+
+        let result: Rgba32FImage = array.into();
+        assert_eq!(result, test_image);
+    }
+
+    #[test]
+    fn test_try_from_array() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let array = Array3::from_shape_vec((height, width, channels), data).unwrap();
+
+        let typed: TypedArray3<Rgba<f32>> = array.clone().try_into().unwrap();
+
+        assert_eq!(typed.into_image().as_ndarray(), array);
+    }
+
+    #[test]
+    fn test_new_repacks_non_standard_layout() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let array = Array3::from_shape_vec((width, height, channels), data)
+            .unwrap()
+            .permuted_axes([1, 0, 2]);
+
+        let typed = TypedArray3::<Rgba<f32>>::new(array.clone()).unwrap();
+
+        assert_eq!(typed.into_image().as_ndarray(), array);
+    }
+
+    #[test]
+    fn test_new_with_offset() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let mut array = Array3::from_shape_vec((height, width, channels), data).unwrap();
+        array.slice_collapse(s![10..20, .., ..]);
+        let compare = array.clone();
+
+        let typed = TypedArray3::<Rgba<f32>>::new(array).unwrap();
+
+        assert_eq!(typed.into_image().as_ndarray(), compare);
+    }
+
+    #[test]
+    fn test_new_with_invalid_channels() {
+        let array = Array3::<f32>::zeros((128, 256, 4));
+
+        let result = TypedArray3::<Rgb<f32>>::new(array).err().unwrap();
+
+        assert_eq!(
+            result,
+            Error::ChannelMismatch {
+                expected: 3,
+                got: 4
+            }
+        );
+    }
+
+    #[test]
+    fn test_pixel_array_view() {
+        let (width, height, channels) = (256, 128, 4);
+        let data = create_test_data(width, height, channels);
+        let array = Array3::from_shape_vec((height, width, channels), data).unwrap();
+
+        let view = RgbaArrayView::<f32>::new(array.view()).unwrap();
+        assert_eq!(view.as_ndarray(), array);
+
+        let result = RgbArrayView::<f32>::new(array.view()).err().unwrap();
+        assert_eq!(
+            result,
+            Error::ChannelMismatch {
+                expected: 3,
+                got: 4
+            }
+        );
+    }
+
+    #[test]
+    fn test_pixel_array_view_mut() {
+        let mut test_image = Rgba32FImage::new(16, 8);
+
+        let mut view = RgbaArrayViewMut::from(&mut test_image);
+        view.as_ndarray_mut().fill(1.0);
+
+        assert_eq!(test_image.as_ndarray(), Array3::from_elem((8, 16, 4), 1.0));
+        let mut typed = TypedArray3::from(test_image);
+        typed.pixel_view_mut().as_ndarray_mut()[[0, 0, 0]] = 2.0;
+        assert_eq!(typed.pixel_view().into_inner()[[0, 0, 0]], 2.0);
+    }
+
+    #[test]
+    fn test_scale_depth() {
+        let test_image = RgbImage::from_fn(16, 8, |x, y| Rgb([x as u8, y as u8, 255]));
+        let typed = TypedArray3::from(test_image);
+
+        let result = typed.scale_depth::<Rgb<u16>>().unwrap();
+        assert_eq!(result.as_ndarray()[[2, 3, 0]], 3 * 257);
+        assert_eq!(result.as_ndarray()[[2, 3, 2]], u16::MAX);
+        assert_eq!(result.scale_depth::<Rgb<u8>>().unwrap(), typed);
+
+        let result = typed.scale_depth::<Rgba<u16>>().err().unwrap();
+        assert_eq!(
+            result,
+            Error::ChannelMismatch {
+                expected: 3,
+                got: 4
+            }
+        );
+    }
+
+    fn create_test_data(width: usize, height: usize, channels: usize) -> Vec<f32> {
+        let total_elements = width * height * channels;
+        (0..total_elements).map(|x| (x + 1) as f32).collect()
+    }
+}
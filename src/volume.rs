@@ -0,0 +1,580 @@
+//! Volume type for working with stacks of images as a single Array4
+
+use crate::error::{Error, Result};
+#[cfg(feature = "image")]
+use crate::traits::ImageArray;
+use crate::traits::check_region;
+use core::cmp::Ordering;
+#[cfg(feature = "image")]
+use image::{ImageBuffer, Pixel};
+use ndarray::{
+    Array3, Array4, ArrayView1, ArrayView3, ArrayView4, ArrayViewMut3, ArrayViewMut4, Axis, s,
+};
+use num_traits::Zero;
+use num_traits::float::FloatCore;
+
+/// Reduction used to project a stack along one of its axes.
+///
+/// The `Nan` variants ignore NaN values, while the other variants propagate them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Projection {
+    /// Maximum value along the axis.
+    Max,
+    /// Average value along the axis.
+    Mean,
+    /// Summed value along the axis.
+    Sum,
+    /// Maximum value along the axis, ignoring NaN.
+    ///
+    /// Results in NaN if all values are NaN.
+    NanMax,
+    /// Average value along the axis, ignoring NaN.
+    ///
+    /// Results in NaN if all values are NaN.
+    NanMean,
+    /// Summed value along the axis, ignoring NaN.
+    ///
+    /// Results in 0.0 if all values are NaN.
+    NanSum,
+}
+
+/// Project a `(z, y, x, c)` stack along the provided axis.
+///
+/// The provided axis is removed from the result, so projecting along `Axis(0)`
+/// results in a `(y, x, c)` array.
+///
+/// **Panics** if the axis is out of bounds.
+pub fn project<T>(stack: ArrayView4<'_, T>, axis: Axis, projection: Projection) -> Array3<T>
+where
+    T: FloatCore,
+{
+    stack.map_axis(axis, |lane| reduce(lane, projection))
+}
+
+/// Project a `(z, y, x, c)` stack of any ordered samples to its maximum along the provided axis.
+///
+/// This is [`Projection::Max`] for samples that are not floats, like the u8 and u16 slices
+/// of confocal or CT stacks. Unordered values, like NaN, propagate.
+///
+/// **Panics** if the axis is out of bounds or empty.
+pub fn project_max<T>(stack: ArrayView4<'_, T>, axis: Axis) -> Array3<T>
+where
+    T: Clone + PartialOrd,
+{
+    stack.map_axis(axis, |lane| lane_max(lane).expect("axis is not empty"))
+}
+
+/// Project a `(z, y, x, c)` stack to its sum along the provided axis, accumulated in `S`.
+///
+/// This is [`Projection::Sum`] for samples that are not floats. The sum is accumulated in a
+/// type that every sample converts into without loss, so for example a u16 stack can be
+/// summed as u64 or f64 without overflowing:
+///
+/// ```
+/// use image_ndarray::project_sum;
+/// use ndarray::{Array4, Axis};
+///
+/// let stack = Array4::from_elem((3, 2, 2, 1), u16::MAX);
+/// let sum = project_sum::<u16, u64>(stack.view(), Axis(0));
+/// assert_eq!(sum[[0, 0, 0]], 3 * u64::from(u16::MAX));
+/// ```
+///
+/// **Panics** if the axis is out of bounds.
+pub fn project_sum<T, S>(stack: ArrayView4<'_, T>, axis: Axis) -> Array3<S>
+where
+    T: Clone + Into<S>,
+    S: Zero + Clone,
+{
+    stack.map_axis(axis, lane_sum)
+}
+
+/// Extract the values of the provided `(x, y)` pixels over all slices of a `(z, y, x, c)` stack.
+///
+/// The result has the shape `(points, z, c)`, so when referencing:
+/// `result[[point, z, c]]`
+///
+/// Returns [`Error::OutOfBounds`] if any of the points is outside of the stack.
+pub fn pixel_timeseries<T>(stack: ArrayView4<'_, T>, points: &[(u32, u32)]) -> Result<Array3<T>>
+where
+    T: Clone,
+{
+    check_stack_regions(&stack, points.iter().map(|&(x, y)| (x, y, 1, 1)))?;
+
+    let (depth, channels) = (stack.len_of(Axis(0)), stack.len_of(Axis(3)));
+    Ok(Array3::from_shape_fn(
+        (points.len(), depth, channels),
+        |(point, z, c)| {
+            let (x, y) = points[point];
+            stack[[z, y as usize, x as usize, c]].clone()
+        },
+    ))
+}
+
+/// Average the provided `(x, y, width, height)` regions over all slices of a `(z, y, x, c)` stack.
+///
+/// The result has the shape `(regions, z, c)`, so when referencing:
+/// `result[[region, z, c]]`
+///
+/// Empty regions result in NaN.
+///
+/// Returns [`Error::OutOfBounds`] if any of the regions does not fit within the stack.
+pub fn region_timeseries<T>(
+    stack: ArrayView4<'_, T>,
+    regions: &[(u32, u32, u32, u32)],
+) -> Result<Array3<T>>
+where
+    T: FloatCore,
+{
+    check_stack_regions(&stack, regions.iter().copied())?;
+
+    let (depth, channels) = (stack.len_of(Axis(0)), stack.len_of(Axis(3)));
+    let mut result = Array3::zeros((regions.len(), depth, channels));
+    for (mut series, &(x, y, width, height)) in result.outer_iter_mut().zip(regions) {
+        let (x, y, width, height) = (x as usize, y as usize, width as usize, height as usize);
+        let region = stack.slice(s![.., y..y + height, x..x + width, ..]);
+        for (mut values, slice) in series.outer_iter_mut().zip(region.outer_iter()) {
+            for (value, channel) in values.iter_mut().zip(slice.axis_iter(Axis(2))) {
+                *value = reduce(channel.flatten().view(), Projection::Mean);
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn check_stack_regions<T>(
+    stack: &ArrayView4<'_, T>,
+    mut regions: impl Iterator<Item = (u32, u32, u32, u32)>,
+) -> Result<()> {
+    let width = u32::try_from(stack.len_of(Axis(2))).unwrap_or(u32::MAX);
+    let height = u32::try_from(stack.len_of(Axis(1))).unwrap_or(u32::MAX);
+    regions.try_for_each(|(x, y, w, h)| check_region(x, y, w, h, width, height))
+}
+
+fn reduce<T: FloatCore>(lane: ArrayView1<'_, T>, projection: Projection) -> T {
+    let count = |values: usize| T::from(values).unwrap_or_else(T::nan);
+    match projection {
+        Projection::Max => lane_max(lane).unwrap_or_else(T::neg_infinity),
+        Projection::Sum => lane_sum(lane),
+        Projection::Mean => reduce(lane, Projection::Sum) / count(lane.len()),
+        Projection::NanMax => lane
+            .iter()
+            .filter(|value| !value.is_nan())
+            .fold(T::nan(), |acc, value| acc.max(*value)),
+        Projection::NanSum => lane
+            .iter()
+            .filter(|value| !value.is_nan())
+            .fold(T::zero(), |acc, value| acc + *value),
+        Projection::NanMean => {
+            let valid = lane.iter().filter(|value| !value.is_nan()).count();
+            reduce(lane, Projection::NanSum) / count(valid)
+        }
+    }
+}
+
+/// Maximum of the lane, where an unordered value like NaN is the result once it is found.
+///
+/// Returns None for an empty lane.
+fn lane_max<T: Clone + PartialOrd>(lane: ArrayView1<'_, T>) -> Option<T> {
+    let mut values = lane.iter();
+    let first = values.next()?.clone();
+    Some(
+        values.fold(first, |max, value| match max.partial_cmp(value) {
+            Some(Ordering::Less) => value.clone(),
+            Some(_) => max,
+            None if max.partial_cmp(&max).is_none() => max,
+            None => value.clone(),
+        }),
+    )
+}
+
+/// Sum of the lane, accumulated in `S`.
+fn lane_sum<T: Clone + Into<S>, S: Zero + Clone>(lane: ArrayView1<'_, T>) -> S {
+    lane.iter()
+        .fold(S::zero(), |sum, value| sum + value.clone().into())
+}
+
+/// A stack of equally sized images, stored as a single Array4.
+///
+/// * `Z` index is the slice
+/// * `Y` index is the row
+/// * `X` index is the columns
+/// * `C` index is the channel
+///
+/// So when referencing:
+/// `array[[z, y, x, c]]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Volume<T> {
+    array: Array4<T>,
+}
+
+impl<T> Volume<T> {
+    /// Create a volume from an Array4 in `(z, y, x, c)` order.
+    ///
+    /// Returns [`Error::EmptyVolume`] if the array does not contain any slice.
+    pub fn new(array: Array4<T>) -> Result<Self> {
+        if array.len_of(Axis(0)) == 0 {
+            return Err(Error::EmptyVolume);
+        }
+        Ok(Self { array })
+    }
+
+    /// Amount of slices in the volume.
+    pub fn depth(&self) -> usize {
+        self.array.len_of(Axis(0))
+    }
+
+    /// Amount of rows in every slice.
+    pub fn height(&self) -> usize {
+        self.array.len_of(Axis(1))
+    }
+
+    /// Amount of columns in every slice.
+    pub fn width(&self) -> usize {
+        self.array.len_of(Axis(2))
+    }
+
+    /// Amount of channels in every slice.
+    pub fn channels(&self) -> usize {
+        self.array.len_of(Axis(3))
+    }
+
+    /// Reference the volume as an ArrayView4.
+    pub fn as_ndarray(&self) -> ArrayView4<'_, T> {
+        self.array.view()
+    }
+
+    /// Reference the volume as an ArrayViewMut4.
+    pub fn as_ndarray_mut(&mut self) -> ArrayViewMut4<'_, T> {
+        self.array.view_mut()
+    }
+
+    /// Consume the volume and return the underlying Array4.
+    pub fn into_ndarray(self) -> Array4<T> {
+        self.array
+    }
+
+    /// View the slice at depth `z`, in `(y, x, c)` order.
+    ///
+    /// Returns [`Error::OutOfBounds`] if `z` is outside of the volume.
+    pub fn slice_z(&self, z: usize) -> Result<ArrayView3<'_, T>> {
+        self.orthogonal_slice(Axis(0), z)
+    }
+
+    /// View the slice at row `y`, in `(z, x, c)` order.
+    ///
+    /// Returns [`Error::OutOfBounds`] if `y` is outside of the volume.
+    pub fn slice_y(&self, y: usize) -> Result<ArrayView3<'_, T>> {
+        self.orthogonal_slice(Axis(1), y)
+    }
+
+    /// View the slice at column `x`, in `(z, y, c)` order.
+    ///
+    /// Returns [`Error::OutOfBounds`] if `x` is outside of the volume.
+    pub fn slice_x(&self, x: usize) -> Result<ArrayView3<'_, T>> {
+        self.orthogonal_slice(Axis(2), x)
+    }
+
+    /// Mutably view the slice at depth `z`, in `(y, x, c)` order.
+    ///
+    /// Returns [`Error::OutOfBounds`] if `z` is outside of the volume.
+    pub fn slice_z_mut(&mut self, z: usize) -> Result<ArrayViewMut3<'_, T>> {
+        if z >= self.depth() {
+            return Err(Error::OutOfBounds);
+        }
+        Ok(self.array.index_axis_mut(Axis(0), z))
+    }
+
+    /// Extract the values of the provided `(x, y)` pixels over all slices.
+    ///
+    /// See [`pixel_timeseries`] for details.
+    pub fn pixel_timeseries(&self, points: &[(u32, u32)]) -> Result<Array3<T>>
+    where
+        T: Clone,
+    {
+        pixel_timeseries(self.as_ndarray(), points)
+    }
+
+    fn orthogonal_slice(&self, axis: Axis, index: usize) -> Result<ArrayView3<'_, T>> {
+        if index >= self.array.len_of(axis) {
+            return Err(Error::OutOfBounds);
+        }
+        Ok(self.array.index_axis(axis, index))
+    }
+}
+
+impl<T> Volume<T>
+where
+    T: Clone + PartialOrd,
+{
+    /// Calculate the maximum intensity projection along the depth axis.
+    ///
+    /// The result is in `(y, x, c)` order. This is the same maximum as [`Projection::Max`],
+    /// see [`project_max`].
+    pub fn max_intensity_projection(&self) -> Array3<T> {
+        project_max(self.as_ndarray(), Axis(0))
+    }
+}
+
+impl<T> Volume<T>
+where
+    T: FloatCore,
+{
+    /// Project the volume along the provided axis.
+    ///
+    /// See [`project`] for details.
+    pub fn project(&self, axis: Axis, projection: Projection) -> Array3<T> {
+        project(self.as_ndarray(), axis, projection)
+    }
+
+    /// Average the provided regions over all slices.
+    ///
+    /// See [`region_timeseries`] for details.
+    pub fn region_timeseries(&self, regions: &[(u32, u32, u32, u32)]) -> Result<Array3<T>> {
+        region_timeseries(self.as_ndarray(), regions)
+    }
+}
+
+#[cfg(feature = "image")]
+impl<T> Volume<T>
+where
+    T: Clone + Copy,
+{
+    /// Stack the provided images into a volume.
+    ///
+    /// All images need to share the same dimensions, otherwise an [`Error::NDArray`] is returned.
+    ///
+    /// This copies the data of every image.
+    pub fn from_images<P>(images: &[ImageBuffer<P, Vec<T>>]) -> Result<Self>
+    where
+        P: Pixel<Subpixel = T>,
+    {
+        let views: Vec<_> = images.iter().map(|image| image.as_ndarray()).collect();
+        if views.is_empty() {
+            return Err(Error::EmptyVolume);
+        }
+        Ok(Self {
+            array: ndarray::stack(Axis(0), &views)?,
+        })
+    }
+
+    /// Copy the slice at depth `z` into an ImageBuffer.
+    ///
+    /// Returns [`Error::OutOfBounds`] if `z` is outside of the volume and
+    /// [`Error::ChannelMismatch`] if the channel count does not match the pixel.
+    pub fn slice_to_image<P>(&self, z: usize) -> Result<ImageBuffer<P, Vec<T>>>
+    where
+        P: Pixel<Subpixel = T>,
+    {
+        let slice = self.slice_z(z)?;
+        ImageBuffer::from_ndarray(slice.as_standard_layout().into_owned())
+    }
+}
+
+#[cfg(feature = "image")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba32FImage;
+    use rstest::*;
+
+    #[test]
+    fn test_orthogonal_slices() {
+        let volume = create_test_volume(3, 4, 5, 2);
+
+        let slice = volume.slice_z(1).unwrap();
+        assert_eq!(slice.shape(), &[4, 5, 2]);
+        assert_eq!(slice[[2, 3, 1]], volume.as_ndarray()[[1, 2, 3, 1]]);
+
+        let slice = volume.slice_y(2).unwrap();
+        assert_eq!(slice.shape(), &[3, 5, 2]);
+        assert_eq!(slice[[1, 3, 1]], volume.as_ndarray()[[1, 2, 3, 1]]);
+
+        let slice = volume.slice_x(3).unwrap();
+        assert_eq!(slice.shape(), &[3, 4, 2]);
+        assert_eq!(slice[[1, 2, 1]], volume.as_ndarray()[[1, 2, 3, 1]]);
+    }
+
+    #[test]
+    fn test_slices_out_of_bounds() {
+        let mut volume = create_test_volume(3, 4, 5, 2);
+
+        assert_eq!(volume.slice_z(3).err().unwrap(), Error::OutOfBounds);
+        assert_eq!(volume.slice_y(4).err().unwrap(), Error::OutOfBounds);
+        assert_eq!(volume.slice_x(5).err().unwrap(), Error::OutOfBounds);
+        assert_eq!(volume.slice_z_mut(3).err().unwrap(), Error::OutOfBounds);
+    }
+
+    #[test]
+    fn test_empty_volume() {
+        let result = Volume::<f32>::new(Array4::zeros((0, 4, 5, 2)))
+            .err()
+            .unwrap();
+
+        assert_eq!(result, Error::EmptyVolume);
+    }
+
+    #[test]
+    fn test_max_intensity_projection() {
+        let mut array = Array4::<f32>::zeros((3, 2, 2, 1));
+        array[[0, 0, 0, 0]] = 5.0;
+        array[[1, 0, 0, 0]] = 2.0;
+        array[[2, 1, 1, 0]] = 7.0;
+        array[[1, 0, 1, 0]] = -1.0;
+        let volume = Volume::new(array).unwrap();
+
+        let projection = volume.max_intensity_projection();
+
+        assert_eq!(
+            projection,
+            Array3::from_shape_vec((2, 2, 1), vec![5.0, 0.0, 0.0, 7.0]).unwrap()
+        );
+    }
+
+    #[rstest]
+    #[case(Projection::Max, [3.0, f32::NAN, f32::NAN])]
+    #[case(Projection::Sum, [6.0, f32::NAN, f32::NAN])]
+    #[case(Projection::Mean, [2.0, f32::NAN, f32::NAN])]
+    #[case(Projection::NanMax, [3.0, 4.0, f32::NAN])]
+    #[case(Projection::NanSum, [6.0, 6.0, 0.0])]
+    #[case(Projection::NanMean, [2.0, 3.0, f32::NAN])]
+    fn test_project(#[case] projection: Projection, #[case] expected: [f32; 3]) {
+        let nan = f32::NAN;
+        let data = vec![1.0, 2.0, nan, 2.0, nan, nan, 3.0, 4.0, nan];
+        let stack = Array4::from_shape_vec((3, 1, 3, 1), data).unwrap();
+
+        let result = project(stack.view(), Axis(0), projection);
+
+        assert_eq!(result.shape(), &[1, 3, 1]);
+        for (value, expected) in result.iter().zip(expected) {
+            if expected.is_nan() {
+                assert!(value.is_nan());
+            } else {
+                assert_eq!(*value, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_project_max_nan_order() {
+        let nan = f32::NAN;
+        let data = vec![nan, 1.0, 1.0, 1.0, nan, 2.0];
+        let stack = Array4::from_shape_vec((2, 1, 3, 1), data).unwrap();
+
+        let result = project_max(stack.view(), Axis(0));
+
+        assert!(result[[0, 0, 0]].is_nan());
+        assert!(result[[0, 1, 0]].is_nan());
+        assert_eq!(result[[0, 2, 0]], 2.0);
+        let projected = project(stack.view(), Axis(0), Projection::Max);
+        assert!(projected[[0, 0, 0]].is_nan() && projected[[0, 1, 0]].is_nan());
+    }
+
+    #[test]
+    fn test_project_integers() {
+        let stack =
+            Array4::from_shape_fn((3, 2, 4, 1), |(z, y, x, _)| (z * 30000 + y * 10 + x) as u16);
+
+        let max = project_max(stack.view(), Axis(0));
+        let sum = project_sum::<u16, u64>(stack.view(), Axis(0));
+
+        assert_eq!(max[[1, 3, 0]], 60013);
+        assert_eq!(sum[[1, 3, 0]], 90039);
+        assert_eq!(sum.shape(), &[2, 4, 1]);
+    }
+
+    #[test]
+    fn test_project_volume_along_axis() {
+        let volume = create_test_volume(3, 4, 5, 2);
+
+        let result = volume.project(Axis(2), Projection::Sum);
+
+        assert_eq!(result.shape(), &[3, 4, 2]);
+        let expected: f32 = (0..5).map(|x| volume.as_ndarray()[[1, 2, x, 1]]).sum();
+        assert_eq!(result[[1, 2, 1]], expected);
+    }
+
+    #[test]
+    fn test_pixel_timeseries() {
+        let volume = create_test_volume(3, 4, 5, 2);
+
+        let result = volume.pixel_timeseries(&[(1, 2), (4, 3)]).unwrap();
+
+        assert_eq!(result.shape(), &[2, 3, 2]);
+        for z in 0..3 {
+            for c in 0..2 {
+                assert_eq!(result[[0, z, c]], volume.as_ndarray()[[z, 2, 1, c]]);
+                assert_eq!(result[[1, z, c]], volume.as_ndarray()[[z, 3, 4, c]]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_region_timeseries() {
+        let volume = create_test_volume(3, 4, 5, 2);
+
+        let result = volume
+            .region_timeseries(&[(1, 1, 2, 2), (0, 0, 5, 4)])
+            .unwrap();
+
+        assert_eq!(result.shape(), &[2, 3, 2]);
+        let array = volume.as_ndarray();
+        for z in 0..3 {
+            for c in 0..2 {
+                let expected = array.slice(s![z, 1..3, 1..3, c]).mean().unwrap();
+                assert_eq!(result[[0, z, c]], expected);
+                let expected = array.slice(s![z, .., .., c]).mean().unwrap();
+                assert_eq!(result[[1, z, c]], expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_timeseries_out_of_bounds() {
+        let volume = create_test_volume(3, 4, 5, 2);
+
+        let result = volume.pixel_timeseries(&[(0, 0), (5, 0)]).err().unwrap();
+        assert_eq!(result, Error::OutOfBounds);
+
+        let result = volume.region_timeseries(&[(1, 1, 4, 4)]).err().unwrap();
+        assert_eq!(result, Error::OutOfBounds);
+    }
+
+    #[test]
+    fn test_images_round_trip() {
+        let images: Vec<_> = (0..3)
+            .map(|z| Rgba32FImage::from_pixel(5, 4, image::Rgba([z as f32; 4])))
+            .collect();
+
+        let volume = Volume::from_images(&images).unwrap();
+
+        assert_eq!(volume.depth(), 3);
+        assert_eq!(volume.height(), 4);
+        assert_eq!(volume.width(), 5);
+        assert_eq!(volume.channels(), 4);
+        for (z, image) in images.iter().enumerate() {
+            let result: Rgba32FImage = volume.slice_to_image(z).unwrap();
+            assert_eq!(&result, image);
+        }
+    }
+
+    #[test]
+    fn test_from_images_with_mismatching_dimensions() {
+        let images = vec![Rgba32FImage::new(5, 4), Rgba32FImage::new(4, 5)];
+
+        let result = Volume::from_images(&images).err().unwrap();
+
+        assert!(matches!(result, Error::NDArray(_)));
+    }
+
+    fn create_test_volume(
+        depth: usize,
+        height: usize,
+        width: usize,
+        channels: usize,
+    ) -> Volume<f32> {
+        let total_elements = depth * height * width * channels;
+        let data = (0..total_elements).map(|x| (x + 1) as f32).collect();
+        Volume::new(Array4::from_shape_vec((depth, height, width, channels), data).unwrap())
+            .unwrap()
+    }
+}